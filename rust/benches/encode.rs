@@ -0,0 +1,131 @@
+//! Encoding benchmark (using criterion)
+//!
+//! Compares encoding a deeply nested message by back-patching length prefixes
+//! with encoding it using lengths precomputed into a `LengthTable`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use heapless::consts::U64;
+use veriform::{encoder::LengthTable, Message};
+
+/// Size of the buffer messages are encoded into
+const BUFFER_SIZE: usize = 8192;
+
+/// Number of elements in the sequence in the innermost message
+const LEAF_ELEMENTS: usize = 64;
+
+/// Number of entries in the length table
+const TABLE_SIZE: usize = 128;
+
+/// Define a message containing a few integers and another nested message
+macro_rules! nested_message {
+    ($name:ident, $inner:ty) => {
+        #[derive(Message)]
+        pub struct $name {
+            #[field(tag = 0, wire_type = "uint64")]
+            pub a: u64,
+
+            #[field(tag = 1, wire_type = "uint64")]
+            pub b: u64,
+
+            #[field(tag = 2, wire_type = "sint64")]
+            pub c: i64,
+
+            #[field(tag = 3, wire_type = "message")]
+            pub inner: $inner,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    a: u64::MAX,
+                    b: 1 << 32,
+                    c: i64::MIN,
+                    inner: Default::default(),
+                }
+            }
+        }
+    };
+}
+
+/// Element of the sequence in the innermost message
+#[derive(Message, Debug)]
+pub enum Element {
+    #[field(tag = 0, wire_type = "bytes", size = 32)]
+    Bytes([u8; 32]),
+}
+
+/// Innermost message, containing a sequence of messages
+#[derive(Message)]
+pub struct Leaf {
+    #[field(tag = 0, wire_type = "sequence", max = 64)]
+    pub elements: heapless::Vec<Element, U64>,
+}
+
+impl Default for Leaf {
+    fn default() -> Self {
+        let mut elements = heapless::Vec::new();
+
+        for n in 0..LEAF_ELEMENTS {
+            elements.push(Element::Bytes([n as u8; 32])).unwrap();
+        }
+
+        Self { elements }
+    }
+}
+
+nested_message!(Depth2, Leaf);
+nested_message!(Depth3, Depth2);
+nested_message!(Depth4, Depth3);
+nested_message!(Depth5, Depth4);
+nested_message!(Depth6, Depth5);
+nested_message!(Depth7, Depth6);
+nested_message!(Depth8, Depth7);
+nested_message!(Depth9, Depth8);
+nested_message!(Depth10, Depth9);
+nested_message!(Depth11, Depth10);
+nested_message!(Depth12, Depth11);
+nested_message!(Depth13, Depth12);
+nested_message!(Depth14, Depth13);
+nested_message!(Depth15, Depth14);
+nested_message!(Depth16, Depth15);
+
+fn bench_message<M: Message>(c: &mut Criterion, name: &str, message: &M) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(message.encoded_len() as u64));
+
+    let mut buffer = [0u8; BUFFER_SIZE];
+
+    // Compute the length (e.g. to size the buffer), then encode the message,
+    // back-patching the length prefixes of nested messages
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            let len = message.encoded_len();
+            message.encode(&mut buffer[..len]).unwrap();
+        });
+    });
+
+    // Compute the length and the lengths of nested messages in one pass,
+    // then encode the message with the precomputed lengths
+    let mut entries = [0usize; TABLE_SIZE];
+
+    group.bench_function("encode_with", |b| {
+        b.iter(|| {
+            let mut lengths = LengthTable::new(&mut entries);
+            let len = message.encoded_len_with(&mut lengths).unwrap();
+            message
+                .encode_with(&mut buffer[..len], &mut lengths)
+                .unwrap();
+        });
+    });
+
+    group.finish();
+}
+
+fn bench(c: &mut Criterion) {
+    bench_message(c, "depth 4", &Depth4::default());
+    bench_message(c, "depth 8", &Depth8::default());
+    bench_message(c, "depth 16", &Depth16::default());
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
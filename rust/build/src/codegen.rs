@@ -0,0 +1,271 @@
+//! Rust code generation
+
+use crate::{
+    error::Error,
+    schema::{Builtin, Definition, DefinitionKind, Field, Schema, Type},
+};
+
+/// Header prepended to generated code
+const HEADER: &str = "// Generated by veriform_build. Do not edit.\n";
+
+/// Attributes of generated types
+const DERIVE: &str = "#[derive(Clone, Debug, Eq, PartialEq, veriform::Message)]";
+
+/// Rust keywords which can be used as identifiers in schemas
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Keywords which can't be used as raw identifiers
+const RESERVED: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Generate Rust code for the types defined in the given schema
+pub fn generate(schema: &Schema) -> Result<String, Error> {
+    let mut out = String::from(HEADER);
+
+    for definition in &schema.definitions {
+        out.push('\n');
+        docs(&definition.docs, "", &mut out);
+        out.push_str(DERIVE);
+        out.push('\n');
+
+        match definition.kind {
+            DefinitionKind::Message => message(definition, &mut out)?,
+            DefinitionKind::Enum => enumeration(definition, &mut out)?,
+        }
+    }
+
+    Ok(out)
+}
+
+/// Generate a `struct` for a message
+fn message(definition: &Definition, out: &mut String) -> Result<(), Error> {
+    if definition.fields.is_empty() {
+        out.push_str(&format!("pub struct {} {{}}\n", definition.name));
+        return Ok(());
+    }
+
+    out.push_str(&format!("pub struct {} {{\n", definition.name));
+
+    for (i, field) in definition.fields.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+
+        let ty = rust_type(definition, field)?;
+        docs(&field.docs, "    ", out);
+        out.push_str(&format!("    {}\n", attribute(field)));
+        out.push_str(&format!("    pub {}: {},\n", field_name(&field.name), ty));
+    }
+
+    out.push_str("}\n");
+    Ok(())
+}
+
+/// Generate an `enum`
+fn enumeration(definition: &Definition, out: &mut String) -> Result<(), Error> {
+    if definition.fields.is_empty() {
+        return Err(Error::Unsupported {
+            name: definition.name.clone(),
+            feature: "enums without variants",
+        });
+    }
+
+    out.push_str(&format!("pub enum {} {{\n", definition.name));
+
+    for (i, field) in definition.fields.iter().enumerate() {
+        if let Type::Sequence(_) = field.ty {
+            return Err(unsupported(
+                definition,
+                field,
+                "enum variants containing sequences",
+            ));
+        }
+
+        if i > 0 {
+            out.push('\n');
+        }
+
+        let ty = rust_type(definition, field)?;
+        docs(&field.docs, "    ", out);
+        out.push_str(&format!("    {}\n", attribute(field)));
+        out.push_str(&format!("    {}({}),\n", variant_name(&field.name), ty));
+    }
+
+    out.push_str("}\n");
+    Ok(())
+}
+
+/// Generate doc comments with the given indentation
+fn docs(lines: &[String], indent: &str, out: &mut String) {
+    for line in lines {
+        if line.is_empty() {
+            out.push_str(&format!("{}///\n", indent));
+        } else {
+            out.push_str(&format!("{}/// {}\n", indent, line));
+        }
+    }
+}
+
+/// Generate the `#[field(...)]` attribute for a field
+fn attribute(field: &Field) -> String {
+    let mut attr = format!(
+        "#[field(tag = {}, wire_type = \"{}\"",
+        field.tag,
+        wire_type(&field.ty)
+    );
+
+    if field.critical {
+        attr.push_str(", critical = true");
+    }
+
+    for (name, value) in &[
+        ("size", field.options.size),
+        ("min", field.options.min),
+        ("max", field.options.max),
+    ] {
+        if let Some(value) = value {
+            attr.push_str(&format!(", {} = {}", name, value));
+        }
+    }
+
+    attr.push_str(")]");
+    attr
+}
+
+/// Get the name of the wire type used to encode a field
+fn wire_type(ty: &Type) -> &'static str {
+    match ty {
+        Type::Bool => "bool",
+        Type::UInt64 => "uint64",
+        Type::SInt64 => "sint64",
+        Type::Bytes => "bytes",
+        Type::String => "string",
+        Type::Sequence(_) => "sequence",
+        Type::Named(_) | Type::Builtin(_) => "message",
+    }
+}
+
+/// Get the Rust type of a field
+fn rust_type(definition: &Definition, field: &Field) -> Result<String, Error> {
+    let ty = match &field.ty {
+        Type::Bool => "bool".to_owned(),
+        Type::UInt64 => "u64".to_owned(),
+        Type::SInt64 => "i64".to_owned(),
+        Type::Bytes => match field.options.size {
+            Some(size) => format!("[u8; {}]", size),
+            None => "Vec<u8>".to_owned(),
+        },
+        Type::String => "String".to_owned(),
+        Type::Sequence(element_type) => match element_type.as_ref() {
+            Type::Named(_) | Type::Builtin(_) => {
+                format!("Vec<{}>", message_type(element_type))
+            }
+            _ => {
+                return Err(unsupported(
+                    definition,
+                    field,
+                    "sequences of types other than messages",
+                ))
+            }
+        },
+        Type::Named(_) | Type::Builtin(_) => message_type(&field.ty),
+    };
+
+    Ok(ty)
+}
+
+/// Get the Rust type of a message or builtin type
+fn message_type(ty: &Type) -> String {
+    match ty {
+        Type::Named(name) => name.clone(),
+        Type::Builtin(Builtin::Timestamp) => "veriform::builtins::Timestamp".to_owned(),
+        Type::Builtin(Builtin::Uuid) => "veriform::builtins::Uuid".to_owned(),
+        _ => unreachable!("not a message type: {}", ty),
+    }
+}
+
+/// Create an error for an unsupported field
+fn unsupported(definition: &Definition, field: &Field, feature: &'static str) -> Error {
+    Error::Unsupported {
+        name: format!("{}.{}", definition.name, field.name),
+        feature,
+    }
+}
+
+/// Convert a field name to a `snake_case` Rust identifier
+fn field_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map(char::is_ascii_lowercase) == Some(true);
+
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                result.push('_');
+            }
+        }
+
+        result.push(c.to_ascii_lowercase());
+    }
+
+    escape(result)
+}
+
+/// Convert a field name to a `CamelCase` enum variant name
+fn variant_name(name: &str) -> String {
+    let mut result = String::new();
+
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let mut chars = part.chars();
+
+        if let Some(first) = chars.next() {
+            result.push(first.to_ascii_uppercase());
+            result.extend(chars);
+        }
+    }
+
+    escape(result)
+}
+
+/// Escape identifiers which are Rust keywords
+fn escape(ident: String) -> String {
+    if RESERVED.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{}", ident)
+    } else {
+        ident
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field_name, variant_name};
+
+    #[test]
+    fn field_names() {
+        assert_eq!(field_name("created_at"), "created_at");
+        assert_eq!(field_name("createdAt"), "created_at");
+        assert_eq!(field_name("HTTPServer"), "http_server");
+        assert_eq!(field_name("sha256Digest"), "sha256_digest");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("self"), "self_");
+    }
+
+    #[test]
+    fn variant_names() {
+        assert_eq!(variant_name("password"), "Password");
+        assert_eq!(variant_name("public_key"), "PublicKey");
+        assert_eq!(variant_name("PublicKey"), "PublicKey");
+    }
+}
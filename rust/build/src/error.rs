@@ -0,0 +1,89 @@
+//! Error types
+
+use crate::schema;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// Error type
+#[derive(Debug)]
+pub enum Error {
+    /// I/O error reading a schema or writing generated code
+    Io {
+        /// path of the file being read or written
+        path: PathBuf,
+
+        /// underlying I/O error
+        error: io::Error,
+    },
+
+    /// `OUT_DIR` isn't set (i.e. not running in a build script) or a schema
+    /// path has no file name
+    OutDir,
+
+    /// error parsing a schema
+    Schema {
+        /// path of the schema
+        path: PathBuf,
+
+        /// underlying parse error
+        error: schema::Error,
+    },
+
+    /// schema uses a feature which isn't supported by the generated code
+    Unsupported {
+        /// name of the unsupported definition or field (`Definition.field`)
+        name: String,
+
+        /// description of the unsupported feature
+        feature: &'static str,
+    },
+}
+
+impl Error {
+    /// Create an I/O error for the given path
+    pub(crate) fn io(path: &Path, error: io::Error) -> Self {
+        Error::Io {
+            path: path.to_owned(),
+            error,
+        }
+    }
+
+    /// Create a schema parse error for the given path
+    pub(crate) fn schema(path: &Path, error: schema::Error) -> Self {
+        Error::Schema {
+            path: path.to_owned(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            Error::OutDir => f.write_str("no output directory (is OUT_DIR set?)"),
+            Error::Schema { path, error } => write!(
+                f,
+                "{}:{}: {}",
+                path.display(),
+                error.position(),
+                error.kind()
+            ),
+            Error::Unsupported { name, feature } => {
+                write!(f, "{}: {} are not supported", name, feature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { error, .. } => Some(error),
+            Error::Schema { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
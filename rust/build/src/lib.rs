@@ -0,0 +1,87 @@
+//! Generate Rust types for Veriform messages from schema files at build time.
+//!
+//! Schemas are written in the Veriform schema language (see the
+//! [`veriform_schema`] crate for the syntax) and stored in `.vf` files.
+//! For every `message` or `enum` they define, a Rust `struct` or `enum`
+//! which derives `veriform::Message` is generated.
+//!
+//! # Usage
+//!
+//! Add `veriform_build` to the `[build-dependencies]` of your crate, then
+//! compile your schemas from its `build.rs`:
+//!
+//! ```no_run
+//! // build.rs
+//! veriform_build::compile(&["schema/credentials.vf"]).unwrap();
+//! ```
+//!
+//! This writes the generated types to `credentials.rs` in `OUT_DIR`, which
+//! can then be included into a module:
+//!
+//! ```ignore
+//! pub mod credentials {
+//!     include!(concat!(env!("OUT_DIR"), "/credentials.rs"));
+//! }
+//! ```
+//!
+//! The generated code requires the `veriform_derive` feature of `veriform`
+//! (and `builtins` for types using `Timestamp` or `UUID`). Variable-sized
+//! `bytes`, `string` and `sequence` fields are represented as `Vec<u8>`,
+//! `String` and `Vec<T>` respectively.
+
+#![doc(html_root_url = "https://docs.rs/veriform_build/0.1.0")]
+#![forbid(unsafe_code)]
+#![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
+
+mod codegen;
+mod error;
+
+pub use crate::{codegen::generate, error::Error};
+pub use veriform_schema as schema;
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Compile the given schema files, writing the generated code for each into
+/// `OUT_DIR` as a `.rs` file with the same name as the schema
+pub fn compile(schemas: &[impl AsRef<Path>]) -> Result<(), Error> {
+    Config::default().compile(schemas)
+}
+
+/// Code generator configuration
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Config {
+    /// Directory to write generated code into (defaults to `OUT_DIR`)
+    pub out_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Compile the given schema files, writing the generated code for each
+    /// into the output directory as a `.rs` file with the same name as the
+    /// schema
+    pub fn compile(&self, schemas: &[impl AsRef<Path>]) -> Result<(), Error> {
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(Error::OutDir)?,
+        };
+
+        for path in schemas {
+            let path = path.as_ref();
+            println!("cargo:rerun-if-changed={}", path.display());
+
+            let input = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+            let schema = schema::parse(&input).map_err(|e| Error::schema(path, e))?;
+            let code = generate(&schema)?;
+
+            let mut out_path = out_dir.join(path.file_name().ok_or(Error::OutDir)?);
+            out_path.set_extension("rs");
+            fs::write(&out_path, code).map_err(|e| Error::io(&out_path, e))?;
+        }
+
+        Ok(())
+    }
+}
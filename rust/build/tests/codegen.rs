@@ -0,0 +1,78 @@
+//! Code generation tests
+
+use std::{env, fs};
+use veriform::{Decoder, Message};
+use veriform_build::{generate, schema, Config, Error};
+
+/// Example schema
+const EXAMPLE_SCHEMA: &str = include_str!("schemas/example.vf");
+
+/// Code generated from the example schema
+const EXAMPLE_CODE: &str = include_str!("schemas/example.rs");
+
+#[allow(dead_code)]
+mod example {
+    include!("schemas/example.rs");
+}
+
+use example::{Account, Credential, PublicKey};
+
+/// Create an example account
+fn example_account() -> Account {
+    Account {
+        account_id: 42,
+        balance: -42,
+        locked: false,
+        credentials: vec![
+            Credential::Password([1; 32]),
+            Credential::PublicKey(PublicKey {
+                id: veriform::builtins::Uuid::from_bytes([2; 16]),
+                r#type: "ed25519".to_owned(),
+                key_bytes: vec![3; 32],
+            }),
+            Credential::Unset(true),
+        ],
+    }
+}
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let schema = schema::parse(EXAMPLE_SCHEMA).unwrap();
+    assert_eq!(generate(&schema).unwrap(), EXAMPLE_CODE);
+}
+
+#[test]
+fn round_trip() {
+    let account = example_account();
+    let encoded = account.encode_vec().unwrap();
+
+    let mut decoder = Decoder::new();
+    let decoded = Account::decode(&mut decoder, &encoded).unwrap();
+    assert_eq!(account, decoded);
+}
+
+#[test]
+fn compile() {
+    let out_dir = env::temp_dir().join(format!("veriform_build-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+
+    let config = Config {
+        out_dir: Some(out_dir.clone()),
+    };
+
+    config.compile(&["tests/schemas/example.vf"]).unwrap();
+    let code = fs::read_to_string(out_dir.join("example.rs")).unwrap();
+    fs::remove_dir_all(&out_dir).unwrap();
+
+    assert_eq!(code, EXAMPLE_CODE);
+}
+
+#[test]
+fn unsupported_sequence() {
+    let schema = schema::parse("message Foo { bar[0]: sequence<uint64> }").unwrap();
+
+    match generate(&schema).unwrap_err() {
+        Error::Unsupported { name, .. } => assert_eq!(name, "Foo.bar"),
+        other => panic!("unexpected error: {}", other),
+    }
+}
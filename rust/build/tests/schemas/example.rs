@@ -0,0 +1,51 @@
+// Generated by veriform_build. Do not edit.
+
+/// Credential used to authenticate a user
+#[derive(Clone, Debug, Eq, PartialEq, veriform::Message)]
+pub enum Credential {
+    /// Password (hashed)
+    #[field(tag = 0, wire_type = "bytes", critical = true, size = 32)]
+    Password([u8; 32]),
+
+    /// Public key
+    #[field(tag = 1, wire_type = "message")]
+    PublicKey(PublicKey),
+
+    /// Placeholder for users who haven't set a credential yet
+    #[field(tag = 2, wire_type = "bool")]
+    Unset(bool),
+}
+
+/// Public key
+#[derive(Clone, Debug, Eq, PartialEq, veriform::Message)]
+pub struct PublicKey {
+    #[field(tag = 0, wire_type = "message", critical = true)]
+    pub id: veriform::builtins::Uuid,
+
+    #[field(tag = 1, wire_type = "string", min = 1, max = 16)]
+    pub r#type: String,
+
+    #[field(tag = 2, wire_type = "bytes", critical = true)]
+    pub key_bytes: Vec<u8>,
+}
+
+/// User account
+///
+/// Contains all of a user's credentials.
+#[derive(Clone, Debug, Eq, PartialEq, veriform::Message)]
+pub struct Account {
+    #[field(tag = 0, wire_type = "uint64")]
+    pub account_id: u64,
+
+    #[field(tag = 1, wire_type = "sint64")]
+    pub balance: i64,
+
+    #[field(tag = 2, wire_type = "bool")]
+    pub locked: bool,
+
+    #[field(tag = 3, wire_type = "sequence", max = 8)]
+    pub credentials: Vec<Credential>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, veriform::Message)]
+pub struct Empty {}
@@ -0,0 +1,58 @@
+//! Subcommands of the `veriform` tool
+
+mod convert;
+mod decode;
+mod explain;
+mod hash;
+mod validate;
+mod vectors;
+
+pub use self::{
+    convert::ConvertCommand, decode::DecodeCommand, explain::ExplainCommand, hash::HashCommand,
+    validate::ValidateCommand, vectors::VectorsCommand,
+};
+
+use crate::error::Error;
+use gumdrop::Options;
+
+/// Subcommands
+#[derive(Debug, Options)]
+pub enum Command {
+    /// Dump the fields of a message
+    #[options(help = "dump the fields of a message")]
+    Decode(DecodeCommand),
+
+    /// Annotated hexdump of the wire encoding of a message
+    #[options(help = "annotated hexdump of the wire encoding of a message")]
+    Explain(ExplainCommand),
+
+    /// Compute the Verihash digest of a message
+    #[options(help = "compute the Verihash digest of a message")]
+    Hash(HashCommand),
+
+    /// Check that a message conforms to the spec
+    #[options(help = "check that a message conforms to the spec")]
+    Validate(ValidateCommand),
+
+    /// Convert a message between binary, text and TJSON
+    #[options(help = "convert a message between binary, text and TJSON")]
+    Convert(ConvertCommand),
+
+    /// Run the shared test vectors
+    #[options(help = "run the shared test vectors")]
+    Vectors(VectorsCommand),
+}
+
+impl Command {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        match self {
+            Command::Decode(cmd) => cmd.run(),
+            Command::Explain(cmd) => cmd.run(),
+            Command::Hash(cmd) => cmd.run(),
+            Command::Validate(cmd) => cmd.run(),
+            Command::Convert(cmd) => cmd.run(),
+            Command::Vectors(cmd) => cmd.run(),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! `veriform convert`: convert messages between binary, text and TJSON
+
+use super::decode::hex;
+use crate::{error::Error, input};
+use gumdrop::Options;
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+use veriform::{text, tjson, value::Object, Decoder, Message};
+
+/// Convert a message between the binary encoding, text format and TJSON
+#[derive(Debug, Options)]
+pub struct ConvertCommand {
+    /// Message to convert (stdin if omitted)
+    #[options(free, help = "message to convert (stdin if omitted)")]
+    input: Option<PathBuf>,
+
+    /// Format of the input
+    #[options(
+        help = "input format: binary, hex, text or tjson (default: binary)",
+        meta = "FORMAT",
+        default = "binary"
+    )]
+    from: Format,
+
+    /// Format of the output
+    #[options(
+        help = "output format: binary, hex, text or tjson (default: tjson)",
+        meta = "FORMAT",
+        default = "tjson"
+    )]
+    to: Format,
+
+    /// File to write the output to
+    #[options(help = "output file (stdout if omitted)", meta = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Don't pretty print TJSON
+    #[options(no_short, help = "don't pretty print TJSON")]
+    compact: bool,
+
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+}
+
+impl ConvertCommand {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        let input = input::read(self.input.as_deref())?;
+
+        let object: Object = match self.from {
+            Format::Binary => Object::decode(&mut Decoder::new(), &input)?,
+            Format::Hex => Object::decode(&mut Decoder::new(), &input::decode_hex(&input)?)?,
+            Format::Text => text::from_str(&utf8(input)?)?,
+            Format::Tjson => tjson::from_str(&utf8(input)?)?,
+        };
+
+        let mut output = match self.to {
+            Format::Binary => object.encode_vec()?,
+            Format::Hex => hex(&object.encode_vec()?).into_bytes(),
+            Format::Text => text::to_string(&object)?.into_bytes(),
+            Format::Tjson if self.compact => tjson::to_string(&object)?.into_bytes(),
+            Format::Tjson => tjson::to_string_pretty(&object)?.into_bytes(),
+        };
+
+        // The text format already ends each field with a newline
+        if self.to != Format::Binary && self.to != Format::Text {
+            output.push(b'\n');
+        }
+
+        match &self.output {
+            Some(path) => fs::write(path, output).map_err(|e| Error::io(Some(path.clone()), e)),
+            None => io::stdout()
+                .write_all(&output)
+                .map_err(|e| Error::io(None, e)),
+        }
+    }
+}
+
+/// Interpret input as UTF-8
+fn utf8(input: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(input).map_err(|e| {
+        let valid_up_to = e.utf8_error().valid_up_to();
+        Error::Veriform(veriform::error::Kind::Utf8 { valid_up_to }.into())
+    })
+}
+
+/// Message formats
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    /// Binary Veriform encoding
+    Binary,
+
+    /// Hex-encoded binary Veriform encoding
+    Hex,
+
+    /// Human-readable text format
+    Text,
+
+    /// TJSON (i.e. JSON with type-tagged member names)
+    Tjson,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "binary" => Ok(Format::Binary),
+            "hex" => Ok(Format::Hex),
+            "text" => Ok(Format::Text),
+            "tjson" | "json" => Ok(Format::Tjson),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
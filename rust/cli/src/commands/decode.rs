@@ -0,0 +1,135 @@
+//! `veriform decode`: schema-less dump of the fields of a message
+
+use crate::{error::Error, input};
+use gumdrop::Options;
+use std::{fmt::Write, path::PathBuf};
+use veriform::{
+    field::WireType,
+    value::{Object, Value},
+    Decoder, Message,
+};
+
+/// Dump the fields of a message (without needing its schema)
+#[derive(Debug, Options)]
+pub struct DecodeCommand {
+    /// Message to decode (stdin if omitted)
+    #[options(free, help = "message to decode (stdin if omitted)")]
+    input: Option<PathBuf>,
+
+    /// Input is hex encoded
+    #[options(short = "x", help = "input is hex encoded")]
+    hex: bool,
+
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+}
+
+impl DecodeCommand {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        let message = input::read_message(self.input.as_ref(), self.hex)?;
+        let object = Object::decode(&mut Decoder::new(), &message)?;
+        println!("{}", dump(&object));
+        Ok(())
+    }
+}
+
+/// Dump a message as a tree, one field per line, e.g.
+///
+/// ```text
+/// {
+///   0!: uint64 = 42
+///   1: string = "hello"
+///   2: sequence<message> [
+///     {
+///       0: bytes(2) = 0a0b
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Critical fields are marked with `!`.
+pub fn dump(object: &Object) -> String {
+    let mut out = String::new();
+    dump_object(object, 0, &mut out);
+    out
+}
+
+/// Dump a message at the given indentation level
+fn dump_object(object: &Object, depth: usize, out: &mut String) {
+    out.push('{');
+
+    for (tag, field) in object {
+        newline(depth + 1, out);
+        let critical = if field.critical { "!" } else { "" };
+        write!(out, "{}{}: ", tag, critical).unwrap();
+        dump_value(&field.value, depth + 1, out);
+    }
+
+    if !object.is_empty() {
+        newline(depth, out);
+    }
+
+    out.push('}');
+}
+
+/// Dump a value at the given indentation level
+fn dump_value(value: &Value, depth: usize, out: &mut String) {
+    match value {
+        Value::Bool(value) => write!(out, "bool = {}", value).unwrap(),
+        Value::UInt64(value) => write!(out, "uint64 = {}", value).unwrap(),
+        Value::SInt64(value) => write!(out, "sint64 = {}", value).unwrap(),
+        Value::Bytes(bytes) => write!(out, "bytes({}) = {}", bytes.len(), hex(bytes)).unwrap(),
+        Value::String(string) => write!(out, "string = {:?}", string).unwrap(),
+        Value::Message(object) => {
+            out.push_str("message ");
+            dump_object(object, depth, out);
+        }
+        Value::Sequence(seq) => {
+            write!(out, "sequence<{}> [", type_name(seq.wire_type())).unwrap();
+
+            for element in seq {
+                newline(depth + 1, out);
+
+                match element {
+                    Value::Message(object) => dump_object(object, depth + 1, out),
+                    other => dump_value(other, depth + 1, out),
+                }
+            }
+
+            if !seq.is_empty() {
+                newline(depth, out);
+            }
+
+            out.push(']');
+        }
+    }
+}
+
+/// Start a new line at the given indentation level
+fn newline(depth: usize, out: &mut String) {
+    out.push('\n');
+
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+/// Get the name of a wire type as used in schemas
+pub fn type_name(wire_type: WireType) -> &'static str {
+    match wire_type {
+        WireType::False | WireType::True => "bool",
+        WireType::UInt64 => "uint64",
+        WireType::SInt64 => "sint64",
+        WireType::Bytes => "bytes",
+        WireType::String => "string",
+        WireType::Message => "message",
+        WireType::Sequence => "sequence",
+    }
+}
+
+/// Encode bytes as lower-case hex
+pub fn hex(bytes: &[u8]) -> String {
+    String::from_utf8(subtle_encoding::hex::encode(bytes)).unwrap()
+}
@@ -0,0 +1,93 @@
+//! `veriform explain`: annotated hexdump of the wire encoding of a message
+
+use super::decode::hex;
+use crate::{error::Error, input};
+use gumdrop::Options;
+use serde_json::json;
+use std::path::PathBuf;
+use veriform::decoder::explain::{Entry, Explain, Item};
+
+/// Explain how each byte of a message is interpreted
+#[derive(Debug, Options)]
+pub struct ExplainCommand {
+    /// Message to explain (stdin if omitted)
+    #[options(free, help = "message to explain (stdin if omitted)")]
+    input: Option<PathBuf>,
+
+    /// Input is hex encoded
+    #[options(short = "x", help = "input is hex encoded")]
+    hex: bool,
+
+    /// Output JSON (one object per line) instead of an annotated hexdump
+    #[options(short = "j", help = "output JSON (one object per line)")]
+    json: bool,
+
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+}
+
+impl ExplainCommand {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        let message = input::read_message(self.input.as_ref(), self.hex)?;
+
+        for entry in Explain::<sha2::Sha256>::new(&message) {
+            let entry = entry?;
+
+            if self.json {
+                println!("{}", to_json(&entry));
+            } else {
+                println!("{}", entry);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Serialize an entry as JSON
+fn to_json(entry: &Entry<'_>) -> serde_json::Value {
+    let mut value = json!({
+        "start": entry.range.start,
+        "end": entry.range.end,
+        "depth": entry.depth,
+        "bytes": hex(entry.bytes),
+    });
+
+    let item = match entry.item {
+        Item::FieldHeader(header) => json!({
+            "type": "field_header",
+            "tag": header.tag,
+            "critical": header.critical,
+            "wire_type": header.wire_type.to_string(),
+        }),
+        Item::UInt64(n) => json!({ "type": "uint64", "value": n.to_string() }),
+        Item::SInt64(n) => json!({ "type": "sint64", "value": n.to_string() }),
+        Item::LengthPrefix { wire_type, length } => json!({
+            "type": "length_prefix",
+            "wire_type": wire_type.to_string(),
+            "length": length,
+        }),
+        Item::SequenceHeader { wire_type, length } => json!({
+            "type": "sequence_header",
+            "wire_type": wire_type.to_string(),
+            "length": length,
+        }),
+        Item::ValueChunk {
+            wire_type,
+            length,
+            remaining,
+        } => json!({
+            "type": "value_chunk",
+            "wire_type": wire_type.to_string(),
+            "length": length,
+            "remaining": remaining,
+        }),
+        Item::MessageEnd => json!({ "type": "message_end" }),
+        Item::SequenceEnd => json!({ "type": "sequence_end" }),
+    };
+
+    value["item"] = item;
+    value
+}
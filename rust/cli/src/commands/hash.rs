@@ -0,0 +1,102 @@
+//! `veriform hash`: compute the Verihash digest of a message
+
+use super::decode::hex;
+use crate::{error::Error, input};
+use gumdrop::Options;
+use std::{path::PathBuf, str::FromStr};
+use veriform::{decoder::Decoder, digest::Digest, value::Object, Message, VerihashVersion};
+
+/// Compute the Verihash digest of a message
+#[derive(Debug, Options)]
+pub struct HashCommand {
+    /// Message to hash (stdin if omitted)
+    #[options(free, help = "message to hash (stdin if omitted)")]
+    input: Option<PathBuf>,
+
+    /// Input is hex encoded
+    #[options(short = "x", help = "input is hex encoded")]
+    hex: bool,
+
+    /// Hash algorithm
+    #[options(
+        help = "hash algorithm: sha256, sha384 or sha512 (default: sha256)",
+        meta = "ALG",
+        default = "sha256"
+    )]
+    algorithm: Algorithm,
+
+    /// Version of Verihash
+    #[options(
+        short = "V",
+        help = "Verihash version: v0 or v1 (default: v0)",
+        meta = "VERSION",
+        parse(try_from_str = "parse_version"),
+        default = "v0"
+    )]
+    verihash_version: VerihashVersion,
+
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+}
+
+impl HashCommand {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        let message = input::read_message(self.input.as_ref(), self.hex)?;
+
+        let digest = match self.algorithm {
+            Algorithm::Sha256 => digest::<sha2::Sha256>(&message, self.verihash_version)?,
+            Algorithm::Sha384 => digest::<sha2::Sha384>(&message, self.verihash_version)?,
+            Algorithm::Sha512 => digest::<sha2::Sha512>(&message, self.verihash_version)?,
+        };
+
+        println!("{}", hex(&digest));
+        Ok(())
+    }
+}
+
+/// Compute the digest of a message while decoding it
+fn digest<D: Digest>(message: &[u8], version: VerihashVersion) -> Result<Vec<u8>, Error> {
+    let mut decoder = Decoder::<D>::with_verihash_version(version);
+    Object::decode(&mut decoder, message)?;
+
+    let mut digest = vec![0u8; D::output_size()];
+    decoder.fill_digest(&mut digest)?;
+    Ok(digest)
+}
+
+/// Hash algorithms supported by Verihash
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Algorithm {
+    /// SHA-256
+    Sha256,
+
+    /// SHA-384
+    Sha384,
+
+    /// SHA-512
+    Sha512,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha256" => Ok(Algorithm::Sha256),
+            "sha384" => Ok(Algorithm::Sha384),
+            "sha512" => Ok(Algorithm::Sha512),
+            _ => Err(format!("unsupported hash algorithm: {}", s)),
+        }
+    }
+}
+
+/// Parse a Verihash version
+pub fn parse_version(s: &str) -> Result<VerihashVersion, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "v0" | "0" => Ok(VerihashVersion::V0),
+        "v1" | "1" => Ok(VerihashVersion::V1),
+        _ => Err(format!("unknown Verihash version: {}", s)),
+    }
+}
@@ -0,0 +1,100 @@
+//! `veriform validate`: check that a message conforms to the spec
+
+use crate::{error::Error, input};
+use gumdrop::Options;
+use std::path::PathBuf;
+use veriform::{
+    value::{Object, Value},
+    Decoder, Message,
+};
+
+/// Check that a message conforms to the rules of the Veriform spec
+#[derive(Debug, Options)]
+pub struct ValidateCommand {
+    /// Message to validate (stdin if omitted)
+    #[options(free, help = "message to validate (stdin if omitted)")]
+    input: Option<PathBuf>,
+
+    /// Input is hex encoded
+    #[options(short = "x", help = "input is hex encoded")]
+    hex: bool,
+
+    /// Reject critical fields, as a parser which doesn't know the schema of
+    /// the message must
+    #[options(
+        short = "c",
+        help = "reject critical fields (as parsers must for unknown fields)"
+    )]
+    no_critical: bool,
+
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+}
+
+impl ValidateCommand {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        let message = input::read_message(self.input.as_ref(), self.hex)?;
+        validate(&message, self.no_critical)?;
+        println!("ok");
+        Ok(())
+    }
+}
+
+/// Validate a message.
+///
+/// Decoding enforces the rules of the spec (ascending and unique tags,
+/// minimally encoded integers, reserved bits, normalized UTF-8 strings, etc),
+/// and re-encoding the decoded message must reproduce it exactly.
+pub fn validate(message: &[u8], no_critical: bool) -> Result<(), Error> {
+    let object = Object::decode(&mut Decoder::new(), message)?;
+
+    if no_critical {
+        check_critical(&object, "")?;
+    }
+
+    let encoded = object.encode_vec()?;
+
+    if encoded != message {
+        let position = encoded
+            .iter()
+            .zip(message)
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| encoded.len().min(message.len()));
+
+        return Err(Error::NonCanonical { position });
+    }
+
+    Ok(())
+}
+
+/// Ensure a message (and all messages nested within it) has no critical
+/// fields
+fn check_critical(object: &Object, path: &str) -> Result<(), Error> {
+    for (tag, field) in object {
+        let path = if path.is_empty() {
+            tag.to_string()
+        } else {
+            format!("{}.{}", path, tag)
+        };
+
+        if field.critical {
+            return Err(Error::Critical { path });
+        }
+
+        match &field.value {
+            Value::Message(nested) => check_critical(nested, &path)?,
+            Value::Sequence(seq) => {
+                for (index, element) in seq.iter().enumerate() {
+                    if let Value::Message(nested) = element {
+                        check_critical(nested, &format!("{}.sequence[{}]", path, index))?;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
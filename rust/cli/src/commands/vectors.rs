@@ -0,0 +1,53 @@
+//! `veriform vectors`: run the test vectors shared across implementations
+
+use crate::{error::Error, vectors};
+use gumdrop::Options;
+use std::path::PathBuf;
+
+/// Run the test vectors shared across Veriform implementations
+#[derive(Debug, Options)]
+pub struct VectorsCommand {
+    /// Directory containing the vector files
+    #[options(free, help = "directory containing the vectors (default: vectors)")]
+    dir: Option<PathBuf>,
+
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+}
+
+impl VectorsCommand {
+    /// Run the command
+    pub fn run(&self) -> Result<(), Error> {
+        let dir = self.dir.clone().unwrap_or_else(|| PathBuf::from("vectors"));
+        let mut failed = 0;
+
+        for path in vectors::files(&dir)? {
+            let file = vectors::File::load(&path)?;
+            let mut file_failed = 0;
+
+            for example in file.examples() {
+                if let Err(failure) = example.check() {
+                    println!("FAILED {}", failure);
+                    file_failed += 1;
+                }
+            }
+
+            println!(
+                "{} ({}): {} passed, {} failed",
+                file.name(),
+                file.kind(),
+                file.examples().len() - file_failed,
+                file_failed
+            );
+
+            failed += file_failed;
+        }
+
+        if failed == 0 {
+            Ok(())
+        } else {
+            Err(Error::Vectors { failed })
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Error types
+
+use crate::vectors;
+use std::{fmt, io, path::PathBuf};
+
+/// Error type
+#[derive(Debug)]
+pub enum Error {
+    /// message contains a critical field, which parsers that don't know the
+    /// message's schema must reject
+    Critical {
+        /// path to the critical field
+        path: String,
+    },
+
+    /// input is not valid hex
+    Hex,
+
+    /// I/O error reading input or writing output
+    Io {
+        /// path of the file being read or written (`None` for stdio)
+        path: Option<PathBuf>,
+
+        /// underlying I/O error
+        error: io::Error,
+    },
+
+    /// re-encoding the decoded message doesn't reproduce the input
+    NonCanonical {
+        /// offset of the first byte which differs
+        position: usize,
+    },
+
+    /// vector file couldn't be loaded (e.g. it contains an unknown kind of
+    /// test vectors)
+    VectorFile(vectors::Failure),
+
+    /// test vectors failed
+    Vectors {
+        /// number of failed examples
+        failed: usize,
+    },
+
+    /// error decoding, encoding or converting a message
+    Veriform(veriform::Error),
+}
+
+impl Error {
+    /// Create an I/O error for the given path
+    pub fn io(path: Option<PathBuf>, error: io::Error) -> Self {
+        Error::Io { path, error }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Critical { path } => write!(f, "critical field in message: path={}", path),
+            Error::Hex => f.write_str("invalid hex input"),
+            Error::Io {
+                path: Some(path),
+                error,
+            } => write!(f, "{}: {}", path.display(), error),
+            Error::Io { path: None, error } => write!(f, "{}", error),
+            Error::NonCanonical { position } => write!(
+                f,
+                "re-encoded message differs from input: position={}",
+                position
+            ),
+            Error::VectorFile(failure) => write!(f, "{}", failure),
+            Error::Vectors { failed } => write!(f, "{} test vector(s) failed", failed),
+            Error::Veriform(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { error, .. } => Some(error),
+            Error::VectorFile(failure) => Some(failure),
+            Error::Veriform(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<veriform::Error> for Error {
+    fn from(error: veriform::Error) -> Error {
+        Error::Veriform(error)
+    }
+}
+
+impl From<vectors::Failure> for Error {
+    fn from(failure: vectors::Failure) -> Error {
+        Error::VectorFile(failure)
+    }
+}
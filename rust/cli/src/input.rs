@@ -0,0 +1,45 @@
+//! Reading input from files or stdin
+
+use crate::error::Error;
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Read the contents of the given file, or stdin if no path (or `-`) is given
+pub fn read(path: Option<&Path>) -> Result<Vec<u8>, Error> {
+    match path.filter(|path| *path != Path::new("-")) {
+        Some(path) => fs::read(path).map_err(|e| Error::io(Some(path.to_owned()), e)),
+        None => {
+            let mut input = Vec::new();
+            io::stdin()
+                .read_to_end(&mut input)
+                .map_err(|e| Error::io(None, e))?;
+            Ok(input)
+        }
+    }
+}
+
+/// Read a binary message, decoding it from hex (ignoring whitespace) if
+/// `hex` is set
+pub fn read_message(path: Option<&PathBuf>, hex: bool) -> Result<Vec<u8>, Error> {
+    let input = read(path.map(PathBuf::as_path))?;
+
+    if hex {
+        decode_hex(&input)
+    } else {
+        Ok(input)
+    }
+}
+
+/// Decode hex, ignoring any whitespace
+pub fn decode_hex(input: &[u8]) -> Result<Vec<u8>, Error> {
+    let digits: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    subtle_encoding::hex::decode(&digits).map_err(|_| Error::Hex)
+}
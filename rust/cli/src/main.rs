@@ -0,0 +1,57 @@
+//! `veriform`: command-line tool for inspecting, hashing and converting
+//! Veriform messages.
+//!
+//! # Usage
+//!
+//! ```text
+//! veriform decode [FILE]          dump the fields of a message
+//! veriform explain [FILE]         annotated hexdump of a message's encoding
+//! veriform hash [FILE]            compute the Verihash digest of a message
+//! veriform validate [FILE]        check a message conforms to the spec
+//! veriform convert [FILE]         convert between binary, text and TJSON
+//! veriform vectors [DIR]          run the shared test vectors
+//! ```
+//!
+//! Messages are read from the given file, or from standard input if no file
+//! (or `-`) is given. Pass `--help` after a command to see its options.
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
+
+mod commands;
+mod error;
+mod input;
+mod vectors;
+
+use crate::commands::Command;
+use gumdrop::Options;
+use std::process;
+
+/// Command-line options
+#[derive(Debug, Options)]
+struct Opts {
+    /// Print help message
+    #[options(help = "print help message")]
+    help: bool,
+
+    /// Command to run
+    #[options(command)]
+    command: Option<Command>,
+}
+
+fn main() {
+    let opts = Opts::parse_args_default_or_exit();
+
+    let command = opts.command.unwrap_or_else(|| {
+        eprintln!("Usage: veriform COMMAND [OPTIONS]");
+        eprintln!();
+        eprintln!("Available commands:");
+        eprintln!("{}", Command::usage());
+        process::exit(2);
+    });
+
+    if let Err(e) = command.run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
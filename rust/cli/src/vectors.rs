@@ -0,0 +1,492 @@
+//! Test vectors shared across Veriform implementations.
+//!
+//! The toplevel `vectors/` directory of the Veriform repository contains
+//! files of test vectors in TJSON format. Each file is an object whose
+//! `kind:s` member gives the [`Kind`] of vectors it contains, and whose
+//! `examples:A<O>` member contains the examples themselves:
+//!
+//! ```text
+//! {
+//!     "kind:s": "varint",
+//!     "examples:A<O>": [
+//!         {
+//!             "value:u": "0",
+//!             "encoded:d16": "01",
+//!             "success:b": true
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Files are checked according to their kind rather than their name, so new
+//! files of an existing kind are picked up by `veriform vectors` without any
+//! changes to the code which runs them.
+
+use serde_json::{Map, Value as JsonValue};
+use std::{
+    convert::TryInto,
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use veriform::{
+    builtins::{Float32, Float64},
+    decoder::{stream, Config},
+    tjson,
+    value::{Object, Value},
+    Decoder, Message, Sha256Digest, VerihashVersion,
+};
+
+/// Kinds of test vectors
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Kind {
+    /// Encodings of the `Float32` and `Float64` builtin types (`floats`)
+    Floats,
+
+    /// Encoded messages along with their TJSON representation (`messages`)
+    Messages,
+
+    /// `vint64` encodings of unsigned integers (`varint`)
+    Varint,
+
+    /// Verihash digests of values of every type (`verihash`)
+    Verihash,
+}
+
+impl Kind {
+    /// Get the name of this kind of test vectors, as used in `kind:s`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Floats => "floats",
+            Kind::Messages => "messages",
+            Kind::Varint => "varint",
+            Kind::Verihash => "verihash",
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = Failure;
+
+    fn from_str(s: &str) -> Result<Self, Failure> {
+        match s {
+            "floats" => Ok(Kind::Floats),
+            "messages" => Ok(Kind::Messages),
+            "varint" => Ok(Kind::Varint),
+            "verihash" => Ok(Kind::Verihash),
+            _ => Err(Failure::new(format!("unknown kind of test vectors: {}", s))),
+        }
+    }
+}
+
+/// Failure loading or checking test vectors, along with a description of
+/// what went wrong
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Failure(String);
+
+impl Failure {
+    /// Create a new failure with the given description
+    fn new(description: impl Into<String>) -> Self {
+        Failure(description.into())
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Find the vector files (i.e. `*.tjson`) in the given directory, sorted by
+/// path
+pub fn files(dir: &Path) -> Result<Vec<PathBuf>, Failure> {
+    let io_failure = |e| Failure::new(format!("{}: {}", dir.display(), e));
+
+    let mut paths = fs::read_dir(dir)
+        .map_err(io_failure)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_failure)?;
+
+    paths.retain(|path| path.extension().and_then(|ext| ext.to_str()) == Some("tjson"));
+    paths.sort();
+    Ok(paths)
+}
+
+/// File of test vectors
+#[derive(Clone, Debug)]
+pub struct File {
+    /// Name of the file
+    name: String,
+
+    /// Kind of test vectors in the file
+    kind: Kind,
+
+    /// Examples in the file
+    examples: Vec<Example>,
+}
+
+impl File {
+    /// Load the vector file at the given path
+    pub fn load(path: &Path) -> Result<Self, Failure> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Failure::new(format!("{}: invalid file name", path.display())))?
+            .to_owned();
+
+        let in_file = |e: &dyn Display| Failure::new(format!("{}: {}", name, e));
+        let json = fs::read_to_string(path).map_err(|e| in_file(&e))?;
+        let root: JsonValue = serde_json::from_str(&json).map_err(|e| in_file(&e))?;
+
+        let kind = root
+            .get("kind:s")
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| in_file(&"missing `kind:s`"))?
+            .parse()
+            .map_err(|e| in_file(&e))?;
+
+        let examples = root
+            .get("examples:A<O>")
+            .and_then(JsonValue::as_array)
+            .ok_or_else(|| in_file(&"missing `examples:A<O>`"))?
+            .iter()
+            .enumerate()
+            .map(|(index, example)| Example::new(&name, kind, index, example))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            name,
+            kind,
+            examples,
+        })
+    }
+
+    /// Get the name of this file
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the kind of test vectors in this file
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Get the examples in this file
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+}
+
+/// Example from a vector file
+#[derive(Clone, Debug)]
+pub struct Example {
+    /// Name of the file containing this example
+    file: String,
+
+    /// Kind of test vector this example is
+    kind: Kind,
+
+    /// Index of this example within the file
+    index: usize,
+
+    /// TJSON members of the example, keyed by name (sans type signature)
+    members: Map<String, JsonValue>,
+
+    /// Type signatures of the members, keyed by name
+    types: Map<String, JsonValue>,
+}
+
+impl Example {
+    /// Parse an example from the given file
+    fn new(file: &str, kind: Kind, index: usize, example: &JsonValue) -> Result<Self, Failure> {
+        let mut members = Map::new();
+        let mut types = Map::new();
+
+        let object = example
+            .as_object()
+            .ok_or_else(|| Failure::new(format!("{} #{}: not an object", file, index)))?;
+
+        for (key, value) in object {
+            let pos = key.rfind(':').ok_or_else(|| {
+                Failure::new(format!("{} #{}: untyped member: {}", file, index, key))
+            })?;
+
+            members.insert(key[..pos].to_owned(), value.clone());
+            types.insert(key[..pos].to_owned(), key[pos + 1..].into());
+        }
+
+        Ok(Self {
+            file: file.to_owned(),
+            kind,
+            index,
+            members,
+            types,
+        })
+    }
+
+    /// Get a description of this example for use in failure messages
+    pub fn describe(&self) -> String {
+        match self.members.get("name").and_then(JsonValue::as_str) {
+            Some(name) => format!("{} #{} ({})", self.file, self.index, name),
+            None => format!("{} #{}", self.file, self.index),
+        }
+    }
+
+    /// Check this example against this crate's decoders, encoders and
+    /// hashers
+    pub fn check(&self) -> Result<(), Failure> {
+        let result = match self.kind {
+            Kind::Floats => self.check_float(),
+            Kind::Messages => self.check_message(),
+            Kind::Varint => self.check_varint(),
+            Kind::Verihash => self.check_verihash(),
+        };
+
+        result.map_err(|msg| Failure::new(format!("{}: {}", self.describe(), msg)))
+    }
+
+    /// Check an example of kind `floats`
+    fn check_float(&self) -> Result<(), String> {
+        let encoded = self.bytes("encoded")?;
+        let ty = self.get("type", "s")?.as_str();
+
+        // Bits of the decoded number in big endian byte order
+        let result = match ty {
+            Some("f32") => Float32::decode(&mut Decoder::new(), &encoded)
+                .map(|float| float.to_bits().to_be_bytes().to_vec()),
+            Some("f64") => Float64::decode(&mut Decoder::new(), &encoded)
+                .map(|float| float.to_bits().to_be_bytes().to_vec()),
+            other => return Err(format!("unknown type: {:?}", other)),
+        };
+
+        if !self.success()? {
+            return match result {
+                Ok(_) => Err("decoded invalid number".to_owned()),
+                Err(_) => Ok(()),
+            };
+        }
+
+        if result.map_err(|e| e.to_string())? != self.bytes("output")? {
+            return Err("decoded number doesn't match `output:d16`".to_owned());
+        }
+
+        // Encoding the input should canonicalize it
+        let input = self.bytes("input")?;
+        let malformed = |_| "malformed `input:d16`".to_owned();
+
+        let reencoded = match ty {
+            Some("f32") => {
+                Float32::from_bits(u32::from_be_bytes(input[..].try_into().map_err(malformed)?))
+                    .encode_vec()
+            }
+            _ => Float64::from_bits(u64::from_be_bytes(input[..].try_into().map_err(malformed)?))
+                .encode_vec(),
+        };
+
+        if reencoded.map_err(|e| e.to_string())? != encoded {
+            return Err("encoded input doesn't match `encoded:d16`".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Check an example of kind `messages`
+    fn check_message(&self) -> Result<(), String> {
+        let encoded = self.bytes("encoded")?;
+        let config = self.config()?;
+        let result = Object::decode(&mut Decoder::with_config(config), &encoded);
+
+        if !self.success()? {
+            match (result, stream_digest(&encoded, config)) {
+                (Err(_), Err(_)) => (),
+                (Ok(_), _) => return Err("decoded invalid message".to_owned()),
+                (_, Ok(_)) => return Err("stream decoded invalid message".to_owned()),
+            }
+
+            // Examples which set limits must only be rejected because of them
+            if config != Config::default() {
+                Object::decode(&mut Decoder::new(), &encoded)
+                    .map_err(|e| format!("rejected without limits: {}", e))?;
+            }
+
+            return Ok(());
+        }
+
+        let decoded = result.map_err(|e| e.to_string())?;
+        let tjson = self.get("decoded", "O")?.to_string();
+        let expected: Object = tjson::from_str(&tjson).map_err(|e| e.to_string())?;
+
+        if decoded != expected {
+            return Err("decoded message doesn't match `decoded:O`".to_owned());
+        }
+
+        if expected.encode_vec().map_err(|e| e.to_string())? != encoded {
+            return Err("re-encoded message doesn't match `encoded:d16`".to_owned());
+        }
+
+        // The digest computed while decoding should match the one computed
+        // from the TJSON representation for every version of Verihash, and
+        // the streaming decoder should compute the same digest
+        for &version in &[VerihashVersion::V0, VerihashVersion::V1] {
+            let config = config.verihash_version(version);
+
+            let mut decoder = Decoder::with_config(config);
+            Object::decode(&mut decoder, &encoded).map_err(|e| e.to_string())?;
+
+            let mut digest = Sha256Digest::default();
+            decoder
+                .fill_digest(&mut digest)
+                .map_err(|e| e.to_string())?;
+
+            let expected = tjson::digest_with_version::<sha2::Sha256>(&tjson, version)
+                .map_err(|e| e.to_string())?;
+
+            if digest[..] != expected[..] {
+                return Err(format!("digest mismatch ({:?})", version));
+            }
+
+            if stream_digest(&encoded, config).map_err(|e| e.to_string())? != digest {
+                return Err(format!("digest mismatch ({:?}, streaming)", version));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check an example of kind `varint`
+    fn check_varint(&self) -> Result<(), String> {
+        let encoded = self.bytes("encoded")?;
+        let mut input = &encoded[..];
+        let result = vint64::decode(&mut input);
+
+        if !self.success()? {
+            return match result {
+                Ok(_) => Err("decoded invalid varint".to_owned()),
+                Err(_) => Ok(()),
+            };
+        }
+
+        let value = self.uint64("value")?;
+
+        if result.ok() != Some(value) || !input.is_empty() {
+            return Err("decoded value doesn't match `value:u`".to_owned());
+        }
+
+        if vint64::encode(value).as_ref() != &encoded[..] {
+            return Err("re-encoded value doesn't match `encoded:d16`".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Check an example of kind `verihash`
+    fn check_verihash(&self) -> Result<(), String> {
+        let algorithm = self.get("algorithm", "s")?.as_str();
+
+        if algorithm != Some("SHA256") {
+            return Err(format!("unsupported algorithm: {:?}", algorithm));
+        }
+
+        let expected = self.bytes("digest")?;
+        let actual = self.value("value")?.tagged_digest::<sha2::Sha256>();
+
+        if actual[..] != expected[..] {
+            return Err("digest doesn't match `digest:d16`".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Get the value of the given member with the given type signature
+    fn get(&self, name: &str, ty: &str) -> Result<&JsonValue, String> {
+        if self.types.get(name).and_then(JsonValue::as_str) != Some(ty) {
+            return Err(format!("missing `{}:{}`", name, ty));
+        }
+
+        Ok(&self.members[name])
+    }
+
+    /// Does this example describe a successful case?
+    fn success(&self) -> Result<bool, String> {
+        self.get("success", "b")?
+            .as_bool()
+            .ok_or_else(|| "malformed `success:b`".to_owned())
+    }
+
+    /// Get a `d16` member as bytes
+    fn bytes(&self, name: &str) -> Result<Vec<u8>, String> {
+        self.get(name, "d16")?
+            .as_str()
+            .and_then(|hex| subtle_encoding::hex::decode(hex).ok())
+            .ok_or_else(|| format!("malformed `{}:d16`", name))
+    }
+
+    /// Get a `u` member as an integer
+    fn uint64(&self, name: &str) -> Result<u64, String> {
+        self.get(name, "u")?
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| format!("malformed `{}:u`", name))
+    }
+
+    /// Get the decoder configuration for this example, i.e. the default
+    /// configuration with any limits given by `max_*:u` members applied
+    fn config(&self) -> Result<Config, String> {
+        let limit = |name: &str| match self.types.get(name) {
+            Some(_) => self.uint64(name).map(|limit| limit as usize),
+            None => Ok(usize::MAX),
+        };
+
+        Ok(Config::default()
+            .max_message_length(limit("max_message_length")?)
+            .max_bytes_length(limit("max_bytes_length")?)
+            .max_string_length(limit("max_string_length")?)
+            .max_sequence_elements(limit("max_sequence_elements")?)
+            .max_fields(limit("max_fields")?))
+    }
+
+    /// Parse a member of any type as a schema-less value
+    fn value(&self, name: &str) -> Result<Value, String> {
+        let ty = self
+            .types
+            .get(name)
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| format!("missing `{}`", name))?;
+
+        let json = self.members[name].to_string();
+        let object: Object =
+            tjson::from_str(&format!(r#"{{"0:{}":{}}}"#, ty, json)).map_err(|e| e.to_string())?;
+
+        object
+            .get(0)
+            .map(|field| field.value.clone())
+            .ok_or_else(|| format!("malformed `{}`", name))
+    }
+}
+
+/// Compute the digest of a message using the streaming decoder, feeding it
+/// the encoded message one byte at a time
+fn stream_digest(encoded: &[u8], config: Config) -> Result<Sha256Digest, veriform::Error> {
+    let mut decoder = stream::Decoder::<sha2::Sha256>::with_config(config);
+
+    for byte in encoded {
+        let mut input = core::slice::from_ref(byte);
+        while decoder.decode(&mut input)?.is_some() {}
+    }
+
+    while decoder.decode(&mut &[][..])?.is_some() {}
+
+    let mut digest = Sha256Digest::default();
+    decoder.fill_digest(&mut digest)?;
+    Ok(digest)
+}
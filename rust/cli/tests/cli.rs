@@ -0,0 +1,166 @@
+//! Integration tests for the `veriform` command-line tool
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+/// Message used in tests as hex (`{"1:O":{"24:u":"42"},"2!:d16":"48656c6c6f"}`)
+const MESSAGE_HEX: &str = "2d070a0655590b48656c6c6f";
+
+/// Run `veriform` with the given arguments and stdin
+fn veriform(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_veriform"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Get the stdout of a successful command
+fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn decode() {
+    let output = veriform(&["decode", "--hex"], MESSAGE_HEX.as_bytes());
+
+    assert_eq!(
+        stdout(output),
+        "{\n  1: message {\n    24: uint64 = 42\n  }\n  2!: bytes(5) = 48656c6c6f\n}\n"
+    );
+}
+
+#[test]
+fn explain() {
+    let output = stdout(veriform(&["explain", "-x"], MESSAGE_HEX.as_bytes()));
+    let lines: Vec<_> = output.lines().collect();
+
+    assert_eq!(
+        lines[0],
+        "     0..1      field header: tag=1 critical=false wire_type=message | 2d"
+    );
+    assert_eq!(lines[3], "     4..5        uint64 value: 42 | 55");
+    assert_eq!(lines.len(), 8);
+
+    let output = stdout(veriform(
+        &["explain", "-x", "--json"],
+        MESSAGE_HEX.as_bytes(),
+    ));
+    let first: serde_json::Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+    assert_eq!(first["item"]["type"], "field_header");
+    assert_eq!(first["item"]["tag"], 1);
+    assert_eq!(first["bytes"], "2d");
+}
+
+#[test]
+fn hash() {
+    let v0 = stdout(veriform(&["hash", "-x"], MESSAGE_HEX.as_bytes()));
+    let v1 = stdout(veriform(
+        &["hash", "-x", "-V", "v1"],
+        MESSAGE_HEX.as_bytes(),
+    ));
+
+    assert_eq!(v0.trim().len(), 64);
+    assert_ne!(v0, v1);
+
+    let sha384 = stdout(veriform(
+        &["hash", "-x", "-a", "sha384"],
+        MESSAGE_HEX.as_bytes(),
+    ));
+    assert_eq!(sha384.trim().len(), 96);
+
+    let sha512 = stdout(veriform(
+        &["hash", "-x", "-a", "sha512"],
+        MESSAGE_HEX.as_bytes(),
+    ));
+    assert_eq!(sha512.trim().len(), 128);
+
+    let output = veriform(&["hash", "-x", "-a", "md5"], MESSAGE_HEX.as_bytes());
+    assert!(!output.status.success());
+}
+
+#[test]
+fn validate() {
+    let output = veriform(&["validate", "-x"], MESSAGE_HEX.as_bytes());
+    assert_eq!(stdout(output), "ok\n");
+
+    // Field 2 is critical
+    let output = veriform(&["validate", "-x", "--no-critical"], MESSAGE_HEX.as_bytes());
+    assert!(!output.status.success());
+
+    // Out-of-order fields
+    let output = veriform(&["validate", "-x"], b"590b48656c6c6f2d070a0655");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn convert_round_trip() {
+    let tjson = stdout(veriform(
+        &["convert", "--from", "hex", "--compact"],
+        MESSAGE_HEX.as_bytes(),
+    ));
+    assert_eq!(
+        tjson,
+        "{\"1:O\":{\"24:u\":\"42\"},\"2!:d16\":\"48656c6c6f\"}\n"
+    );
+
+    let hex = stdout(veriform(
+        &["convert", "--from", "tjson", "--to", "hex"],
+        tjson.as_bytes(),
+    ));
+    assert_eq!(hex.trim(), MESSAGE_HEX);
+}
+
+#[test]
+fn convert_text_round_trip() {
+    let text = stdout(veriform(
+        &["convert", "--from", "hex", "--to", "text"],
+        MESSAGE_HEX.as_bytes(),
+    ));
+    assert_eq!(text, "1: {\n  24: 42\n}\n2!: x\"48656c6c6f\"\n");
+
+    let hex = stdout(veriform(
+        &["convert", "--from", "text", "--to", "hex"],
+        text.as_bytes(),
+    ));
+    assert_eq!(hex.trim(), MESSAGE_HEX);
+}
+
+#[test]
+fn vectors() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../vectors");
+    let output = stdout(veriform(&["vectors", dir.to_str().unwrap()], b""));
+    assert!(output.contains("floats.tjson (floats): "), "{}", output);
+    assert!(output.contains("messages.tjson (messages): "), "{}", output);
+    assert!(!output.contains("FAILED"), "{}", output);
+}
+
+#[test]
+fn vectors_unknown_kind() {
+    let dir = std::env::temp_dir().join(format!("veriform-vectors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("example.tjson"),
+        r#"{"kind:s":"unknown","examples:A<O>":[]}"#,
+    )
+    .unwrap();
+
+    let output = veriform(&["vectors", dir.to_str().unwrap()], b"");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown kind"));
+}
@@ -0,0 +1,112 @@
+//! Trait bounds for generic messages
+
+use crate::{
+    field::{self, WireType},
+    reflect,
+};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse::Parser, punctuated::Punctuated, Generics, Ident, Token, Type, WherePredicate};
+
+/// `where` clause predicates for the impls generated for a message
+pub(crate) struct Bounds {
+    /// Type parameters of the message type
+    params: Vec<Ident>,
+
+    /// Predicates for the `Message` impl
+    message: Vec<TokenStream>,
+
+    /// Predicates for the `Reflect` impl
+    reflect: Vec<TokenStream>,
+}
+
+impl Bounds {
+    /// Create an empty set of bounds for a type with the given generics
+    pub fn new(generics: &Generics) -> Self {
+        Self {
+            params: generics
+                .type_params()
+                .map(|param| param.ident.clone())
+                .collect(),
+            message: Vec::new(),
+            reflect: Vec::new(),
+        }
+    }
+
+    /// Add the bounds required by a field with the given name and type,
+    /// either inferred from its type or given by its `bound` attribute
+    pub fn add_field(&mut self, name: &str, ty: &Type, attrs: &field::Attrs) {
+        if let Some(bound) = attrs.bound() {
+            let predicates = Punctuated::<WherePredicate, Token![,]>::parse_terminated
+                .parse_str(bound)
+                .unwrap_or_else(|e| panic!("invalid bound on field `{}`: {}", name, e));
+
+            for predicate in predicates {
+                let predicate = predicate.into_token_stream();
+                self.message.push(predicate.clone());
+                self.reflect.push(predicate);
+            }
+
+            return;
+        }
+
+        match attrs.wire_type() {
+            WireType::Message => self.add_message(reflect::option_type(ty).unwrap_or(ty)),
+            WireType::Sequence => {
+                // TODO(tarcieri): support other types of sequences besides messages
+                let element_type = reflect::element_type(ty).unwrap_or_else(|| {
+                    panic!("can't determine element type of sequence field `{}`", name)
+                });
+
+                self.add_message(element_type);
+            }
+            WireType::Map if self.is_generic(ty) => {
+                let predicate = quote!(#ty: veriform::map::Map);
+                self.message.push(predicate.clone());
+                self.reflect.push(predicate);
+            }
+            _ => (),
+        }
+    }
+
+    /// `where` clause for the `Message` impl
+    pub fn message_where_clause(&self) -> TokenStream {
+        where_clause(&self.message)
+    }
+
+    /// `where` clause for the `Reflect` impl
+    pub fn reflect_where_clause(&self) -> TokenStream {
+        where_clause(&self.reflect)
+    }
+
+    /// Add bounds for a nested message of the given type
+    fn add_message(&mut self, ty: &Type) {
+        if self.is_generic(ty) {
+            self.message.push(quote!(#ty: veriform::Message));
+            self.reflect.push(quote!(#ty: veriform::reflect::Reflect));
+        }
+    }
+
+    /// Does the given type refer to any of the type parameters?
+    fn is_generic(&self, ty: &Type) -> bool {
+        !self.params.is_empty() && self.refers_to_param(ty.to_token_stream())
+    }
+
+    /// Do the given tokens contain the identifier of a type parameter?
+    fn refers_to_param(&self, tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => self.params.contains(&ident),
+            TokenTree::Group(group) => self.refers_to_param(group.stream()),
+            _ => false,
+        })
+    }
+}
+
+/// Generate a `where` clause with the given predicates (if there are any)
+fn where_clause(predicates: &[TokenStream]) -> TokenStream {
+    if predicates.is_empty() {
+        TokenStream::new()
+    } else {
+        quote!(where #(#predicates),*)
+    }
+}
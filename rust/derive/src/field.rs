@@ -19,13 +19,63 @@ use syn::Ident;
 ///     pub uint64_field: u64,
 ///
 ///     #[field(tag = 1, wire_type = "sint64")]
-///     pub sint64_field: i64,
+///     pub sint64_field: i32,
 ///
 ///     #[field(tag = 2, wire_type = "sequence", critical = true, max = 8)]
 ///     pub msg_sequence_field: Vec<ExampleMessageB>,
+///
+///     #[field(tag = 3, wire_type = "map")]
+///     pub map_field: BTreeMap<String, ExampleMessageB>,
 /// }
 /// ```
 ///
+/// # Integers
+///
+/// `uint64` fields may be `u8`, `u16`, `u32`, `u64` or `usize`, and `sint64`
+/// fields may be `i8`, `i16`, `i32` or `i64`. Values are widened to 64 bits
+/// when encoded, and values which are out of range for the field's type are
+/// rejected when decoded.
+///
+/// # Optional Fields
+///
+/// `message` fields of a struct may be an `Option` (e.g. `Option<Box<T>>`
+/// for recursive types). Fields which are `None` are omitted when encoded,
+/// and fields which are absent are decoded as `None`. Such fields are always
+/// marked as `optional` in their reflection descriptor.
+///
+/// ```ignore
+/// #[derive(Message)]
+/// pub struct Node {
+///     #[field(tag = 0, wire_type = "uint64")]
+///     pub value: u64,
+///
+///     #[field(tag = 1, wire_type = "message")]
+///     pub next: Option<Box<Node>>,
+/// }
+/// ```
+///
+/// # Generics
+///
+/// When a `message` or `sequence` field's type refers to a type parameter,
+/// the generated impls are bounded on the message type implementing
+/// `Message` (or `Reflect`, for a derived `Reflect` impl). For `map` fields,
+/// the bound is on the field's type implementing `Map`.
+///
+/// These inferred bounds can be replaced using the `bound` attribute, which
+/// contains `where` clause predicates to use instead (or `""` for none) in
+/// both impls:
+///
+/// ```ignore
+/// #[derive(Message)]
+/// pub struct Signed<T> {
+///     #[field(tag = 0, wire_type = "message", bound = "T: MyPayload")]
+///     pub payload: T,
+/// }
+/// ```
+///
+/// Types may also have lifetime parameters, e.g. used by a `PhantomData`
+/// member, which is left out of the message and instantiated when decoding.
+///
 /// [`Message`]: https://docs.rs/veriform/latest/veriform/derive.Message.html
 #[derive(Debug, FromField, FromVariant)]
 #[darling(attributes(field))]
@@ -55,6 +105,10 @@ pub(crate) struct Attrs {
     /// Maximum size of a variable-sized field
     #[darling(default)]
     max: Option<usize>,
+
+    /// Custom `where` clause predicates replacing the inferred bounds
+    #[darling(default)]
+    bound: Option<String>,
 }
 
 impl Attrs {
@@ -72,6 +126,31 @@ impl Attrs {
     pub fn is_critical(&self) -> bool {
         self.critical
     }
+
+    /// Is this field optional?
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    /// Size of a fixed-size field
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    /// Minimum size of a variable-sized field
+    pub fn min(&self) -> Option<usize> {
+        self.min
+    }
+
+    /// Maximum size of a variable-sized field
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+
+    /// Custom `where` clause predicates replacing the inferred bounds
+    pub fn bound(&self) -> Option<&str> {
+        self.bound.as_deref()
+    }
 }
 
 /// Wire type identifiers for Veriform types
@@ -99,6 +178,9 @@ pub(crate) enum WireType {
 
     /// `sequence`: sequences of other types (a.k.a. lists, arrays)
     Sequence,
+
+    /// `map`: key/value maps, encoded as a sequence of entry messages
+    Map,
 }
 
 impl WireType {
@@ -114,16 +196,18 @@ impl WireType {
             "string" => WireType::String,
             "message" => WireType::Message,
             "sequence" => WireType::Sequence,
+            "map" => WireType::Map,
             other => panic!("invalid wire type: {}", other),
         }
     }
 
-    /// Get the Rust type for this token
+    /// Get the Rust reference type to decode for this token (for the types
+    /// which are decoded by reference).
+    ///
+    /// Other types are inferred from the type of the field, e.g. `uint64`
+    /// fields may be any unsigned integer type up to `u64`.
     pub fn rust_type(self) -> Option<TokenStream> {
         let ty = match self {
-            WireType::Bool => quote!(bool),
-            WireType::UInt64 => quote!(u64),
-            WireType::SInt64 => quote!(i64),
             WireType::Bytes => quote!(&[u8]),
             WireType::String => quote!(&str),
             _ => return None,
@@ -132,19 +216,35 @@ impl WireType {
         Some(ty)
     }
 
-    /// Is the underlying Rust type to decode a reference type?
-    pub fn is_ref_type(self) -> bool {
-        self == WireType::Bytes || self == WireType::String
+    /// Get the identifier of the corresponding `veriform::field::WireType`
+    /// variant (for the types which have exactly one)
+    pub fn ident(self) -> TokenStream {
+        match self {
+            WireType::Bool => panic!("bool is encoded as one of two wire types"),
+            WireType::UInt64 => quote!(UInt64),
+            WireType::SInt64 => quote!(SInt64),
+            WireType::Bytes => quote!(Bytes),
+            WireType::String => quote!(String),
+            WireType::Message => quote!(Message),
+            WireType::Sequence | WireType::Map => quote!(Sequence),
+        }
     }
 
-    /// Is this [`WireType`] a `Message`?
-    pub fn is_message(self) -> bool {
+    /// Get the identifier of the corresponding `veriform::reflect::FieldType`
+    /// variant
+    pub fn field_type(self) -> TokenStream {
         match self {
-            WireType::Message => true,
-            _ => false,
+            WireType::Bool => quote!(Bool),
+            WireType::Map => quote!(Map),
+            other => other.ident(),
         }
     }
 
+    /// Is the underlying Rust type to decode a reference type?
+    pub fn is_ref_type(self) -> bool {
+        self == WireType::Bytes || self == WireType::String
+    }
+
     /// Is this [`WireType`] a `Sequence`?
     pub fn is_sequence(self) -> bool {
         match self {
@@ -152,4 +252,9 @@ impl WireType {
             _ => false,
         }
     }
+
+    /// Is this [`WireType`] a `Map`?
+    pub fn is_map(self) -> bool {
+        self == WireType::Map
+    }
 }
@@ -1,4 +1,4 @@
-//! Custom derive for Veriform `Message` trait.
+//! Custom derive for Veriform `Message` and `Reflect` traits.
 //!
 //! # Development Notes
 //!
@@ -13,9 +13,11 @@
 #![forbid(unsafe_code)]
 #![warn(rust_2018_idioms)]
 
+mod bounds;
 mod digest;
 mod field;
 mod message;
+mod reflect;
 
 use synstructure::decl_derive;
 
@@ -33,6 +35,11 @@ decl_derive!(
     ///   The `digest` field MUST be the last in the message. For more
     ///   information, see the [`digest::Attrs`] docs.
     ///
+    /// With the `alloc` feature of `veriform`, `message` fields (and elements
+    /// of `sequence` fields) may be wrapped in `Box`, `Rc` or `Arc` (on targets
+    /// with atomic pointers), which are encoded as the message they point to.
+    /// This allows recursive types.
+    ///
     /// See [`tests/derive.rs`] for usage examples.
     ///
     /// [`Message`]: https://docs.rs/veriform/latest/veriform/message/trait.Message.html
@@ -41,3 +48,18 @@ decl_derive!(
     /// [`tests/derive.rs`]: https://github.com/iqlusioninc/veriform/blob/develop/rust/tests/derive.rs
     message::derive
 );
+
+decl_derive!(
+    [Reflect, attributes(digest, field)] =>
+    /// Derive the [`Reflect`] trait for an `enum` or `struct` which also
+    /// derives [`Message`], providing a descriptor of the message's schema.
+    ///
+    /// Descriptors are generated from the same `#[field(...)]` attributes as
+    /// the [`Message`] impl. The descriptors of nested messages are referenced
+    /// from the message's own, so nested message types must also impl
+    /// [`Reflect`].
+    ///
+    /// [`Message`]: https://docs.rs/veriform/latest/veriform/message/trait.Message.html
+    /// [`Reflect`]: https://docs.rs/veriform/latest/veriform/reflect/trait.Reflect.html
+    reflect::derive
+);
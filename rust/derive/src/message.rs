@@ -1,17 +1,22 @@
 //! Custom derive support for the `Message` trait
 
 use crate::{
+    bounds::Bounds,
     digest,
     field::{self, WireType},
+    reflect,
 };
 use darling::{FromField, FromVariant};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{DataEnum, DataStruct, Field, Ident};
-use synstructure::Structure;
+use syn::{DataEnum, DataStruct, Field, Ident, Type};
+use synstructure::{AddBounds, Structure};
 
 /// Custom derive for `Message`
-pub(crate) fn derive(s: Structure<'_>) -> TokenStream {
+pub(crate) fn derive(mut s: Structure<'_>) -> TokenStream {
+    // Bounds are inferred from the wire types of fields (see `Bounds`)
+    s.add_bounds(AddBounds::None);
+
     match &s.ast().data {
         syn::Data::Enum(data) => DeriveEnum::derive(s, data),
         syn::Data::Struct(data) => DeriveStruct::derive(s, data),
@@ -29,6 +34,15 @@ struct DeriveEnum {
 
     /// Body of `Message::encoded_len()` in-progress for an enum
     encoded_len_body: TokenStream,
+
+    /// Body of `Message::encode_with()` in-progress for an enum
+    encode_with_body: TokenStream,
+
+    /// Body of `Message::encoded_len_with()` in-progress for an enum
+    encoded_len_with_body: TokenStream,
+
+    /// Bounds on the generic parameters of an enum
+    bounds: Bounds,
 }
 
 impl DeriveEnum {
@@ -45,6 +59,9 @@ impl DeriveEnum {
             decode_body: TokenStream::new(),
             encode_body: TokenStream::new(),
             encoded_len_body: TokenStream::new(),
+            encode_with_body: TokenStream::new(),
+            encoded_len_with_body: TokenStream::new(),
+            bounds: Bounds::new(&s.ast().generics),
         };
 
         for (variant_info, variant) in s.variants().iter().zip(&data.variants) {
@@ -54,13 +71,32 @@ impl DeriveEnum {
 
             state.derive_decode_match_arm(&variant.ident, &attrs);
 
+            let ty = &variant
+                .fields
+                .iter()
+                .next()
+                .unwrap_or_else(|| panic!("no field in enum variant: {}", variant.ident))
+                .ty;
+
+            state
+                .bounds
+                .add_field(&variant.ident.to_string(), ty, &attrs);
+
             variant_info
                 .each(|bi| encode_field(&bi.binding, &attrs))
                 .to_tokens(&mut state.encode_body);
 
             variant_info
                 .each(|bi| encoded_len_for_field(&bi.binding, &attrs))
-                .to_tokens(&mut state.encoded_len_body)
+                .to_tokens(&mut state.encoded_len_body);
+
+            variant_info
+                .each(|bi| encode_field_with(&bi.binding, &attrs))
+                .to_tokens(&mut state.encode_with_body);
+
+            variant_info
+                .each(|bi| encoded_len_with_for_field(&bi.binding, &attrs))
+                .to_tokens(&mut state.encoded_len_with_body);
         }
 
         state.finish(s)
@@ -82,6 +118,10 @@ impl DeriveEnum {
             }
         } else if wire_type.is_sequence() {
             todo!();
+        } else if wire_type.is_map() {
+            quote! {
+                veriform::derive_helpers::decode_map(decoder, #tag, &mut input).map(Self::#name)
+            }
         } else {
             quote! {
                 decoder.decode(#tag, &mut input).map(Self::#name)
@@ -100,9 +140,12 @@ impl DeriveEnum {
         let decode_body = self.decode_body;
         let encode_body = self.encode_body;
         let encoded_len_body = self.encoded_len_body;
+        let encode_with_body = self.encode_with_body;
+        let encoded_len_with_body = self.encoded_len_with_body;
+        let where_clause = self.bounds.message_where_clause();
 
         s.gen_impl(quote! {
-            gen impl Message for @Self {
+            gen impl veriform::Message for @Self #where_clause {
                 fn decode<D>(
                     decoder: &mut veriform::decoder::Decoder<D>,
                     mut input: &[u8]
@@ -115,19 +158,19 @@ impl DeriveEnum {
                     #[allow(unused_imports)]
                     use veriform::decoder::{Decode, DecodeRef};
 
-                    let msg = match veriform::derive_helpers::decode_tag(input)? {
+                    let msg = match veriform::derive_helpers::decode_tag(decoder, input)? {
                         #decode_body
-                        tag => Err(veriform::derive_helpers::unknown_tag(tag))
+                        tag => Err(veriform::derive_helpers::unknown_tag(decoder, tag))
                     }?;
 
-                    veriform::derive_helpers::check_input_consumed(input)?;
+                    veriform::derive_helpers::check_input_consumed(decoder, input)?;
                     Ok(msg)
                 }
 
-                fn encode<'a>(
+                fn encode<'__veriform_a>(
                     &self,
-                    buffer: &'a mut [u8]
-                ) -> Result<&'a [u8], veriform::Error> {
+                    buffer: &'__veriform_a mut [u8]
+                ) -> Result<&'__veriform_a [u8], veriform::Error> {
                     let mut encoder = veriform::Encoder::new(buffer);
 
                     match self {
@@ -142,6 +185,31 @@ impl DeriveEnum {
                         #encoded_len_body
                     }
                 }
+
+                #[allow(unused_variables)]
+                fn encode_with<'__veriform_a>(
+                    &self,
+                    buffer: &'__veriform_a mut [u8],
+                    lengths: &mut veriform::encoder::LengthTable<'_>
+                ) -> Result<&'__veriform_a [u8], veriform::Error> {
+                    let mut encoder = veriform::Encoder::new(buffer);
+
+                    match self {
+                        #encode_with_body
+                    }
+
+                    Ok(encoder.finish())
+                }
+
+                #[allow(unused_variables)]
+                fn encoded_len_with(
+                    &self,
+                    lengths: &mut veriform::encoder::LengthTable<'_>
+                ) -> Result<usize, veriform::Error> {
+                    Ok(match self {
+                        #encoded_len_with_body
+                    })
+                }
             }
         })
     }
@@ -161,6 +229,15 @@ struct DeriveStruct {
 
     /// Body of `Message::encoded_len()` in-progress for a struct
     encoded_len_body: TokenStream,
+
+    /// Body of `Message::encode_with()` in-progress for a struct
+    encode_with_body: TokenStream,
+
+    /// Body of `Message::encoded_len_with()` in-progress for a struct
+    encoded_len_with_body: TokenStream,
+
+    /// Bounds on the generic parameters of a struct
+    bounds: Bounds,
 }
 
 impl DeriveStruct {
@@ -172,6 +249,9 @@ impl DeriveStruct {
             inst_body: TokenStream::new(),
             encode_body: TokenStream::new(),
             encoded_len_body: quote!(0),
+            encode_with_body: TokenStream::new(),
+            encoded_len_with_body: quote!(0),
+            bounds: Bounds::new(&s.ast().generics),
         };
 
         let variant = &s.variants()[0];
@@ -202,6 +282,10 @@ impl DeriveStruct {
                     _ => (), // ignore other attributes
                 }
             }
+
+            if is_phantom_data(&field.ty) {
+                state.derive_phantom_data(field);
+            }
         }
 
         state.finish(&s, variant.pat())
@@ -215,18 +299,53 @@ impl DeriveStruct {
             panic!("error parsing field attributes: {}", e);
         });
 
-        self.derive_decode_field(name, &attrs);
+        let optional = reflect::option_type(&field.ty).is_some();
+
+        if optional && attrs.wire_type() != WireType::Message {
+            panic!(
+                "`Option` is only supported for fields with the `message` wire type (field `{}`)",
+                name
+            );
+        }
+
+        if optional {
+            let tag = attrs.tag();
+            quote! {
+                let #name = veriform::derive_helpers::decode_optional(
+                    decoder,
+                    #tag,
+                    &mut input
+                )?;
+            }
+            .to_tokens(&mut self.decode_body);
+        } else {
+            self.derive_decode_field(name, &attrs);
+        }
+
+        self.bounds.add_field(&name.to_string(), &field.ty, &attrs);
 
         let inst_field = quote!(#name,);
         inst_field.to_tokens(&mut self.inst_body);
 
-        let enc_field = encode_field(binding, &attrs);
+        let enc_field = optional_encode(binding, optional, encode_field(binding, &attrs));
         let enc_field_with_semicolon = quote!(#enc_field;);
         enc_field_with_semicolon.to_tokens(&mut self.encode_body);
 
-        let enc_field_len = encoded_len_for_field(binding, &attrs);
+        let enc_field_len = optional_len(binding, optional, encoded_len_for_field(binding, &attrs));
         let enc_field_len_with_plus = quote!(+ #enc_field_len);
         enc_field_len_with_plus.to_tokens(&mut self.encoded_len_body);
+
+        let enc_field_with = optional_encode(binding, optional, encode_field_with(binding, &attrs));
+        let enc_field_with_semicolon = quote!(#enc_field_with;);
+        enc_field_with_semicolon.to_tokens(&mut self.encode_with_body);
+
+        let enc_field_len_with = optional_len(
+            binding,
+            optional,
+            encoded_len_with_for_field(binding, &attrs),
+        );
+        let enc_field_len_with_plus = quote!(+ #enc_field_len_with);
+        enc_field_len_with_plus.to_tokens(&mut self.encoded_len_with_body);
     }
 
     /// Derive a match arm of an struct `decode` method
@@ -236,15 +355,25 @@ impl DeriveStruct {
 
         match wire_type.rust_type() {
             Some(ty) => {
-                if wire_type.is_ref_type() {
-                    quote! { let #name: #ty = decoder.decode_ref(#tag, &mut input)?; }
-                } else {
-                    quote! { let #name: #ty = decoder.decode(#tag, &mut input)?; }
+                let error_wire_type = wire_type.ident();
+                quote! {
+                    let #name = {
+                        let field: #ty = decoder.decode_ref(#tag, &mut input)?;
+                        field.try_into().map_err(|_| {
+                            veriform::field::WireType::#error_wire_type.decoding_error()
+                        })?
+                    };
                 }
             }
             None => {
-                if wire_type.is_message() {
-                    quote! { let #name = decoder.decode(#tag, &mut input)?; }
+                if wire_type.is_map() {
+                    quote! {
+                        let #name = veriform::derive_helpers::decode_map(
+                            decoder,
+                            #tag,
+                            &mut input
+                        )?;
+                    }
                 } else if wire_type.is_sequence() {
                     // TODO(tarcieri): hoist more of this into a `derive_helper` function?
                     quote! {
@@ -255,7 +384,9 @@ impl DeriveStruct {
                         )?;
                     }
                 } else {
-                    unreachable!();
+                    // The type to decode (including the width of integers) is
+                    // inferred from the type of the field
+                    quote! { let #name = decoder.decode(#tag, &mut input)?; }
                 }
             }
         }
@@ -276,6 +407,14 @@ impl DeriveStruct {
         }
     }
 
+    /// Derive instantiating a `PhantomData` member of a struct, which isn't
+    /// part of the message (e.g. a marker for an otherwise unused lifetime)
+    fn derive_phantom_data(&mut self, field: &Field) {
+        let name = parse_field_name(field);
+        let inst_field = quote!(#name: core::marker::PhantomData,);
+        inst_field.to_tokens(&mut self.inst_body);
+    }
+
     /// Derive computing a SHA-256 digest of a message
     fn derive_sha256_digest(&mut self, name: &Ident) {
         let fill_digest = quote! {
@@ -295,9 +434,12 @@ impl DeriveStruct {
         let inst_body = self.inst_body;
         let encode_body = self.encode_body;
         let encoded_len_body = self.encoded_len_body;
+        let encode_with_body = self.encode_with_body;
+        let encoded_len_with_body = self.encoded_len_with_body;
+        let where_clause = self.bounds.message_where_clause();
 
         s.gen_impl(quote! {
-            gen impl Message for @Self {
+            gen impl veriform::Message for @Self #where_clause {
                 fn decode<D>(
                     decoder: &mut veriform::decoder::Decoder<D>,
                     mut input: &[u8]
@@ -305,6 +447,8 @@ impl DeriveStruct {
                 where
                     D: veriform::digest::Digest,
                 {
+                    #[allow(unused_imports)]
+                    use core::convert::TryInto;
                     #[allow(unused_imports)]
                     use veriform::decoder::{Decode, DecodeRef};
 
@@ -313,10 +457,10 @@ impl DeriveStruct {
                     Ok(Self { #inst_body })
                 }
 
-                fn encode<'a>(
+                fn encode<'__veriform_a>(
                     &self,
-                    buffer: &'a mut [u8]
-                ) -> Result<&'a [u8], veriform::Error> {
+                    buffer: &'__veriform_a mut [u8]
+                ) -> Result<&'__veriform_a [u8], veriform::Error> {
                     let mut encoder = veriform::Encoder::new(buffer);
 
                     match self {
@@ -331,6 +475,31 @@ impl DeriveStruct {
                         #pattern => { #encoded_len_body }
                     }
                 }
+
+                #[allow(unused_variables)]
+                fn encode_with<'__veriform_a>(
+                    &self,
+                    buffer: &'__veriform_a mut [u8],
+                    lengths: &mut veriform::encoder::LengthTable<'_>
+                ) -> Result<&'__veriform_a [u8], veriform::Error> {
+                    let mut encoder = veriform::Encoder::new(buffer);
+
+                    match self {
+                        #pattern => { #encode_with_body }
+                    }
+
+                    Ok(encoder.finish())
+                }
+
+                #[allow(unused_variables)]
+                fn encoded_len_with(
+                    &self,
+                    lengths: &mut veriform::encoder::LengthTable<'_>
+                ) -> Result<usize, veriform::Error> {
+                    Ok(match self {
+                        #pattern => { #encoded_len_with_body }
+                    })
+                }
             }
         })
     }
@@ -344,15 +513,59 @@ fn parse_field_name(field: &Field) -> &Ident {
         .unwrap_or_else(|| panic!("no name on struct field (e.g. tuple structs unsupported)"))
 }
 
+/// Is the given type `PhantomData`?
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == "PhantomData"),
+        _ => false,
+    }
+}
+
+/// Encode an optional field only when it's present
+fn optional_encode(binding: &Ident, optional: bool, encode: TokenStream) -> TokenStream {
+    if optional {
+        quote! {
+            if let Some(#binding) = #binding {
+                #encode;
+            }
+        }
+    } else {
+        encode
+    }
+}
+
+/// Compute the encoded length of an optional field, which is zero when it's
+/// absent
+fn optional_len(binding: &Ident, optional: bool, len: TokenStream) -> TokenStream {
+    if optional {
+        quote! {
+            match #binding {
+                Some(#binding) => #len,
+                None => 0,
+            }
+        }
+    } else {
+        len
+    }
+}
+
 /// Encode a field of a message
 fn encode_field(binding: &Ident, attrs: &field::Attrs) -> TokenStream {
     let tag = attrs.tag();
     let critical = attrs.is_critical();
 
     match attrs.wire_type() {
-        WireType::Bool => todo!(),
-        WireType::UInt64 => quote! { encoder.uint64(#tag, #critical, *#binding)? },
-        WireType::SInt64 => quote! { encoder.sint64(#tag, #critical, *#binding)? },
+        WireType::Bool => quote! { encoder.boolean(#tag, #critical, *#binding)? },
+        WireType::UInt64 => quote! {
+            encoder.uint64(#tag, #critical, veriform::derive_helpers::Widen::<u64>::widen(*#binding))?
+        },
+        WireType::SInt64 => quote! {
+            encoder.sint64(#tag, #critical, veriform::derive_helpers::Widen::<i64>::widen(*#binding))?
+        },
         WireType::Bytes => quote! { encoder.bytes(#tag, #critical, #binding)? },
         WireType::String => quote! { encoder.string(#tag, #critical, #binding)? },
         WireType::Message => quote! { encoder.message(#tag, #critical, #binding)? },
@@ -360,6 +573,7 @@ fn encode_field(binding: &Ident, attrs: &field::Attrs) -> TokenStream {
             // TODO(tarcieri): support other types of sequences besides messages
             veriform::derive_helpers::encode_message_seq(&mut encoder, #tag, #critical, #binding)?;
         },
+        WireType::Map => quote! { encoder.map(#tag, #critical, #binding)? },
     }
 }
 
@@ -368,9 +582,19 @@ fn encoded_len_for_field(binding: &Ident, attrs: &field::Attrs) -> TokenStream {
     let tag = attrs.tag();
 
     match attrs.wire_type() {
-        WireType::Bool => todo!(),
-        WireType::UInt64 => quote! { veriform::field::length::uint64(#tag, *#binding) },
-        WireType::SInt64 => quote! { veriform::field::length::sint64(#tag, *#binding) },
+        WireType::Bool => quote! { veriform::field::length::boolean(#tag) },
+        WireType::UInt64 => quote! {
+            veriform::field::length::uint64(
+                #tag,
+                veriform::derive_helpers::Widen::<u64>::widen(*#binding)
+            )
+        },
+        WireType::SInt64 => quote! {
+            veriform::field::length::sint64(
+                #tag,
+                veriform::derive_helpers::Widen::<i64>::widen(*#binding)
+            )
+        },
         WireType::Bytes => quote! { veriform::field::length::bytes(#tag, #binding) },
         WireType::String => quote! { veriform::field::length::string(#tag, #binding) },
         WireType::Message => quote! { veriform::field::length::message(#tag, #binding) },
@@ -381,5 +605,51 @@ fn encoded_len_for_field(binding: &Ident, attrs: &field::Attrs) -> TokenStream {
                 #binding.iter().map(|elem| elem as &dyn veriform::Message)
             )
         },
+        WireType::Map => quote! { veriform::field::length::map(#tag, #binding) },
+    }
+}
+
+/// Encode a field of a message using the lengths of nested messages and
+/// sequences recorded in a `LengthTable`
+fn encode_field_with(binding: &Ident, attrs: &field::Attrs) -> TokenStream {
+    let tag = attrs.tag();
+    let critical = attrs.is_critical();
+
+    match attrs.wire_type() {
+        WireType::Message => quote! { encoder.message_with(#tag, #critical, #binding, lengths)? },
+        WireType::Sequence => quote! {
+            // TODO(tarcieri): support other types of sequences besides messages
+            veriform::derive_helpers::encode_message_seq_with(
+                &mut encoder,
+                #tag,
+                #critical,
+                #binding,
+                lengths
+            )?;
+        },
+        WireType::Map => quote! { encoder.map_with(#tag, #critical, #binding, lengths)? },
+        _ => encode_field(binding, attrs),
+    }
+}
+
+/// Compute the encoded length of a field, recording the lengths of nested
+/// messages and sequences in a `LengthTable`
+fn encoded_len_with_for_field(binding: &Ident, attrs: &field::Attrs) -> TokenStream {
+    let tag = attrs.tag();
+
+    match attrs.wire_type() {
+        WireType::Message => {
+            quote! { veriform::field::length::message_with(#tag, #binding, lengths)? }
+        }
+        WireType::Sequence => quote! {
+            // TODO(tarcieri): support other types of sequences besides messages
+            veriform::field::length::message_seq_with(
+                #tag,
+                #binding.iter().map(|elem| elem as &dyn veriform::Message),
+                lengths
+            )?
+        },
+        WireType::Map => quote! { veriform::field::length::map_with(#tag, #binding, lengths)? },
+        _ => encoded_len_for_field(binding, attrs),
     }
 }
@@ -0,0 +1,161 @@
+//! Custom derive support for the `Reflect` trait
+
+use crate::{
+    bounds::Bounds,
+    field::{self, WireType},
+};
+use darling::{FromField, FromVariant};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{GenericArgument, PathArguments, Type};
+use synstructure::{AddBounds, Structure};
+
+/// Custom derive for `Reflect`
+pub(crate) fn derive(mut s: Structure<'_>) -> TokenStream {
+    // Bounds are inferred from the wire types of fields (see `Bounds`)
+    s.add_bounds(AddBounds::None);
+
+    let mut fields = Vec::new();
+    let mut bounds = Bounds::new(&s.ast().generics);
+
+    let kind = match &s.ast().data {
+        syn::Data::Enum(data) => {
+            for variant in &data.variants {
+                let attrs = field::Attrs::from_variant(variant).unwrap_or_else(|e| {
+                    panic!("error parsing field attributes: {}", e);
+                });
+
+                let ty = &variant
+                    .fields
+                    .iter()
+                    .next()
+                    .unwrap_or_else(|| panic!("no field in enum variant: {}", variant.ident))
+                    .ty;
+
+                let name = variant.ident.to_string();
+                fields.push(field_descriptor(&name, ty, &attrs));
+                bounds.add_field(&name, ty, &attrs);
+            }
+
+            quote!(Enum)
+        }
+        syn::Data::Struct(data) => {
+            // Only `#[field(...)]` members are part of the schema (e.g. not
+            // `#[digest(...)]` members)
+            for field in &data.fields {
+                if !field.attrs.iter().any(|attr| attr.path.is_ident("field")) {
+                    continue;
+                }
+
+                let attrs = field::Attrs::from_field(field).unwrap_or_else(|e| {
+                    panic!("error parsing field attributes: {}", e);
+                });
+
+                let name = field
+                    .ident
+                    .as_ref()
+                    .unwrap_or_else(|| {
+                        panic!("no name on struct field (e.g. tuple structs unsupported)")
+                    })
+                    .to_string();
+
+                fields.push(field_descriptor(&name, &field.ty, &attrs));
+                bounds.add_field(&name, &field.ty, &attrs);
+            }
+
+            quote!(Struct)
+        }
+        other => panic!("can't derive `Reflect` on: {:?}", other),
+    };
+
+    let name = s.ast().ident.to_string();
+    let where_clause = bounds.reflect_where_clause();
+
+    s.gen_impl(quote! {
+        gen impl veriform::reflect::Reflect for @Self #where_clause {
+            const DESCRIPTOR: veriform::reflect::MessageDescriptor =
+                veriform::reflect::MessageDescriptor {
+                    name: #name,
+                    kind: veriform::reflect::MessageKind::#kind,
+                    fields: &[#(#fields),*],
+                };
+        }
+    })
+}
+
+/// Generate the descriptor of a field (or enum variant) with the given name
+/// and Rust type
+fn field_descriptor(name: &str, ty: &Type, attrs: &field::Attrs) -> TokenStream {
+    let name = name.trim_start_matches("r#");
+    let tag = attrs.tag();
+    let field_type = attrs.wire_type().field_type();
+    let critical = attrs.is_critical();
+    let optional = attrs.is_optional() || option_type(ty).is_some();
+    let size = option_tokens(attrs.size());
+    let min = option_tokens(attrs.min());
+    let max = option_tokens(attrs.max());
+
+    let nested = match attrs.wire_type() {
+        WireType::Message => {
+            let ty = option_type(ty).unwrap_or(ty);
+            quote!(Some(veriform::reflect::descriptor::<#ty>))
+        }
+        WireType::Sequence => {
+            // TODO(tarcieri): support other types of sequences besides messages
+            let element_type = element_type(ty).unwrap_or_else(|| {
+                panic!("can't determine element type of sequence field `{}`", name)
+            });
+
+            quote!(Some(veriform::reflect::descriptor::<#element_type>))
+        }
+        _ => quote!(None),
+    };
+
+    quote! {
+        veriform::reflect::FieldDescriptor {
+            name: #name,
+            tag: #tag,
+            ty: veriform::reflect::FieldType::#field_type,
+            critical: #critical,
+            optional: #optional,
+            size: #size,
+            min: #min,
+            max: #max,
+            nested: #nested,
+        }
+    }
+}
+
+/// Convert an optional size bound into tokens
+fn option_tokens(value: Option<usize>) -> TokenStream {
+    match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    }
+}
+
+/// Get the type contained in an `Option` type (i.e. `T` in `Option<T>`), or
+/// `None` if the given type isn't an `Option`
+pub(crate) fn option_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(path) if path.path.segments.last()?.ident == "Option" => element_type(ty),
+        _ => None,
+    }
+}
+
+/// Get the type of the elements of a sequence type, i.e. its first generic
+/// type argument (e.g. `T` in `Vec<T>` or `heapless::Vec<T, N>`)
+pub(crate) fn element_type(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
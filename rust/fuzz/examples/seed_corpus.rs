@@ -0,0 +1,87 @@
+//! Generate the seed corpus for the fuzz targets from the test vectors in
+//! the toplevel `vectors/` directory of this repository.
+//!
+//! Run from the `fuzz` directory with `cargo run --example seed_corpus`.
+
+use serde_json::Value as JsonValue;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Targets seeded with encoded messages
+const MESSAGE_TARGETS: &[&str] = &["decode_message", "event_decoder"];
+
+/// Targets seeded with encoded `vint64`s
+const VINT64_TARGETS: &[&str] = &["vint64_decode"];
+
+/// Chunk size prepended to the seeds of the `event_decoder` target
+const EVENT_DECODER_CHUNK_SIZE: u8 = 3;
+
+fn main() {
+    let messages = load_encoded("messages");
+    let varints = load_encoded("varint");
+
+    for target in MESSAGE_TARGETS {
+        for (i, encoded) in messages.iter().enumerate() {
+            let mut seed = vec![];
+
+            if *target == "event_decoder" {
+                seed.push(EVENT_DECODER_CHUNK_SIZE);
+            }
+
+            seed.extend_from_slice(encoded);
+            write_seed(target, &format!("messages-{:02}", i), &seed);
+        }
+    }
+
+    for target in VINT64_TARGETS {
+        for (i, encoded) in varints.iter().enumerate() {
+            write_seed(target, &format!("varint-{:02}", i), encoded);
+        }
+
+        write_seed(target, "varint-all", &varints.concat());
+    }
+}
+
+/// Path to the shared test vectors
+fn vectors_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../vectors")
+}
+
+/// Load the `encoded:d16` members of every example in the given vector file
+fn load_encoded(name: &str) -> Vec<Vec<u8>> {
+    let path = vectors_dir().join(format!("{}.tjson", name));
+    let json = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("error reading {}: {}", path.display(), e));
+
+    let vectors: JsonValue = serde_json::from_str(&json).unwrap();
+
+    vectors["examples:A<O>"]
+        .as_array()
+        .unwrap_or_else(|| panic!("{}: missing `examples:A<O>`", path.display()))
+        .iter()
+        .filter_map(|example| example["encoded:d16"].as_str())
+        .map(decode_hex)
+        .collect()
+}
+
+/// Decode a hex string
+fn decode_hex(hex: &str) -> Vec<u8> {
+    assert_eq!(hex.len() % 2, 0, "odd-length hex: {}", hex);
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Write a seed to the corpus of the given target
+fn write_seed(target: &str, name: &str, seed: &[u8]) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("corpus")
+        .join(target);
+
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(name), seed).unwrap();
+}
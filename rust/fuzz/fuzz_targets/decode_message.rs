@@ -0,0 +1,29 @@
+//! Decode arbitrary input as derived messages and as `Object`s, checking
+//! that anything which decodes successfully also re-encodes and hashes
+//! consistently
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use veriform::Message;
+use veriform_fuzz::{decode, decode_object, ExampleStruct};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((message, digest)) = decode::<ExampleStruct>(data) {
+        assert_eq!(message.digest, Some(digest));
+
+        let encoded = message.encode_vec().unwrap();
+        assert_eq!(encoded.len(), message.encoded_len());
+
+        let (decoded, _) = decode::<ExampleStruct>(&encoded).unwrap();
+        assert_eq!(decoded.uint64_field, message.uint64_field);
+        assert_eq!(decoded.sint64_field, message.sint64_field);
+        assert_eq!(decoded.msg_sequence_field, message.msg_sequence_field);
+    }
+
+    if let Ok((object, digest)) = decode_object(data) {
+        // Veriform encodings are canonical
+        assert_eq!(object.encode_vec().unwrap(), data);
+        assert_eq!(object.digest::<sha2::Sha256>()[..], digest[..]);
+    }
+});
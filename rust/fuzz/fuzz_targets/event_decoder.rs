@@ -0,0 +1,50 @@
+//! Feed arbitrary input to the streaming event decoder in chunks of varying
+//! sizes, checking it agrees with the `Object` decoder
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use veriform::error;
+use veriform_fuzz::{decode_object, decode_stream};
+
+fuzz_target!(|data: &[u8]| {
+    let (chunk_size, input) = match data.split_first() {
+        Some((&byte, input)) => (usize::from(byte).max(1), input),
+        None => return,
+    };
+
+    let stream_result = decode_stream(input, chunk_size);
+
+    // Results must not depend on how the input is split up. Errors are
+    // reported as data arrives, so when the input has several defects which
+    // one is reported first can vary, but whether decoding fails can't.
+    let bytewise_result = decode_stream(input, 1);
+    assert_eq!(
+        stream_result.as_ref().map(|(_, digest)| digest).ok(),
+        bytewise_result.as_ref().map(|(_, digest)| digest).ok()
+    );
+
+    match (stream_result, decode_object(input)) {
+        (Ok((_, stream_digest)), Ok((_, object_digest))) => {
+            assert_eq!(stream_digest, object_digest)
+        }
+        (Err(_), Err(_)) => (),
+        // The decoders have different implementation limits: the slice
+        // decoder doesn't support sequences within messages which are
+        // themselves within sequences
+        (Ok(_), Err(e)) | (Err(e), Ok(_)) if is_implementation_limit(e.kind()) => (),
+        (stream_result, object_result) => panic!(
+            "decoders disagree: stream={:?} object={:?}",
+            stream_result, object_result
+        ),
+    }
+});
+
+/// Is this error the result of a decoder's implementation limits (as opposed
+/// to malformed input)?
+fn is_implementation_limit(kind: error::Kind) -> bool {
+    matches!(
+        kind,
+        error::Kind::NestedSequence | error::Kind::NestingDepth { .. }
+    )
+}
@@ -0,0 +1,32 @@
+//! Differential round trip: encode an arbitrary message, decode it using
+//! every decoder, and re-encode it, checking the encodings and digests match
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use veriform::Message;
+use veriform_fuzz::{decode, decode_object, decode_stream, ExampleStruct};
+
+fuzz_target!(|message: ExampleStruct| {
+    let encoded = message.encode_vec().unwrap();
+    assert_eq!(encoded.len(), message.encoded_len());
+
+    let (decoded, digest) = decode::<ExampleStruct>(&encoded).unwrap();
+    assert_eq!(decoded.digest, Some(digest));
+    assert_eq!(decoded.encode_vec().unwrap(), encoded);
+    assert_eq!(
+        ExampleStruct {
+            digest: None,
+            ..decoded
+        },
+        message
+    );
+
+    let (object, object_digest) = decode_object(&encoded).unwrap();
+    assert_eq!(object_digest, digest);
+    assert_eq!(object.digest::<sha2::Sha256>()[..], digest[..]);
+    assert_eq!(object.encode_vec().unwrap(), encoded);
+
+    let (_, stream_digest) = decode_stream(&encoded, 1).unwrap();
+    assert_eq!(stream_digest, digest);
+});
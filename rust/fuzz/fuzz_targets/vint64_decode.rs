@@ -0,0 +1,59 @@
+//! Decode `vint64`s, checking they re-encode canonically and that the bulk
+//! decoder agrees with the scalar one
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+/// Number of values decoded at a time by the bulk decoder
+const BATCH_SIZE: usize = 16;
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = data;
+    let mut expected = vec![];
+
+    // Offset of the value which failed to decode (if any)
+    let scalar_failure = loop {
+        if input.is_empty() {
+            break None;
+        }
+
+        let remaining = input;
+
+        match vint64::decode(&mut input) {
+            Ok(value) => {
+                let consumed = &remaining[..remaining.len() - input.len()];
+                assert_eq!(vint64::encode(value).as_ref(), consumed);
+                assert_eq!(vint64::encoded_len(value), consumed.len());
+                assert_eq!(vint64::decoded_len(consumed[0]), consumed.len());
+                expected.push(value);
+            }
+            Err(_) => break Some(data.len() - remaining.len()),
+        }
+    };
+
+    let mut input = data;
+    let mut actual = vec![];
+    let mut batch = [0u64; BATCH_SIZE];
+
+    let batch_failure = loop {
+        if input.is_empty() {
+            break None;
+        }
+
+        match vint64::decode_slice(&mut input, &mut batch) {
+            Ok(n) => actual.extend_from_slice(&batch[..n]),
+            Err(_) => break Some(data.len() - input.len()),
+        }
+    };
+
+    assert_eq!(scalar_failure, batch_failure);
+
+    // The values preceding the failing one within the final batch are not
+    // returned by the bulk decoder
+    if batch_failure.is_none() {
+        assert_eq!(expected, actual);
+    } else {
+        assert!(expected.starts_with(&actual));
+    }
+});
@@ -0,0 +1,131 @@
+//! Shared support code for the Veriform fuzz targets: example messages (the
+//! same ones used by the `veriform_derive` integration tests) along with
+//! `Arbitrary` impls for them, and helpers for decoding messages using each
+//! of the available decoders.
+
+#![forbid(unsafe_code)]
+#![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
+
+use heapless::consts::U8;
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use veriform::{
+    decoder::stream::Decoder as StreamDecoder, value::Object, Decoder, Error, Message, Sha256Digest,
+};
+
+/// Empty message
+#[derive(Message, Debug, Eq, PartialEq)]
+pub struct EmptyStruct {}
+
+impl<'a> Arbitrary<'a> for EmptyStruct {
+    fn arbitrary(_u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(EmptyStruct {})
+    }
+}
+
+/// Example enum
+#[derive(Message, Debug, Eq, PartialEq)]
+pub enum ExampleEnum {
+    /// Fixed-size bytes variant
+    #[field(tag = 0, wire_type = "bytes", size = 32)]
+    BytesVariant([u8; 32]),
+
+    /// Nested message variant
+    #[field(tag = 1, wire_type = "message")]
+    MessageVariant(EmptyStruct),
+}
+
+impl<'a> Arbitrary<'a> for ExampleEnum {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        if u.arbitrary()? {
+            Ok(ExampleEnum::BytesVariant(u.arbitrary()?))
+        } else {
+            Ok(ExampleEnum::MessageVariant(u.arbitrary()?))
+        }
+    }
+}
+
+/// Example struct
+#[derive(Message, Debug, Eq, PartialEq)]
+pub struct ExampleStruct {
+    /// Unsigned integer field
+    #[field(tag = 0, wire_type = "uint64", critical = true)]
+    pub uint64_field: u64,
+
+    /// Signed integer field
+    #[field(tag = 1, wire_type = "sint64", critical = true)]
+    pub sint64_field: i64,
+
+    /// Sequence of messages
+    #[field(tag = 2, wire_type = "sequence", critical = true, max = 8)]
+    pub msg_sequence_field: heapless::Vec<ExampleEnum, U8>,
+
+    /// Digest of the message (computed when decoding)
+    #[digest(alg = "sha256")]
+    pub digest: Option<Sha256Digest>,
+}
+
+impl<'a> Arbitrary<'a> for ExampleStruct {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let uint64_field = u.arbitrary()?;
+        let sint64_field = u.arbitrary()?;
+        let mut msg_sequence_field = heapless::Vec::new();
+
+        for _ in 0..u.int_in_range(0..=8)? {
+            msg_sequence_field
+                .push(u.arbitrary()?)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        }
+
+        Ok(Self {
+            uint64_field,
+            sint64_field,
+            msg_sequence_field,
+            digest: None,
+        })
+    }
+}
+
+/// Decode a message, returning it along with the digest computed by the
+/// decoder
+pub fn decode<M: Message>(input: &[u8]) -> Result<(M, Sha256Digest), Error> {
+    let mut decoder = Decoder::new();
+    let message = M::decode(&mut decoder, input)?;
+
+    let mut digest = Sha256Digest::default();
+    decoder.fill_digest(&mut digest)?;
+    Ok((message, digest))
+}
+
+/// Decode a message as an [`Object`], returning it along with the digest
+/// computed by the decoder
+pub fn decode_object(input: &[u8]) -> Result<(Object, Sha256Digest), Error> {
+    decode(input)
+}
+
+/// Decode a message using the streaming decoder, feeding it input in chunks
+/// of the given size (which must be non-zero) and returning the number of
+/// events along with the digest of the message
+pub fn decode_stream(input: &[u8], chunk_size: usize) -> Result<(usize, Sha256Digest), Error> {
+    let mut decoder = StreamDecoder::<sha2::Sha256>::new();
+    let mut events = 0;
+
+    for mut chunk in input.chunks(chunk_size) {
+        while !chunk.is_empty() {
+            let chunk_len = chunk.len();
+
+            if decoder.decode(&mut chunk)?.is_some() {
+                events += 1;
+            } else {
+                assert!(chunk.len() < chunk_len, "streaming decoder stalled");
+            }
+        }
+    }
+
+    while decoder.decode(&mut &[][..])?.is_some() {
+        events += 1;
+    }
+
+    let mut digest = Sha256Digest::default();
+    decoder.fill_digest(&mut digest)?;
+    Ok((events, digest))
+}
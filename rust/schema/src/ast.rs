@@ -0,0 +1,182 @@
+//! Abstract syntax tree for schemas
+
+use core::fmt;
+
+/// Tag which identifies a field (or enum variant)
+pub type Tag = u64;
+
+/// Parsed schema
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Schema {
+    /// Definitions in the order they appear in the schema
+    pub definitions: Vec<Definition>,
+}
+
+impl Schema {
+    /// Find the definition with the given name
+    pub fn get(&self, name: &str) -> Option<&Definition> {
+        self.definitions.iter().find(|def| def.name == name)
+    }
+}
+
+/// Definition of a `message` or `enum`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Definition {
+    /// Is this a `message` or an `enum`?
+    pub kind: DefinitionKind,
+
+    /// Name of the defined type
+    pub name: String,
+
+    /// Lines of the doc comment preceding the definition
+    pub docs: Vec<String>,
+
+    /// Fields of a message or variants of an enum
+    pub fields: Vec<Field>,
+
+    /// Position of the definition in the schema
+    pub position: Position,
+}
+
+/// Kinds of definitions
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DefinitionKind {
+    /// `message`: contains all of its fields
+    Message,
+
+    /// `enum`: contains exactly one of its variants
+    Enum,
+}
+
+/// Field of a message (or variant of an enum)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Field {
+    /// Name of the field
+    pub name: String,
+
+    /// Tag which identifies the field
+    pub tag: Tag,
+
+    /// Is this field critical?
+    pub critical: bool,
+
+    /// Type of the field's value
+    pub ty: Type,
+
+    /// Constraints on the field's value
+    pub options: Options,
+
+    /// Lines of the doc comment preceding the field
+    pub docs: Vec<String>,
+
+    /// Position of the field in the schema
+    pub position: Position,
+}
+
+/// Types of field values
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Type {
+    /// `bool`: boolean value
+    Bool,
+
+    /// `uint64`: 64-bit unsigned integer
+    UInt64,
+
+    /// `sint64`: 64-bit signed integer
+    SInt64,
+
+    /// `bytes`: binary data
+    Bytes,
+
+    /// `string`: Unicode string
+    String,
+
+    /// `sequence<T>`: sequence of values of the given type
+    Sequence(Box<Type>),
+
+    /// `message` or `enum` defined in the schema
+    Named(String),
+
+    /// Builtin type
+    Builtin(Builtin),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => f.write_str("bool"),
+            Type::UInt64 => f.write_str("uint64"),
+            Type::SInt64 => f.write_str("sint64"),
+            Type::Bytes => f.write_str("bytes"),
+            Type::String => f.write_str("string"),
+            Type::Sequence(ty) => write!(f, "sequence<{}>", ty),
+            Type::Named(name) => f.write_str(name),
+            Type::Builtin(builtin) => f.write_str(builtin.name()),
+        }
+    }
+}
+
+/// Builtin types
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Builtin {
+    /// `Timestamp`: TAI64N timestamp
+    Timestamp,
+
+    /// `UUID`: universally unique identifier
+    Uuid,
+}
+
+impl Builtin {
+    /// Find the builtin type with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Timestamp" => Some(Builtin::Timestamp),
+            "UUID" => Some(Builtin::Uuid),
+            _ => None,
+        }
+    }
+
+    /// Get the name of this builtin type in schemas
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Timestamp => "Timestamp",
+            Builtin::Uuid => "UUID",
+        }
+    }
+}
+
+/// Constraints on the value of a field
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    /// Fixed size of a `bytes` or `string` value
+    pub size: Option<usize>,
+
+    /// Minimum size of a `bytes`, `string` or `sequence` value
+    pub min: Option<usize>,
+
+    /// Maximum size of a `bytes`, `string` or `sequence` value
+    pub max: Option<usize>,
+}
+
+impl Options {
+    /// Are no options set?
+    pub fn is_empty(&self) -> bool {
+        self.size.is_none() && self.min.is_none() && self.max.is_none()
+    }
+}
+
+/// Position within a schema
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Position {
+    /// Line number (starting at 1)
+    pub line: usize,
+
+    /// Column number (starting at 1)
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
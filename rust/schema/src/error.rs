@@ -0,0 +1,110 @@
+//! Error types
+
+use crate::ast::{Position, Tag};
+use core::fmt;
+use displaydoc::Display;
+
+/// Error type
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error {
+    /// Kind of error
+    kind: Kind,
+
+    /// Position in the schema where the error occurred
+    position: Position,
+}
+
+impl Error {
+    /// Create a new error of the given kind at the given position
+    pub fn new(kind: Kind, position: Position) -> Self {
+        Self { kind, position }
+    }
+
+    /// Get the kind of error
+    pub fn kind(&self) -> &Kind {
+        &self.kind
+    }
+
+    /// Get the position in the schema where the error occurred
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Kinds of errors
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum Kind {
+    /// duplicate definition: {name}
+    DuplicateDefinition {
+        /// name of the type defined more than once
+        name: String,
+    },
+
+    /// duplicate field: {name}
+    DuplicateField {
+        /// name of the field defined more than once
+        name: String,
+    },
+
+    /// duplicate tag: {tag}
+    DuplicateTag {
+        /// tag used by more than one field
+        tag: Tag,
+    },
+
+    /// invalid integer
+    Integer,
+
+    /// invalid option `{name}` for type `{ty}`
+    Option {
+        /// name of the option
+        name: &'static str,
+
+        /// type of the field
+        ty: String,
+    },
+
+    /// field {tag} is out-of-order
+    Order {
+        /// tag of the out-of-order field
+        tag: Tag,
+    },
+
+    /// tag out of range: {tag}
+    TagRange {
+        /// tag which is too large to encode
+        tag: Tag,
+    },
+
+    /// unexpected character: {c:?}
+    UnexpectedChar {
+        /// character which isn't allowed in schemas
+        c: char,
+    },
+
+    /// unexpected end of schema
+    UnexpectedEnd,
+
+    /// expected {expected}, found {found}
+    UnexpectedToken {
+        /// description of what the parser expected
+        expected: &'static str,
+
+        /// token which was found instead
+        found: String,
+    },
+
+    /// unknown type: {name}
+    UnknownType {
+        /// name of the undefined type
+        name: String,
+    },
+}
@@ -0,0 +1,207 @@
+//! Lexer which splits schemas into tokens
+
+use crate::{
+    ast::Position,
+    error::{Error, Kind},
+};
+use core::{fmt, iter::Peekable, str::Chars};
+
+/// Tokens in a schema
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Token {
+    /// Identifier or keyword
+    Ident(String),
+
+    /// Unsigned integer
+    Int(u64),
+
+    /// Punctuation character
+    Punct(char),
+
+    /// Line of a doc comment (with the leading `///` removed)
+    Doc(String),
+
+    /// End of the schema
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{}`", ident),
+            Token::Int(int) => write!(f, "`{}`", int),
+            Token::Punct(c) => write!(f, "`{}`", c),
+            Token::Doc(_) => f.write_str("doc comment"),
+            Token::End => f.write_str("end of schema"),
+        }
+    }
+}
+
+/// Punctuation characters allowed in schemas
+const PUNCTUATION: &str = "{}[]()<>:,=!";
+
+/// Lexer
+pub(crate) struct Lexer<'a> {
+    /// Remaining characters in the schema
+    chars: Peekable<Chars<'a>>,
+
+    /// Position of the next character
+    position: Position,
+}
+
+impl<'a> Lexer<'a> {
+    /// Create a new lexer for the given schema
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    /// Get the next token along with its position
+    pub fn next_token(&mut self) -> Result<(Token, Position), Error> {
+        self.skip_whitespace();
+        let position = self.position;
+
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None => return Ok((Token::End, position)),
+        };
+
+        let token = if c == '/' {
+            match self.comment(position)? {
+                Some(doc) => doc,
+                None => return self.next_token(),
+            }
+        } else if c.is_ascii_digit() {
+            self.int(position)?
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            Token::Ident(self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'))
+        } else if PUNCTUATION.contains(c) {
+            self.bump();
+            Token::Punct(c)
+        } else {
+            return Err(Error::new(Kind::UnexpectedChar { c }, position));
+        };
+
+        Ok((token, position))
+    }
+
+    /// Lex a comment, returning a [`Token::Doc`] for doc comments and
+    /// `None` for regular comments (which are skipped)
+    fn comment(&mut self, position: Position) -> Result<Option<Token>, Error> {
+        self.bump();
+
+        if self.chars.peek() != Some(&'/') {
+            return Err(Error::new(Kind::UnexpectedChar { c: '/' }, position));
+        }
+
+        self.bump();
+        let is_doc = self.chars.peek() == Some(&'/');
+        let text = self.take_while(|c| c != '\n');
+
+        if !is_doc || text.starts_with("//") {
+            return Ok(None);
+        }
+
+        let text = &text[1..];
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        Ok(Some(Token::Doc(text.trim_end().to_owned())))
+    }
+
+    /// Lex an unsigned integer
+    fn int(&mut self, position: Position) -> Result<Token, Error> {
+        let digits = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        digits
+            .parse()
+            .map(Token::Int)
+            .map_err(|_| Error::new(Kind::Integer, position))
+    }
+
+    /// Skip whitespace characters
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().map(|c| c.is_whitespace()) == Some(true) {
+            self.bump();
+        }
+    }
+
+    /// Take characters while they match the given predicate
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut result = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if !predicate(c) {
+                break;
+            }
+
+            result.push(c);
+            self.bump();
+        }
+
+        result
+    }
+
+    /// Consume the next character, updating the position
+    fn bump(&mut self) {
+        if let Some(c) = self.chars.next() {
+            if c == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lexer, Token};
+
+    fn tokens(input: &str) -> Vec<Token> {
+        let mut lexer = Lexer::new(input);
+        let mut result = vec![];
+
+        loop {
+            match lexer.next_token().unwrap().0 {
+                Token::End => return result,
+                token => result.push(token),
+            }
+        }
+    }
+
+    #[test]
+    fn lex_field() {
+        assert_eq!(
+            tokens("secs[0]: !bytes(size = 8), // seconds"),
+            [
+                Token::Ident("secs".to_owned()),
+                Token::Punct('['),
+                Token::Int(0),
+                Token::Punct(']'),
+                Token::Punct(':'),
+                Token::Punct('!'),
+                Token::Ident("bytes".to_owned()),
+                Token::Punct('('),
+                Token::Ident("size".to_owned()),
+                Token::Punct('='),
+                Token::Int(8),
+                Token::Punct(')'),
+                Token::Punct(','),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_doc_comments() {
+        assert_eq!(
+            tokens("/// Example\n///\n//// not a doc comment\nmessage"),
+            [
+                Token::Doc("Example".to_owned()),
+                Token::Doc("".to_owned()),
+                Token::Ident("message".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Parser for the Veriform schema language.
+//!
+//! Schemas describe the messages exchanged between Veriform implementations
+//! in a language-independent way, so the same field tags can be shared
+//! between them rather than copied by hand.
+//!
+//! # Syntax
+//!
+//! ```text
+//! /// TAI64N timestamp
+//! message Timestamp {
+//!     secs[0]: !bytes(size = 8),
+//!     nanos[1]: bytes(size = 4)
+//! }
+//!
+//! /// Credential used to authenticate a user
+//! enum Credential {
+//!     password[0]: string(max = 64),
+//!     keys[1]: sequence<PublicKey>(max = 8),
+//! }
+//!
+//! message PublicKey {
+//!     id![0]: UUID,
+//!     algorithm[1]: uint64,
+//!     revoked[2]: bool,
+//!     created_at[3]: Timestamp,
+//! }
+//! ```
+//!
+//! A schema is a list of `message` and `enum` definitions. Each contains a
+//! comma-separated list of fields (or, for an `enum`, variants) of the form
+//! `name[tag]: type(options)`, where:
+//!
+//! - `tag` is the integer which identifies the field. The fields of a
+//!   message must be listed in order of increasing tag.
+//! - `type` is one of `bool`, `uint64`, `sint64`, `bytes`, `string`,
+//!   `sequence<type>`, the name of a `message` or `enum`, or one of the
+//!   builtin types (`Timestamp` or `UUID`) unless the schema defines a type
+//!   with the same name.
+//! - `options` are an optional comma-separated list of `size = n` (fixed
+//!   size of `bytes` or `string`), `min = n` and `max = n` (bounds on the
+//!   size of `bytes`, `string` or `sequence` values).
+//!
+//! Fields are marked critical by a `!` after their name or before their type.
+//! Doc comments (`///`) are attached to the following definition or field,
+//! and other comments (`//`) are ignored.
+
+#![doc(html_root_url = "https://docs.rs/veriform_schema/0.1.0")]
+#![forbid(unsafe_code)]
+#![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
+
+mod ast;
+mod error;
+mod lexer;
+mod parser;
+mod validate;
+
+pub use crate::{
+    ast::{Builtin, Definition, DefinitionKind, Field, Options, Position, Schema, Tag, Type},
+    error::{Error, Kind},
+};
+
+/// Parse a schema, ensuring its definitions are well-formed and all of the
+/// types it references are defined
+pub fn parse(input: &str) -> Result<Schema, Error> {
+    let schema = parser::Parser::new(input).parse()?;
+    validate::validate(schema)
+}
+
+impl core::str::FromStr for Schema {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Error> {
+        parse(input)
+    }
+}
@@ -0,0 +1,272 @@
+//! Recursive descent parser for schemas
+
+use crate::{
+    ast::{Definition, DefinitionKind, Field, Options, Position, Schema, Type},
+    error::{Error, Kind},
+    lexer::{Lexer, Token},
+};
+use core::convert::TryFrom;
+
+/// Parser
+pub(crate) struct Parser<'a> {
+    /// Lexer which provides tokens
+    lexer: Lexer<'a>,
+
+    /// Next token (if it has been peeked at)
+    peeked: Option<(Token, Position)>,
+}
+
+impl<'a> Parser<'a> {
+    /// Create a new parser for the given schema
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            lexer: Lexer::new(input),
+            peeked: None,
+        }
+    }
+
+    /// Parse the schema
+    pub fn parse(mut self) -> Result<Schema, Error> {
+        let mut schema = Schema::default();
+
+        loop {
+            let docs = self.docs()?;
+
+            if self.peek()?.0 == Token::End {
+                return Ok(schema);
+            }
+
+            schema.definitions.push(self.definition(docs)?);
+        }
+    }
+
+    /// Parse a `message` or `enum` definition
+    fn definition(&mut self, docs: Vec<String>) -> Result<Definition, Error> {
+        let (keyword, position) = self.ident("`message` or `enum`")?;
+
+        let kind = match keyword.as_str() {
+            "message" => DefinitionKind::Message,
+            "enum" => DefinitionKind::Enum,
+            _ => {
+                return Err(unexpected(
+                    "`message` or `enum`",
+                    Token::Ident(keyword),
+                    position,
+                ))
+            }
+        };
+
+        let name = self.ident("type name")?.0;
+        self.punct('{')?;
+
+        let mut fields = vec![];
+
+        loop {
+            let field_docs = self.docs()?;
+
+            if self.eat_punct('}')? {
+                break;
+            }
+
+            fields.push(self.field(field_docs)?);
+
+            if !self.eat_punct(',')? {
+                self.punct('}')?;
+                break;
+            }
+        }
+
+        Ok(Definition {
+            kind,
+            name,
+            docs,
+            fields,
+            position,
+        })
+    }
+
+    /// Parse a field: `name[tag]: type(options)`
+    fn field(&mut self, docs: Vec<String>) -> Result<Field, Error> {
+        let (name, position) = self.ident("field name")?;
+        let mut critical = self.eat_punct('!')?;
+
+        self.punct('[')?;
+        let (tag, _) = self.int()?;
+        self.punct(']')?;
+        self.punct(':')?;
+
+        critical |= self.eat_punct('!')?;
+        let ty = self.ty()?;
+        let options = self.options()?;
+
+        Ok(Field {
+            name,
+            tag,
+            critical,
+            ty,
+            options,
+            docs,
+            position,
+        })
+    }
+
+    /// Parse a type
+    fn ty(&mut self) -> Result<Type, Error> {
+        let (name, _) = self.ident("type")?;
+
+        let ty = match name.as_str() {
+            "bool" => Type::Bool,
+            "uint64" => Type::UInt64,
+            "sint64" => Type::SInt64,
+            "bytes" => Type::Bytes,
+            "string" => Type::String,
+            "sequence" => {
+                self.punct('<')?;
+                let element_type = self.ty()?;
+                self.punct('>')?;
+                Type::Sequence(Box::new(element_type))
+            }
+            _ => Type::Named(name),
+        };
+
+        Ok(ty)
+    }
+
+    /// Parse the (optional) options of a field: `(name = value, ...)`
+    fn options(&mut self) -> Result<Options, Error> {
+        let mut options = Options::default();
+
+        if !self.eat_punct('(')? {
+            return Ok(options);
+        }
+
+        while !self.eat_punct(')')? {
+            let (name, position) = self.ident("option name")?;
+
+            let option = match name.as_str() {
+                "size" => &mut options.size,
+                "min" => &mut options.min,
+                "max" => &mut options.max,
+                _ => {
+                    return Err(unexpected(
+                        "`size`, `min` or `max`",
+                        Token::Ident(name),
+                        position,
+                    ))
+                }
+            };
+
+            self.punct('=')?;
+            *option = Some(self.size()?);
+
+            if !self.eat_punct(',')? {
+                self.punct(')')?;
+                break;
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Parse any doc comments
+    fn docs(&mut self) -> Result<Vec<String>, Error> {
+        let mut docs = vec![];
+
+        while let Token::Doc(_) = self.peek()?.0 {
+            if let (Token::Doc(line), _) = self.next()? {
+                docs.push(line);
+            }
+        }
+
+        Ok(docs)
+    }
+
+    /// Parse an identifier
+    fn ident(&mut self, expected: &'static str) -> Result<(String, Position), Error> {
+        match self.next()? {
+            (Token::Ident(ident), position) => Ok((ident, position)),
+            (token, position) => Err(unexpected(expected, token, position)),
+        }
+    }
+
+    /// Parse an unsigned integer
+    fn int(&mut self) -> Result<(u64, Position), Error> {
+        match self.next()? {
+            (Token::Int(int), position) => Ok((int, position)),
+            (token, position) => Err(unexpected("integer", token, position)),
+        }
+    }
+
+    /// Parse an unsigned integer which fits in a `usize`
+    fn size(&mut self) -> Result<usize, Error> {
+        let (int, position) = self.int()?;
+        usize::try_from(int).map_err(|_| Error::new(Kind::Integer, position))
+    }
+
+    /// Parse the given punctuation character
+    fn punct(&mut self, c: char) -> Result<(), Error> {
+        match self.next()? {
+            (Token::Punct(p), _) if p == c => Ok(()),
+            (token, position) => Err(unexpected(punct_name(c), token, position)),
+        }
+    }
+
+    /// Consume the given punctuation character if it's next, returning
+    /// whether it was consumed
+    fn eat_punct(&mut self, c: char) -> Result<bool, Error> {
+        if self.peek()?.0 == Token::Punct(c) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Peek at the next token
+    fn peek(&mut self) -> Result<&(Token, Position), Error> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next_token()?);
+        }
+
+        Ok(self.peeked.as_ref().unwrap())
+    }
+
+    /// Consume the next token
+    fn next(&mut self) -> Result<(Token, Position), Error> {
+        match self.peeked.take() {
+            Some(peeked) => Ok(peeked),
+            None => self.lexer.next_token(),
+        }
+    }
+}
+
+/// Create an error for an unexpected token
+fn unexpected(expected: &'static str, found: Token, position: Position) -> Error {
+    let kind = match found {
+        Token::End => Kind::UnexpectedEnd,
+        token => Kind::UnexpectedToken {
+            expected,
+            found: token.to_string(),
+        },
+    };
+
+    Error::new(kind, position)
+}
+
+/// Get a description of a punctuation character for errors
+fn punct_name(c: char) -> &'static str {
+    match c {
+        '{' => "`{`",
+        '}' => "`}`",
+        '[' => "`[`",
+        ']' => "`]`",
+        '(' => "`(`",
+        ')' => "`)`",
+        '<' => "`<`",
+        '>' => "`>`",
+        ':' => "`:`",
+        ',' => "`,`",
+        '=' => "`=`",
+        _ => "punctuation",
+    }
+}
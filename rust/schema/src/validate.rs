@@ -0,0 +1,118 @@
+//! Validation of parsed schemas
+
+use crate::{
+    ast::{Builtin, DefinitionKind, Field, Schema, Tag, Type},
+    error::{Error, Kind},
+};
+use std::collections::BTreeSet;
+
+/// Largest tag which can be encoded in a field header
+const MAX_TAG: Tag = u64::MAX >> 4;
+
+/// Validate a parsed schema, resolving the names of builtin types
+pub(crate) fn validate(mut schema: Schema) -> Result<Schema, Error> {
+    let mut names = BTreeSet::new();
+
+    for definition in &schema.definitions {
+        if !names.insert(definition.name.clone()) {
+            return Err(Error::new(
+                Kind::DuplicateDefinition {
+                    name: definition.name.clone(),
+                },
+                definition.position,
+            ));
+        }
+    }
+
+    for definition in &mut schema.definitions {
+        let mut field_names = BTreeSet::new();
+        let mut tags = BTreeSet::new();
+        let mut last_tag = None;
+
+        for field in &mut definition.fields {
+            if !field_names.insert(field.name.clone()) {
+                return Err(Error::new(
+                    Kind::DuplicateField {
+                        name: field.name.clone(),
+                    },
+                    field.position,
+                ));
+            }
+
+            if !tags.insert(field.tag) {
+                return Err(Error::new(
+                    Kind::DuplicateTag { tag: field.tag },
+                    field.position,
+                ));
+            }
+
+            if field.tag > MAX_TAG {
+                return Err(Error::new(
+                    Kind::TagRange { tag: field.tag },
+                    field.position,
+                ));
+            }
+
+            // Fields of messages are encoded in the order they're defined
+            if definition.kind == DefinitionKind::Message {
+                if last_tag.map(|tag| field.tag < tag) == Some(true) {
+                    return Err(Error::new(Kind::Order { tag: field.tag }, field.position));
+                }
+
+                last_tag = Some(field.tag);
+            }
+
+            resolve(&mut field.ty, &names).map_err(|kind| Error::new(kind, field.position))?;
+            check_options(field)?;
+        }
+    }
+
+    Ok(schema)
+}
+
+/// Resolve references to builtin types (unless shadowed by a definition),
+/// ensuring all other names refer to definitions
+fn resolve(ty: &mut Type, names: &BTreeSet<String>) -> Result<(), Kind> {
+    match ty {
+        Type::Sequence(element_type) => resolve(element_type, names),
+        Type::Named(name) if !names.contains(name.as_str()) => match Builtin::from_name(name) {
+            Some(builtin) => {
+                *ty = Type::Builtin(builtin);
+                Ok(())
+            }
+            None => Err(Kind::UnknownType { name: name.clone() }),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Ensure the options of a field are applicable to its type and consistent
+fn check_options(field: &Field) -> Result<(), Error> {
+    let options = &field.options;
+    let sized = matches!(field.ty, Type::Bytes | Type::String);
+    let bounded = sized || matches!(field.ty, Type::Sequence(_));
+
+    let invalid =
+        if options.size.is_some() && (!sized || options.min.is_some() || options.max.is_some()) {
+            Some("size")
+        } else if options.min.is_some() && !bounded {
+            Some("min")
+        } else if options.max.is_some() && !bounded {
+            Some("max")
+        } else if matches!((options.min, options.max), (Some(min), Some(max)) if min > max) {
+            Some("min")
+        } else {
+            None
+        };
+
+    match invalid {
+        Some(name) => Err(Error::new(
+            Kind::Option {
+                name,
+                ty: field.ty.to_string(),
+            },
+            field.position,
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,215 @@
+//! Schema parser tests
+
+use veriform_schema::{
+    parse, Builtin, DefinitionKind, Field, Kind, Options, Position, Schema, Type,
+};
+
+/// Example schema (from the crate documentation)
+const EXAMPLE_SCHEMA: &str = r#"
+/// TAI64N timestamp
+message Timestamp {
+    secs[0]: !bytes(size = 8),
+    nanos[1]: bytes(size = 4)
+}
+
+/// Credential used to authenticate a user
+enum Credential {
+    password[0]: string(max = 64),
+    keys[1]: sequence<PublicKey>(max = 8),
+}
+
+message PublicKey {
+    id![0]: UUID,
+    algorithm[1]: uint64,
+    revoked[2]: bool,
+    created_at[3]: Timestamp,
+}
+"#;
+
+/// Parse a schema, returning the kind and position of the error
+fn parse_err(input: &str) -> (Kind, Position) {
+    let err = parse(input).unwrap_err();
+    (err.kind().clone(), err.position())
+}
+
+#[test]
+fn parse_example() {
+    let schema: Schema = EXAMPLE_SCHEMA.parse().unwrap();
+    assert_eq!(schema.definitions.len(), 3);
+
+    let timestamp = schema.get("Timestamp").unwrap();
+    assert_eq!(timestamp.kind, DefinitionKind::Message);
+    assert_eq!(timestamp.docs, ["TAI64N timestamp"]);
+    assert_eq!(
+        timestamp.fields[0],
+        Field {
+            name: "secs".to_owned(),
+            tag: 0,
+            critical: true,
+            ty: Type::Bytes,
+            options: Options {
+                size: Some(8),
+                min: None,
+                max: None
+            },
+            docs: vec![],
+            position: Position { line: 4, column: 5 },
+        }
+    );
+    assert!(!timestamp.fields[1].critical);
+
+    let credential = schema.get("Credential").unwrap();
+    assert_eq!(credential.kind, DefinitionKind::Enum);
+    assert_eq!(credential.fields[0].ty, Type::String);
+    assert_eq!(credential.fields[0].options.max, Some(64));
+    assert_eq!(
+        credential.fields[1].ty,
+        Type::Sequence(Box::new(Type::Named("PublicKey".to_owned())))
+    );
+
+    let public_key = schema.get("PublicKey").unwrap();
+    assert!(public_key.fields[0].critical);
+    assert_eq!(public_key.fields[0].ty, Type::Builtin(Builtin::Uuid));
+    assert_eq!(public_key.fields[2].ty, Type::Bool);
+
+    // `Timestamp` refers to the definition in the schema, not the builtin
+    assert_eq!(public_key.fields[3].ty, Type::Named("Timestamp".to_owned()));
+}
+
+#[test]
+fn parse_builtins() {
+    let schema = parse("message Event { at[0]: Timestamp, id[1]: UUID }").unwrap();
+    let fields = &schema.definitions[0].fields;
+    assert_eq!(fields[0].ty, Type::Builtin(Builtin::Timestamp));
+    assert_eq!(fields[1].ty, Type::Builtin(Builtin::Uuid));
+}
+
+#[test]
+fn parse_empty() {
+    assert_eq!(parse("// nothing here\n").unwrap(), Schema::default());
+    assert!(parse("message Empty {}").unwrap().definitions[0]
+        .fields
+        .is_empty());
+}
+
+#[test]
+fn unexpected_token() {
+    assert_eq!(
+        parse_err("message Foo {\n    bar[0] uint64\n}"),
+        (
+            Kind::UnexpectedToken {
+                expected: "`:`",
+                found: "`uint64`".to_owned()
+            },
+            Position {
+                line: 2,
+                column: 12
+            }
+        )
+    );
+}
+
+#[test]
+fn unexpected_end() {
+    assert_eq!(
+        parse_err("message Foo {\n    bar[0]: uint64,\n").0,
+        Kind::UnexpectedEnd
+    );
+}
+
+#[test]
+fn unexpected_char() {
+    assert_eq!(
+        parse_err("message Foo { bar[0]: uint64; }").0,
+        Kind::UnexpectedChar { c: ';' }
+    );
+}
+
+#[test]
+fn unknown_type() {
+    assert_eq!(
+        parse_err("message Foo { bar[0]: Bar }").0,
+        Kind::UnknownType {
+            name: "Bar".to_owned()
+        }
+    );
+}
+
+#[test]
+fn duplicate_definition() {
+    assert_eq!(
+        parse_err("message Foo {}\nenum Foo {}"),
+        (
+            Kind::DuplicateDefinition {
+                name: "Foo".to_owned()
+            },
+            Position { line: 2, column: 1 }
+        )
+    );
+}
+
+#[test]
+fn duplicate_field() {
+    assert_eq!(
+        parse_err("message Foo { bar[0]: bool, bar[1]: bool }").0,
+        Kind::DuplicateField {
+            name: "bar".to_owned()
+        }
+    );
+}
+
+#[test]
+fn duplicate_tag() {
+    assert_eq!(
+        parse_err("enum Foo { bar[0]: bool, baz[0]: bool }").0,
+        Kind::DuplicateTag { tag: 0 }
+    );
+}
+
+#[test]
+fn out_of_order_tag() {
+    assert_eq!(
+        parse_err("message Foo { bar[1]: bool, baz[0]: bool }").0,
+        Kind::Order { tag: 0 }
+    );
+
+    // Enum variants may be listed in any order
+    assert!(parse("enum Foo { bar[1]: bool, baz[0]: bool }").is_ok());
+}
+
+#[test]
+fn tag_range() {
+    assert_eq!(
+        parse_err("message Foo { bar[1152921504606846976]: bool }").0,
+        Kind::TagRange {
+            tag: 1_152_921_504_606_846_976
+        }
+    );
+}
+
+#[test]
+fn invalid_options() {
+    assert_eq!(
+        parse_err("message Foo { bar[0]: uint64(max = 1) }").0,
+        Kind::Option {
+            name: "max",
+            ty: "uint64".to_owned()
+        }
+    );
+
+    assert_eq!(
+        parse_err("message Foo { bar[0]: bytes(size = 1, max = 2) }").0,
+        Kind::Option {
+            name: "size",
+            ty: "bytes".to_owned()
+        }
+    );
+
+    assert_eq!(
+        parse_err("message Foo { bar[0]: sequence<bool>(min = 2, max = 1) }").0,
+        Kind::Option {
+            name: "min",
+            ty: "sequence<bool>".to_owned()
+        }
+    );
+}
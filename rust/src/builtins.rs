@@ -2,12 +2,16 @@
 //!
 //! These are the equivalent of Protobufs' "well-known types"
 
+mod float;
+
 #[cfg(feature = "tai64")]
 mod timestamp;
 
 #[cfg(feature = "uuid")]
 mod uuid;
 
+pub use self::float::{Float32, Float64};
+
 #[cfg(feature = "tai64")]
 pub use self::timestamp::Timestamp;
 
@@ -0,0 +1,245 @@
+//! IEEE 754 floating point numbers.
+//!
+//! In Veriform these are encoded as:
+//!
+//! ```text
+//! message Float32 {
+//!     value![0]: bytes(size = 4),
+//! }
+//!
+//! message Float64 {
+//!     value![0]: bytes(size = 8),
+//! }
+//! ```
+//!
+//! where `value` is the IEEE 754 `binary32` or `binary64` representation of
+//! the number in little endian byte order.
+//!
+//! Every number has exactly one encoding, so its Verihash is the same on all
+//! platforms:
+//!
+//! - NaNs are all encoded as the canonical quiet NaN, i.e. the one with the
+//!   sign bit clear and only the most significant bit of the significand set
+//! - negative zero is encoded as (positive) zero
+//! - subnormal numbers are encoded exactly, i.e. they're never flushed to zero
+//!
+//! Decoders reject any other NaN and negative zero.
+
+use crate::{
+    decoder::{DecodeRef, Decoder},
+    digest::Digest,
+    error::{self, Error},
+    field,
+    reflect::{FieldDescriptor, FieldType, MessageDescriptor, MessageKind, Reflect},
+    Encoder, Message,
+};
+use core::convert::TryInto;
+
+/// Define a floating point builtin type wrapping the given primitive type,
+/// whose bits are the given unsigned integer type
+macro_rules! impl_float {
+    (
+        $(#[$attr:meta])*
+        $name:ident($float:ident, $bits:ident, $size:expr, $canonical_nan:expr)
+    ) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug, Default)]
+        pub struct $name($float);
+
+        impl $name {
+            /// Bits of the canonical NaN
+            pub const CANONICAL_NAN: $bits = $canonical_nan;
+
+            /// Create a new floating point number, canonicalizing NaN and
+            /// negative zero
+            pub fn new(value: $float) -> Self {
+                Self::from_bits(value.to_bits())
+            }
+
+            /// Create a floating point number from its IEEE 754 bits,
+            /// canonicalizing NaN and negative zero
+            pub fn from_bits(bits: $bits) -> Self {
+                Self($float::from_bits(bits.canonicalize()))
+            }
+
+            /// Get the value of this number
+            pub fn value(self) -> $float {
+                self.0
+            }
+
+            /// Get the (canonical) IEEE 754 bits of this number
+            pub fn to_bits(self) -> $bits {
+                self.0.to_bits().canonicalize()
+            }
+        }
+
+        impl From<$float> for $name {
+            fn from(value: $float) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for $float {
+            fn from(value: $name) -> $float {
+                value.0
+            }
+        }
+
+        /// Numbers are equal if their encodings are, i.e. NaN is equal to
+        /// itself (unlike the primitive type)
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.to_bits() == other.to_bits()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl Message for $name {
+            fn decode<D>(decoder: &mut Decoder<D>, mut input: &[u8]) -> Result<Self, Error>
+            where
+                D: Digest,
+            {
+                let bytes: &[u8] = decoder.decode_ref(0, &mut input)?;
+
+                let bits = bytes
+                    .try_into()
+                    .map($bits::from_le_bytes)
+                    .map_err(|_| decoder.annotate(error::Kind::Builtin.into()))?;
+
+                if bits.canonicalize() != bits {
+                    return Err(decoder.annotate(error::Kind::Builtin.into()));
+                }
+
+                if !input.is_empty() {
+                    return Err(decoder.annotate(error::Kind::TrailingData.into()));
+                }
+
+                Ok(Self($float::from_bits(bits)))
+            }
+
+            fn encode<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
+                let mut encoder = Encoder::new(buffer);
+                encoder.bytes(0, true, &self.to_bits().to_le_bytes())?;
+                Ok(encoder.finish())
+            }
+
+            fn encoded_len(&self) -> usize {
+                field::length::bytes(0, &[0; $size])
+            }
+        }
+
+        impl Reflect for $name {
+            const DESCRIPTOR: MessageDescriptor = MessageDescriptor {
+                name: stringify!($name),
+                kind: MessageKind::Struct,
+                fields: &[FieldDescriptor {
+                    name: "value",
+                    tag: 0,
+                    ty: FieldType::Bytes,
+                    critical: true,
+                    optional: false,
+                    size: Some($size),
+                    min: None,
+                    max: None,
+                    nested: None,
+                }],
+            };
+        }
+
+        impl Canonicalize for $bits {
+            fn canonicalize(self) -> Self {
+                let value = $float::from_bits(self);
+
+                if value.is_nan() {
+                    $name::CANONICAL_NAN
+                } else if value == 0.0 {
+                    0
+                } else {
+                    self
+                }
+            }
+        }
+    };
+}
+
+impl_float!(
+    /// IEEE 754 `binary32` (i.e. single precision) floating point number
+    Float32(f32, u32, 4, 0x7fc0_0000)
+);
+
+impl_float!(
+    /// IEEE 754 `binary64` (i.e. double precision) floating point number
+    Float64(f64, u64, 8, 0x7ff8_0000_0000_0000)
+);
+
+/// Canonicalization of the bits of floating point numbers
+trait Canonicalize {
+    /// Replace NaN with the canonical NaN and negative zero with zero
+    fn canonicalize(self) -> Self;
+}
+
+#[cfg(all(test, feature = "alloc", feature = "sha2"))]
+mod tests {
+    use super::{Float32, Float64};
+    use crate::{error, Decoder, Encoder, Message};
+
+    #[test]
+    fn round_trip() {
+        for &value in &[
+            1.5,
+            -2.25,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+            f64::INFINITY,
+        ] {
+            let float = Float64::new(value);
+            let encoded = float.encode_vec().unwrap();
+            assert_eq!(encoded.len(), float.encoded_len());
+
+            let decoded = Float64::decode(&mut Decoder::new(), &encoded).unwrap();
+            assert_eq!(decoded.value().to_bits(), value.to_bits());
+        }
+    }
+
+    #[test]
+    fn canonical_nan() {
+        let nan = Float32::from_bits(0xffc0_0001);
+        assert_eq!(nan.to_bits(), Float32::CANONICAL_NAN);
+        assert_eq!(nan, Float32::new(f32::NAN));
+
+        let encoded = Float32::from_bits(0x7fc0_0001).encode_vec().unwrap();
+        assert_eq!(encoded, Float32::new(f32::NAN).encode_vec().unwrap());
+    }
+
+    #[test]
+    fn negative_zero() {
+        let zero = Float64::new(-0.0);
+        assert_eq!(zero.to_bits(), 0);
+        assert_eq!(zero, Float64::new(0.0));
+    }
+
+    #[test]
+    fn reject_non_canonical() {
+        let mut encoded = Float64::new(0.0).encode_vec().unwrap();
+
+        // Set the sign bit (i.e. negative zero)
+        *encoded.last_mut().unwrap() = 0x80;
+
+        let err = Float64::decode(&mut Decoder::new(), &encoded).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::Builtin);
+    }
+
+    #[test]
+    fn reject_wrong_length() {
+        let mut buffer = [0u8; 16];
+        let mut encoder = Encoder::new(&mut buffer);
+        encoder.bytes(0, true, &[0, 0, 0, 0, 0, 0]).unwrap();
+        let encoded = encoder.finish();
+
+        let err = Float32::decode(&mut Decoder::new(), encoded).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::Builtin);
+        assert_eq!(err.position(), Some(encoded.len()));
+    }
+}
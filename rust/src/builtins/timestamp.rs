@@ -15,7 +15,9 @@ use crate::{
     decoder::{Decode, Decoder},
     digest::Digest,
     error::{self, Error},
-    field, Encoder, Message,
+    field,
+    reflect::{FieldDescriptor, FieldType, MessageDescriptor, MessageKind, Reflect},
+    Encoder, Message,
 };
 use core::convert::TryInto;
 
@@ -25,15 +27,11 @@ impl Message for Timestamp {
         D: Digest,
     {
         let secs: u64 = decoder.decode(0, &mut input)?;
-        let nanos: u64 = decoder.decode(1, &mut input)?;
-
-        if nanos > core::u32::MAX as u64 {
-            return Err(error::Kind::Length.into());
-        }
+        let nanos: u32 = decoder.decode(1, &mut input)?;
 
         let mut bytes = [0u8; 12];
         bytes[..8].copy_from_slice(&secs.to_le_bytes());
-        bytes[8..].copy_from_slice(&(nanos as u32).to_le_bytes());
+        bytes[8..].copy_from_slice(&nanos.to_le_bytes());
         bytes.try_into().map_err(|_| error::Kind::Builtin.into())
     }
 
@@ -51,6 +49,37 @@ impl Message for Timestamp {
     }
 }
 
+impl Reflect for Timestamp {
+    const DESCRIPTOR: MessageDescriptor = MessageDescriptor {
+        name: "Timestamp",
+        kind: MessageKind::Struct,
+        fields: &[
+            FieldDescriptor {
+                name: "secs",
+                tag: 0,
+                ty: FieldType::UInt64,
+                critical: true,
+                optional: false,
+                size: None,
+                min: None,
+                max: None,
+                nested: None,
+            },
+            FieldDescriptor {
+                name: "nanos",
+                tag: 1,
+                ty: FieldType::UInt64,
+                critical: false,
+                optional: false,
+                size: None,
+                min: None,
+                max: None,
+                nested: None,
+            },
+        ],
+    };
+}
+
 /// Convert a Timestamp timestamp to two integers
 fn tai64_to_ints(tai64n: &Timestamp) -> (u64, u32) {
     let encoded = tai64n.to_bytes();
@@ -14,7 +14,9 @@ use crate::{
     decoder::{DecodeRef, Decoder},
     digest::Digest,
     error::{self, Error},
-    field, Encoder, Message,
+    field,
+    reflect::{FieldDescriptor, FieldType, MessageDescriptor, MessageKind, Reflect},
+    Encoder, Message,
 };
 use core::convert::TryInto;
 
@@ -41,3 +43,21 @@ impl Message for Uuid {
         field::length::bytes(0, self.as_bytes())
     }
 }
+
+impl Reflect for Uuid {
+    const DESCRIPTOR: MessageDescriptor = MessageDescriptor {
+        name: "UUID",
+        kind: MessageKind::Struct,
+        fields: &[FieldDescriptor {
+            name: "value",
+            tag: 0,
+            ty: FieldType::Bytes,
+            critical: true,
+            optional: false,
+            size: Some(16),
+            min: None,
+            max: None,
+            nested: None,
+        }],
+    };
+}
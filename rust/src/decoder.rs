@@ -1,8 +1,11 @@
 //! Veriform decoder
 
+pub mod explain;
 pub(crate) mod message;
 pub mod sequence;
+pub mod stream;
 
+mod config;
 mod decodable;
 mod event;
 mod traits;
@@ -12,18 +15,22 @@ mod vint64;
 #[macro_use]
 mod trace;
 
-pub use self::traits::{Decode, DecodeRef, DecodeSeq};
+pub use self::{
+    config::{Config, MAX_DEPTH},
+    traits::{Decode, DecodeRef, DecodeSeq},
+};
 
 pub(crate) use self::{decodable::Decodable, event::Event};
 
 use crate::{
-    error::{self, Error},
+    error::{self, Error, Path, Segment},
     field::{Tag, WireType},
-    verihash::DigestOutput,
+    verihash::{DigestOutput, VerihashVersion},
     Message,
 };
+use core::convert::TryFrom;
 use digest::Digest;
-use heapless::consts::U16;
+use heapless::consts::U15;
 
 /// Veriform decoder.
 ///
@@ -31,32 +38,118 @@ use heapless::consts::U16;
 /// computation.
 ///
 /// It's intended to be used in conjunction with the [`Message`] trait.
+///
+/// By default digests are computed using [`VerihashVersion::V0`]. Use
+/// [`Decoder::with_verihash_version`] to select another version, or
+/// [`Decoder::with_config`] to also limit the messages the decoder accepts.
+///
+/// Errors returned while decoding are annotated with the absolute position
+/// and [`Path`] of the field in which they occurred.
+///
+/// Nested messages and sequences are decoded within scopes which finish them
+/// when they end (e.g. [`sequence::Iter`] finishes its sequence when it's
+/// dropped). Errors which occur while finishing a scope that can't be
+/// returned directly, such as a sequence iterator dropped before all of its
+/// elements were decoded, are recorded and returned from all subsequent
+/// calls, including [`Decoder::fill_digest`].
 pub struct Decoder<D: Digest> {
-    /// Stack of message decoders (max nesting depth 16)
-    stack: heapless::Vec<message::Decoder<D>, U16>,
+    /// Decoder configuration
+    config: Config,
+
+    /// Toplevel message being decoded
+    root: Frame<D>,
+
+    /// Stack of nested message decoders (at most [`MAX_DEPTH`] including the
+    /// toplevel message)
+    stack: heapless::Vec<Frame<D>, U15>,
+
+    /// Error which occurred while finishing a nested message or sequence
+    /// which couldn't be returned at the time
+    deferred: Option<Error>,
+
+    /// Total number of fields decoded so far
+    fields: usize,
+}
+
+/// Message being decoded, along with its location in the toplevel message
+struct Frame<D: Digest> {
+    /// Message decoder
+    decoder: message::Decoder<D>,
+
+    /// Offset of the start of the message body within the toplevel message
+    offset: usize,
+
+    /// Tag of the field containing this message (`None` for the toplevel
+    /// message)
+    tag: Option<Tag>,
+
+    /// Index of this message within a sequence (if it's a sequence element)
+    index: Option<usize>,
 
-    /// Sequence decoder if we're presently decoding a sequence
-    // TODO(tarcieri): support nested sequences?
+    /// Decoder for the sequence presently being decoded in this message
+    /// (if any)
     seq_decoder: Option<sequence::Decoder<D>>,
 }
 
+impl<D> Frame<D>
+where
+    D: Digest,
+{
+    /// Create a new frame for a message at the given offset
+    fn new(version: VerihashVersion, offset: usize) -> Self {
+        Self {
+            decoder: message::Decoder::with_verihash_version(version),
+            offset,
+            tag: None,
+            index: None,
+            seq_decoder: None,
+        }
+    }
+}
+
 impl<D> Decoder<D>
 where
     D: Digest,
 {
     /// Initialize decoder
     pub fn new() -> Self {
-        let mut stack = heapless::Vec::new();
-        stack.push(message::Decoder::new()).unwrap();
+        Self::with_verihash_version(VerihashVersion::default())
+    }
+
+    /// Initialize decoder which computes the given version of Verihash
+    pub fn with_verihash_version(version: VerihashVersion) -> Self {
+        Self::with_config(Config {
+            verihash_version: version,
+            ..Config::default()
+        })
+    }
+
+    /// Initialize decoder with the given configuration
+    pub fn with_config(config: Config) -> Self {
         Decoder {
-            stack,
-            seq_decoder: None,
+            config,
+            root: Frame::new(config.verihash_version, 0),
+            stack: heapless::Vec::new(),
+            deferred: None,
+            fields: 0,
         }
     }
 
+    /// Get the configuration of this decoder
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Get the version of Verihash computed by this decoder
+    pub fn verihash_version(&self) -> VerihashVersion {
+        self.config.verihash_version
+    }
+
     /// Fill the provided slice with the digest of the message if it fits
     // TODO(tarcieri): find a better way to handle generic digest sizes
     pub fn fill_digest(&mut self, output: &mut [u8]) -> Result<(), Error> {
+        self.check_deferred()?;
+
         let digest = self
             .peek()
             .compute_digest()?
@@ -70,56 +163,272 @@ where
         Ok(())
     }
 
+    /// Annotate an error which occurred while decoding the message presently
+    /// being decoded with its current position and path.
+    ///
+    /// Errors returned by this decoder are annotated automatically. This
+    /// method is intended for errors raised by [`Message::decode`]
+    /// implementations themselves, e.g. unexpected trailing data. Errors
+    /// which have already been annotated are returned unchanged.
+    pub fn annotate(&self, err: Error) -> Error {
+        self.locate(err, self.position(), None, None)
+    }
+
+    /// Annotate an error with the location of the field with the given tag,
+    /// which begins at the current position
+    pub(crate) fn annotate_field(&self, err: Error, tag: Tag) -> Error {
+        self.annotate_field_at(err, self.position(), tag)
+    }
+
+    /// Annotate an error with the location of the field with the given tag,
+    /// which begins at the given position
+    pub(crate) fn annotate_field_at(&self, err: Error, position: usize, tag: Tag) -> Error {
+        self.locate(err, position, Some(tag), None)
+    }
+
     /// Get the depth of the pushdown stack
     #[cfg(feature = "log")]
     pub(crate) fn depth(&self) -> usize {
-        self.stack.len()
+        self.stack.len() + 1
     }
 
-    /// Push a new message decoder down onto the stack
-    fn push(&mut self) -> Result<(), Error> {
+    /// Get the current position within the toplevel message
+    pub(crate) fn position(&self) -> usize {
+        let frame = self.stack.last().unwrap_or(&self.root);
+        frame.offset.saturating_add(frame.decoder.position())
+    }
+
+    /// Annotate an error with the given position and the path to the given
+    /// field (and sequence element) of the message presently being decoded
+    fn locate(&self, err: Error, position: usize, tag: Option<Tag>, index: Option<usize>) -> Error {
+        let mut path = Path::new();
+
+        // The toplevel message isn't contained in a field
+        let segments = self
+            .stack
+            .iter()
+            .map(|frame| (frame.tag, frame.index))
+            .chain(Some((tag, index)));
+
+        for (tag, index) in segments {
+            if let Some(tag) = tag {
+                path.push(Segment::Field(tag));
+            }
+
+            if let Some(index) = index {
+                path.push(Segment::Element(index));
+            }
+        }
+
+        err.locate(position, path)
+    }
+
+    /// Decode a field of the message presently being decoded using the
+    /// provided function, annotating any errors which occur with the field's
+    /// location
+    fn decode_field<T, F>(&mut self, tag: Tag, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut message::Decoder<D>) -> Result<T, Error>,
+    {
+        let position = self.position();
+
+        let result = self
+            .check_deferred()
+            .and_then(|_| self.count_field())
+            .and_then(|_| f(self.peek()))
+            .and_then(|value| {
+                // The length of the toplevel message isn't known in advance,
+                // so it's checked as its fields are consumed
+                if self.stack.is_empty() {
+                    self.config.check_message_length(self.position())?;
+                }
+
+                Ok(value)
+            });
+
+        result.map_err(|e| self.locate(e, position, Some(tag), None))
+    }
+
+    /// Count a field towards the total number of fields in the message
+    fn count_field(&mut self) -> Result<(), Error> {
+        self.fields = self.fields.saturating_add(1);
+        self.config.check_fields(self.fields)
+    }
+
+    /// Push a new message decoder for a message whose body begins at the
+    /// given offset down onto the stack
+    fn push(&mut self, offset: usize, tag: Tag, index: Option<usize>) -> Result<(), Error> {
+        // Depth of the new message, including the toplevel message
+        self.config.check_depth(self.stack.len() + 2)?;
+
+        let frame = Frame {
+            tag: Some(tag),
+            index,
+            ..Frame::new(self.config.verihash_version, offset)
+        };
+
         self.stack
-            .push(message::Decoder::new())
-            .map_err(|_| error::Kind::NestingDepth.into())
+            .push(frame)
+            .map_err(|_| error::Kind::NestingDepth { max: MAX_DEPTH }.into())
     }
 
-    /// Pop the message decoder from the stack when we've finished a message.
+    /// Pop the nested message decoder from the stack when we've finished a
+    /// message.
     ///
-    /// Returns a digest of the nested message if message hashing is enabled.
-    ///
-    /// Panics if the decoder stack underflows.
-    // TODO(tarcieri): panic-free higher-level API, possibly RAII-based?
-    fn pop(&mut self) -> Option<DigestOutput<D>> {
-        self.stack.pop().unwrap().compute_digest().unwrap()
+    /// Returns a digest of the nested message if message hashing is enabled,
+    /// or an error if the message was incomplete or no nested message is
+    /// being decoded.
+    fn pop(&mut self) -> Result<Option<DigestOutput<D>>, Error> {
+        let mut frame = self.stack.pop().ok_or(error::Kind::Failed)?;
+        frame.decoder.compute_digest()
+    }
+
+    /// Annotate an error which occurred while decoding the message on the
+    /// top of the stack, then discard its decoder
+    fn discard(&mut self, err: Error) -> Error {
+        let err = self.annotate(err);
+        self.stack.pop();
+        err
     }
 
     /// Peek at the message decoder on the top of the stack
     fn peek(&mut self) -> &mut message::Decoder<D> {
-        self.stack.last_mut().unwrap()
+        &mut self.peek_frame().decoder
     }
 
-    /// Push a sequence decoder
-    // TODO(tarcieri): support nested sequences?
+    /// Peek at the frame of the message on the top of the stack
+    fn peek_frame(&mut self) -> &mut Frame<D> {
+        match self.stack.last_mut() {
+            Some(frame) => frame,
+            None => &mut self.root,
+        }
+    }
+
+    /// Push a sequence decoder for a sequence in the message on the top of
+    /// the stack.
+    ///
+    /// Each message has its own sequence decoder, so messages contained in a
+    /// sequence can themselves contain sequences (e.g. recursive types).
     fn push_seq(&mut self, wire_type: WireType, length: usize) -> Result<(), Error> {
-        if self.seq_decoder.is_none() {
-            self.seq_decoder = Some(sequence::Decoder::new(wire_type, length));
+        let version = self.config.verihash_version;
+        let frame = self.peek_frame();
+
+        if frame.seq_decoder.is_none() {
+            frame.seq_decoder = Some(sequence::Decoder::new(version, wire_type, length));
             Ok(())
         } else {
             Err(error::Kind::NestedSequence.into())
         }
     }
 
-    /// Pop the sequence decoder.
+    /// Pop the sequence decoder when we've finished a sequence.
     ///
-    /// Panics if the decoder stack underflows.
-    // TODO(tarcieri): panic-free higher-level API, possibly RAII-based?
-    fn pop_seq(&mut self) -> Option<DigestOutput<D>> {
-        self.seq_decoder.take().unwrap().compute_digest().unwrap()
+    /// Returns a digest of the sequence if hashing is enabled, or an error if
+    /// the sequence was incomplete or no sequence is being decoded.
+    fn pop_seq(&mut self) -> Result<Option<DigestOutput<D>>, Error> {
+        let seq_decoder = self
+            .peek_frame()
+            .seq_decoder
+            .take()
+            .ok_or(error::Kind::Failed)?;
+
+        if !seq_decoder.is_complete() {
+            return Err(error::Kind::IncompleteSequence {
+                remaining: seq_decoder.remaining(),
+            }
+            .into());
+        }
+
+        seq_decoder.compute_digest()
     }
 
-    /// Peek at the sequence decoder.
-    fn peek_seq(&mut self) -> &mut sequence::Decoder<D> {
-        self.seq_decoder.as_mut().unwrap()
+    /// Discard the sequence decoder after an error
+    fn discard_seq(&mut self) {
+        self.peek_frame().seq_decoder = None;
+    }
+
+    /// Peek at the sequence decoder
+    fn peek_seq(&mut self) -> Result<&mut sequence::Decoder<D>, Error> {
+        self.peek_frame()
+            .seq_decoder
+            .as_mut()
+            .ok_or_else(|| error::Kind::Failed.into())
+    }
+
+    /// Record an error which occurred while finishing a nested message or
+    /// sequence where it couldn't be returned (i.e. in a `Drop` handler), or
+    /// which causes a sequence to be discarded without being hashed.
+    ///
+    /// Only the first such error is recorded.
+    fn defer(&mut self, err: Error) {
+        if self.deferred.is_none() {
+            self.deferred = Some(err);
+        }
+    }
+
+    /// Return the error recorded by [`Decoder::defer`] (if any)
+    fn check_deferred(&self) -> Result<(), Error> {
+        match self.deferred {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Decode a nested message with the given tag, using the provided
+    /// function to decode its body and hashing the resulting digest
+    pub(crate) fn decode_nested<'i, T, F>(
+        &mut self,
+        tag: Tag,
+        input: &mut &'i [u8],
+        f: F,
+    ) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self, &'i [u8]) -> Result<T, Error>,
+    {
+        let position = self.position();
+        let config = self.config;
+        let msg_bytes = self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::Message)?;
+            let msg_bytes = decoder.decode_message(input)?;
+            config.check_message_length(msg_bytes.len())?;
+            Ok(msg_bytes)
+        })?;
+
+        let offset = self.position().saturating_sub(msg_bytes.len());
+        self.push(offset, tag, None)
+            .map_err(|e| self.locate(e, position, Some(tag), None))?;
+
+        let msg = f(self, msg_bytes).map_err(|e| self.discard(e))?;
+
+        self.pop()
+            .and_then(|digest| match digest {
+                Some(digest) => self.peek().hash_message_digest(tag, &digest),
+                None => Ok(()),
+            })
+            .map_err(|e| self.locate(e, position, Some(tag), None))?;
+
+        Ok(msg)
+    }
+
+    /// Begin decoding a sequence with the given tag whose elements are of
+    /// the given wire type
+    pub(crate) fn decode_seq_of<'a, 'b, T: ?Sized>(
+        &'a mut self,
+        tag: Tag,
+        wire_type: WireType,
+        input: &mut &'b [u8],
+    ) -> Result<sequence::Iter<'a, 'b, T, D>, Error> {
+        let position = self.position();
+        let seq_bytes = self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::Sequence)?;
+            decoder.decode_sequence(wire_type, input)
+        })?;
+
+        let offset = self.position().saturating_sub(seq_bytes.len());
+        self.push_seq(wire_type, seq_bytes.len())
+            .map_err(|e| self.locate(e, position, Some(tag), None))?;
+
+        Ok(sequence::Iter::new(self, tag, seq_bytes, offset))
     }
 }
 
@@ -141,17 +450,22 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: msg?", tag);
 
-        self.peek().expect_header(input, tag, WireType::Message)?;
-        let msg_bytes = self.peek().decode_message(input)?;
-
-        self.push()?;
-        let msg = M::decode(self, msg_bytes)?;
+        self.decode_nested(tag, input, M::decode)
+    }
+}
 
-        if let Some(digest) = self.pop() {
-            self.peek().hash_message_digest(tag, &digest)?;
-        }
+impl<D> Decode<bool> for Decoder<D>
+where
+    D: Digest,
+{
+    fn decode(&mut self, tag: Tag, input: &mut &[u8]) -> Result<bool, Error> {
+        #[cfg(feature = "log")]
+        begin!(self, "[{}]: bool?", tag);
 
-        Ok(msg)
+        self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::True)?;
+            decoder.decode_bool(input)
+        })
     }
 }
 
@@ -163,8 +477,10 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: uint64?", tag);
 
-        self.peek().expect_header(input, tag, WireType::UInt64)?;
-        self.peek().decode_uint64(input)
+        self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::UInt64)?;
+            decoder.decode_uint64(input)
+        })
     }
 }
 
@@ -176,11 +492,40 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: sint64?", tag);
 
-        self.peek().expect_header(input, tag, WireType::SInt64)?;
-        self.peek().decode_sint64(input)
+        self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::SInt64)?;
+            decoder.decode_sint64(input)
+        })
     }
 }
 
+/// Impl [`Decode`] for integer types narrower than the 64-bit type of the
+/// given wire type, ensuring decoded values are in range
+macro_rules! impl_decode_narrow {
+    ($wire_type:ident, $decode:ident, $($int:ty),+) => {
+        $(
+            impl<D> Decode<$int> for Decoder<D>
+            where
+                D: Digest,
+            {
+                fn decode(&mut self, tag: Tag, input: &mut &[u8]) -> Result<$int, Error> {
+                    #[cfg(feature = "log")]
+                    begin!(self, "[{}]: {}?", tag, stringify!($int));
+
+                    self.decode_field(tag, |decoder| {
+                        decoder.expect_header(input, tag, WireType::$wire_type)?;
+                        let value = decoder.$decode(input)?;
+                        <$int>::try_from(value).map_err(|_| error::Kind::Range { tag }.into())
+                    })
+                }
+            }
+        )+
+    };
+}
+
+impl_decode_narrow!(UInt64, decode_uint64, u8, u16, u32, usize);
+impl_decode_narrow!(SInt64, decode_sint64, i8, i16, i32);
+
 impl<D> DecodeRef<[u8]> for Decoder<D>
 where
     D: Digest,
@@ -189,8 +534,13 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: bytes?", tag);
 
-        self.peek().expect_header(input, tag, WireType::Bytes)?;
-        self.peek().decode_bytes(input)
+        let config = self.config;
+        self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::Bytes)?;
+            let bytes = decoder.decode_bytes(input)?;
+            config.check_value_length(WireType::Bytes, bytes.len())?;
+            Ok(bytes)
+        })
     }
 }
 
@@ -202,8 +552,13 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: string?", tag);
 
-        self.peek().expect_header(input, tag, WireType::String)?;
-        self.peek().decode_string(input)
+        let config = self.config;
+        self.decode_field(tag, |decoder| {
+            decoder.expect_header(input, tag, WireType::String)?;
+            let string = decoder.decode_string(input)?;
+            config.check_value_length(WireType::String, string.len())?;
+            Ok(string)
+        })
     }
 }
 
@@ -220,11 +575,7 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: seq<msg>?", tag);
 
-        self.peek().expect_header(input, tag, WireType::Sequence)?;
-        let seq_bytes = self.peek().decode_sequence(WireType::Message, input)?;
-        self.push_seq(WireType::Message, seq_bytes.len())?;
-
-        Ok(sequence::Iter::new(self, tag, seq_bytes))
+        self.decode_seq_of(tag, WireType::Message, input)
     }
 }
 
@@ -240,11 +591,7 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: seq<uint64>?", tag);
 
-        self.peek().expect_header(input, tag, WireType::Sequence)?;
-        let seq_bytes = self.peek().decode_sequence(WireType::UInt64, input)?;
-        self.push_seq(WireType::UInt64, seq_bytes.len())?;
-
-        Ok(sequence::Iter::new(self, tag, seq_bytes))
+        self.decode_seq_of(tag, WireType::UInt64, input)
     }
 }
 
@@ -260,18 +607,75 @@ where
         #[cfg(feature = "log")]
         begin!(self, "[{}]: seq<sint64>?", tag);
 
-        self.peek().expect_header(input, tag, WireType::Sequence)?;
-        let seq_bytes = self.peek().decode_sequence(WireType::SInt64, input)?;
-        self.push_seq(WireType::SInt64, seq_bytes.len())?;
+        self.decode_seq_of(tag, WireType::SInt64, input)
+    }
+}
+
+impl<D> DecodeSeq<[u8], D> for Decoder<D>
+where
+    D: Digest,
+{
+    fn decode_seq<'a, 'b>(
+        &'a mut self,
+        tag: Tag,
+        input: &mut &'b [u8],
+    ) -> Result<sequence::Iter<'a, 'b, [u8], D>, Error> {
+        #[cfg(feature = "log")]
+        begin!(self, "[{}]: seq<bytes>?", tag);
+
+        self.decode_seq_of(tag, WireType::Bytes, input)
+    }
+}
 
-        Ok(sequence::Iter::new(self, tag, seq_bytes))
+impl<D> DecodeSeq<str, D> for Decoder<D>
+where
+    D: Digest,
+{
+    fn decode_seq<'a, 'b>(
+        &'a mut self,
+        tag: Tag,
+        input: &mut &'b [u8],
+    ) -> Result<sequence::Iter<'a, 'b, str, D>, Error> {
+        #[cfg(feature = "log")]
+        begin!(self, "[{}]: seq<string>?", tag);
+
+        self.decode_seq_of(tag, WireType::String, input)
     }
 }
 
-#[cfg(all(test, feature = "sha2"))]
+/// Peek at the header of the next field in the input without consuming it
+#[cfg(any(feature = "alloc", feature = "veriform_derive"))]
+pub(crate) fn peek_header(mut input: &[u8]) -> Result<crate::field::Header, Error> {
+    Ok(crate::field::Header::from(::vint64::decode(&mut input)?))
+}
+
+/// Peek at the element wire type of the sequence field at the start of the
+/// input without consuming it
+#[cfg(feature = "alloc")]
+pub(crate) fn peek_sequence_type(mut input: &[u8]) -> Result<WireType, Error> {
+    ::vint64::decode(&mut input)?;
+    Ok(WireType::from_unmasked(::vint64::decode(&mut input)?))
+}
+
+#[cfg(all(test, feature = "alloc", feature = "sha2"))]
 mod tests {
-    use super::{Decode, DecodeRef};
-    use crate::Decoder;
+    use super::{Config, Decode, DecodeRef, DecodeSeq};
+    use crate::{decoder::sequence, error, field::WireType, Decoder};
+    use alloc::string::ToString;
+
+    #[test]
+    fn decode_bool() {
+        let input = [130, 10, 198, 10];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::new();
+
+        let value: bool = decoder.decode(42, &mut input_ref).unwrap();
+        assert!(!value);
+
+        let value: bool = decoder.decode(43, &mut input_ref).unwrap();
+        assert!(value);
+        assert!(input_ref.is_empty());
+    }
 
     #[test]
     fn decode_uint64() {
@@ -312,4 +716,227 @@ mod tests {
         assert_eq!(string, "baz");
         assert!(input_ref.is_empty());
     }
+
+    #[test]
+    fn decode_empty_bytes() {
+        let input = [73, 1];
+        let mut input_ref = &input[..];
+
+        let bytes: &[u8] = Decoder::new().decode_ref(2, &mut input_ref).unwrap();
+        assert!(bytes.is_empty());
+        assert!(input_ref.is_empty());
+    }
+
+    #[test]
+    fn decode_string_seq() {
+        let input = [79, 86, 2, 7, 102, 111, 111, 1, 7, 98, 97, 122];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::new();
+
+        let mut seq: sequence::Iter<'_, '_, str, _> =
+            decoder.decode_seq(2, &mut input_ref).unwrap();
+
+        for &s in &["foo", "", "baz"] {
+            assert_eq!(seq.next().unwrap().unwrap(), s);
+        }
+
+        assert!(seq.next().is_none());
+        assert!(input_ref.is_empty());
+    }
+
+    #[test]
+    fn decode_nested_error_location() {
+        // Field 1: message containing field 2: "bytes"
+        let input = [45, 15, 73, 11, 98, 121, 116, 101, 115];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::new();
+
+        let err = decoder
+            .decode_nested(1, &mut input_ref, |decoder, mut body| {
+                Decode::<u64>::decode(decoder, 2, &mut body)
+            })
+            .unwrap_err();
+
+        assert_eq!(
+            err.kind(),
+            error::Kind::UnexpectedWireType {
+                actual: WireType::Bytes,
+                wanted: WireType::UInt64
+            }
+        );
+        assert_eq!(err.position(), Some(2));
+        assert_eq!(
+            err.to_string(),
+            "unexpected wire type: actual=Bytes wanted=UInt64 position=2 path=1.2"
+        );
+    }
+
+    #[test]
+    fn decode_seq_error_location() {
+        // Field 2: sequence of uint64s whose second element is truncated
+        let input = [79, 69, 3, 2];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::new();
+
+        let mut seq: sequence::Iter<'_, '_, u64, _> =
+            decoder.decode_seq(2, &mut input_ref).unwrap();
+
+        assert_eq!(seq.next().unwrap().unwrap(), 1);
+
+        let err = seq.next().unwrap().unwrap_err();
+        assert_eq!(err.position(), Some(3));
+        assert_eq!(err.path().unwrap().to_string(), "2.sequence[1]");
+    }
+
+    #[test]
+    fn decode_seq_dropped_early() {
+        // Field 2: sequence of uint64s: 1, 2
+        let input = [79, 69, 3, 5];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::new();
+
+        let mut seq: sequence::Iter<'_, '_, u64, _> =
+            decoder.decode_seq(2, &mut input_ref).unwrap();
+
+        assert_eq!(seq.next().unwrap().unwrap(), 1);
+        drop(seq);
+
+        let err = decoder.fill_digest(&mut [0u8; 32]).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::IncompleteSequence { remaining: 1 });
+        assert_eq!(err.position(), Some(3));
+        assert_eq!(err.path().unwrap().to_string(), "2");
+
+        // Subsequent fields also report the error
+        let err = Decode::<u64>::decode(&mut decoder, 3, &mut input_ref).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::IncompleteSequence { remaining: 1 });
+    }
+
+    #[test]
+    fn decode_empty_seq() {
+        let input = [79, 5];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::new();
+
+        let seq: sequence::Iter<'_, '_, u64, _> = decoder.decode_seq(2, &mut input_ref).unwrap();
+        assert_eq!(seq.count(), 0);
+        assert!(input_ref.is_empty());
+    }
+
+    #[test]
+    fn limit_depth() {
+        // Field 1: message containing field 2: "bytes"
+        let input = [45, 15, 73, 11, 98, 121, 116, 101, 115];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::with_config(Config {
+            max_depth: 1,
+            ..Config::default()
+        });
+
+        let err = decoder
+            .decode_nested(1, &mut input_ref, |decoder, mut body| {
+                DecodeRef::<[u8]>::decode_ref(decoder, 2, &mut body)
+            })
+            .unwrap_err();
+
+        assert_eq!(err.kind(), error::Kind::NestingDepth { max: 1 });
+        assert_eq!(err.position(), Some(0));
+    }
+
+    #[test]
+    fn limit_message_length() {
+        // Field 1: message containing field 2: "bytes"
+        let input = [45, 15, 73, 11, 98, 121, 116, 101, 115];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::with_config(Config {
+            max_message_length: 6,
+            ..Config::default()
+        });
+
+        let err = decoder
+            .decode_nested(1, &mut input_ref, |decoder, mut body| {
+                DecodeRef::<[u8]>::decode_ref(decoder, 2, &mut body)
+            })
+            .unwrap_err();
+
+        assert_eq!(err.kind(), error::Kind::MessageLength { length: 7, max: 6 });
+    }
+
+    #[test]
+    fn limit_bytes_length() {
+        let input = [73, 11, 98, 121, 116, 101, 115];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::with_config(Config {
+            max_bytes_length: 4,
+            ..Config::default()
+        });
+
+        let err = DecodeRef::<[u8]>::decode_ref(&mut decoder, 2, &mut input_ref).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            error::Kind::ValueLength {
+                wire_type: WireType::Bytes,
+                length: 5,
+                max: 4
+            }
+        );
+        assert_eq!(err.path().unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn limit_sequence_elements() {
+        // Field 2: sequence of uint64s: 1, 2
+        let input = [79, 69, 3, 5];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::with_config(Config {
+            max_sequence_elements: 1,
+            ..Config::default()
+        });
+
+        let mut seq: sequence::Iter<'_, '_, u64, _> =
+            decoder.decode_seq(2, &mut input_ref).unwrap();
+
+        assert_eq!(seq.next().unwrap().unwrap(), 1);
+
+        let err = seq.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), error::Kind::SequenceElements { max: 1 });
+        assert_eq!(err.path().unwrap().to_string(), "2.sequence[1]");
+    }
+
+    #[test]
+    fn failed_sequence_poisons_digest() {
+        // Field 2: sequence of uint64s: 1, 2
+        let input = [79, 69, 3, 5];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::with_config(Config {
+            max_sequence_elements: 1,
+            ..Config::default()
+        });
+
+        // Skipping the element which failed to decode mustn't allow a digest
+        // which omits the sequence to be computed
+        let seq: sequence::Iter<'_, '_, u64, _> = decoder.decode_seq(2, &mut input_ref).unwrap();
+        assert_eq!(seq.filter_map(Result::ok).count(), 1);
+
+        let mut digest = [0u8; 32];
+        let err = decoder.fill_digest(&mut digest).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::SequenceElements { max: 1 });
+        assert_eq!(err.path().unwrap().to_string(), "2.sequence[1]");
+    }
+
+    #[test]
+    fn limit_fields() {
+        let input = [130, 10, 198, 10];
+        let mut input_ref = &input[..];
+        let mut decoder = Decoder::with_config(Config {
+            max_fields: 1,
+            ..Config::default()
+        });
+
+        let value: bool = decoder.decode(42, &mut input_ref).unwrap();
+        assert!(!value);
+
+        let err = Decode::<bool>::decode(&mut decoder, 43, &mut input_ref).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::FieldCount { max: 1 });
+        assert_eq!(err.position(), Some(2));
+    }
 }
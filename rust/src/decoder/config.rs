@@ -0,0 +1,197 @@
+//! Decoder configuration
+
+use crate::{
+    error::{self, Error},
+    field::WireType,
+    verihash::VerihashVersion,
+};
+
+/// Maximum nesting depth supported by the decoders, including the toplevel
+/// message
+pub const MAX_DEPTH: usize = 16;
+
+/// Decoder configuration, including limits on the messages the decoder will
+/// accept.
+///
+/// By default only nesting depth is limited (to [`MAX_DEPTH`]). Decoders
+/// exposed to untrusted input should set the other limits as appropriate
+/// for the messages they expect, using the builder methods:
+///
+/// ```
+/// use veriform::decoder::Config;
+///
+/// let config = Config::default()
+///     .max_depth(8)
+///     .max_message_length(65536)
+///     .max_fields(1024);
+/// ```
+///
+/// More limits may be added in future versions, so this struct can only be
+/// constructed using [`Default`] (and modified using the builder methods).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Config {
+    /// Maximum nesting depth of messages, including the toplevel message.
+    ///
+    /// Sequences don't count towards the depth (for both the slice and the
+    /// streaming decoder), only the messages containing them and contained
+    /// in them do.
+    ///
+    /// This is capped at [`MAX_DEPTH`], the depth the decoders store their
+    /// state for inline: larger values are treated as [`MAX_DEPTH`], so
+    /// recursive types can't be nested more deeply than that.
+    pub max_depth: usize,
+
+    /// Maximum length of a message in bytes, including nested messages
+    pub max_message_length: usize,
+
+    /// Maximum length of a `bytes` value
+    pub max_bytes_length: usize,
+
+    /// Maximum length of a `string` value
+    pub max_string_length: usize,
+
+    /// Maximum number of elements in a sequence
+    pub max_sequence_elements: usize,
+
+    /// Maximum total number of fields in a message, including the fields of
+    /// all nested messages
+    pub max_fields: usize,
+
+    /// Version of Verihash to compute
+    pub verihash_version: VerihashVersion,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_message_length: usize::MAX,
+            max_bytes_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_sequence_elements: usize::MAX,
+            max_fields: usize::MAX,
+            verihash_version: VerihashVersion::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Set the maximum nesting depth of messages, including the toplevel
+    /// message (capped at [`MAX_DEPTH`])
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Set the maximum length of a message in bytes, including nested
+    /// messages
+    pub fn max_message_length(mut self, max: usize) -> Self {
+        self.max_message_length = max;
+        self
+    }
+
+    /// Set the maximum length of a `bytes` value
+    pub fn max_bytes_length(mut self, max: usize) -> Self {
+        self.max_bytes_length = max;
+        self
+    }
+
+    /// Set the maximum length of a `string` value
+    pub fn max_string_length(mut self, max: usize) -> Self {
+        self.max_string_length = max;
+        self
+    }
+
+    /// Set the maximum number of elements in a sequence
+    pub fn max_sequence_elements(mut self, max: usize) -> Self {
+        self.max_sequence_elements = max;
+        self
+    }
+
+    /// Set the maximum total number of fields in a message, including the
+    /// fields of all nested messages
+    pub fn max_fields(mut self, max: usize) -> Self {
+        self.max_fields = max;
+        self
+    }
+
+    /// Set the version of Verihash to compute
+    pub fn verihash_version(mut self, version: VerihashVersion) -> Self {
+        self.verihash_version = version;
+        self
+    }
+
+    /// Ensure the given nesting depth (including the toplevel message) is
+    /// allowed
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<(), Error> {
+        let max = self.max_depth.min(MAX_DEPTH);
+
+        if depth > max {
+            Err(error::Kind::NestingDepth { max }.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ensure a message of the given length is allowed
+    pub(crate) fn check_message_length(&self, length: usize) -> Result<(), Error> {
+        if length > self.max_message_length {
+            Err(error::Kind::MessageLength {
+                length,
+                max: self.max_message_length,
+            }
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ensure a `bytes` or `string` value of the given length is allowed
+    pub(crate) fn check_value_length(
+        &self,
+        wire_type: WireType,
+        length: usize,
+    ) -> Result<(), Error> {
+        let max = match wire_type {
+            WireType::Bytes => self.max_bytes_length,
+            WireType::String => self.max_string_length,
+            _ => return Ok(()),
+        };
+
+        if length > max {
+            Err(error::Kind::ValueLength {
+                wire_type,
+                length,
+                max,
+            }
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ensure a sequence may contain the given number of elements
+    pub(crate) fn check_sequence_elements(&self, count: usize) -> Result<(), Error> {
+        if count > self.max_sequence_elements {
+            Err(error::Kind::SequenceElements {
+                max: self.max_sequence_elements,
+            }
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ensure a message may contain the given total number of fields
+    pub(crate) fn check_fields(&self, count: usize) -> Result<(), Error> {
+        if count > self.max_fields {
+            Err(error::Kind::FieldCount {
+                max: self.max_fields,
+            }
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -23,6 +23,18 @@ pub(crate) trait Decodable {
         input: &mut &'a [u8],
     ) -> Result<&'a [u8], Error>;
 
+    /// Decode an expected `bool`, returning an error for anything else
+    fn decode_bool(&mut self, input: &mut &[u8]) -> Result<bool, Error> {
+        match self.decode(input)? {
+            Some(Event::Bool(value)) => Ok(value),
+            _ => Err(error::Kind::Decode {
+                element: Element::Value,
+                wire_type: WireType::True,
+            }
+            .into()),
+        }
+    }
+
     /// Decode an expected `uint64`, returning an error for anything else
     fn decode_uint64(&mut self, input: &mut &[u8]) -> Result<u64, Error> {
         match self.decode(input)? {
@@ -87,6 +99,11 @@ pub(crate) trait Decodable {
             }
         };
 
+        // Empty sequences have no body
+        if length == 0 {
+            return Ok(&[]);
+        }
+
         match self.decode(input)? {
             Some(Event::ValueChunk {
                 bytes, remaining, ..
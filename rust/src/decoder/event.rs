@@ -1,6 +1,13 @@
 //! Events emitted by Veriform's decoder
 
-use crate::field::{Header, WireType};
+use crate::{
+    error::{self, Error},
+    field::{Header, WireType},
+    message::Element,
+};
+
+/// Bit of a sequence header which is reserved and must be zero
+const SEQUENCE_HEADER_RESERVED_BIT: u64 = 0b1000;
 
 /// Events emitted by Veriform's decoder
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -47,3 +54,22 @@ pub(crate) enum Event<'a> {
         length: usize,
     },
 }
+
+impl<'a> Event<'a> {
+    /// Decode a sequence header event from the given `vint64` value
+    pub fn sequence_header(value: u64) -> Result<Self, Error> {
+        // Reject the reserved bit to ensure the encoding is canonical
+        if value & SEQUENCE_HEADER_RESERVED_BIT != 0 {
+            return Err(error::Kind::Decode {
+                element: Element::SequenceHeader,
+                wire_type: WireType::Sequence,
+            }
+            .into());
+        }
+
+        Ok(Event::SequenceHeader {
+            wire_type: WireType::from_unmasked(value),
+            length: (value >> 4) as usize,
+        })
+    }
+}
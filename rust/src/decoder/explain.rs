@@ -0,0 +1,366 @@
+//! Wire-level explanation of encoded messages.
+//!
+//! [`Explain`] walks an encoded message using the streaming decoder (and
+//! therefore the same message and sequence decoder state machines used to
+//! decode messages), producing an [`Entry`] describing how each range of
+//! bytes in the message was interpreted: every field header, length prefix,
+//! sequence header, integer value and chunk of a value.
+//!
+//! Entries are structured data, and also impl `Display` as one line of an
+//! annotated hexdump, indented by nesting depth:
+//!
+//! ```text
+//!      0..1      field header: tag=1 critical=false wire_type=message | 2d
+//!      1..2      length prefix: wire_type=message length=3 | 07
+//!      2..4        field header: tag=24 critical=false wire_type=uint64 | 0a 06
+//!      4..5        uint64 value: 42 | 55
+//!      5..5      end of message
+//! ```
+
+use super::{
+    stream::{Decoder, Event},
+    Config,
+};
+use crate::{
+    error::Error,
+    field::{Header, WireType},
+};
+use core::{
+    fmt::{self, Display},
+    ops::Range,
+};
+use digest::Digest;
+
+/// Iterator over the [`Entry`]s explaining an encoded message.
+///
+/// Yields an error (and then stops) if the message is malformed, including
+/// if it's truncated.
+pub struct Explain<'a, D: Digest> {
+    /// Streaming decoder
+    decoder: Decoder<D>,
+
+    /// Encoded message
+    message: &'a [u8],
+
+    /// Remaining input which hasn't been consumed by the decoder
+    remaining: &'a [u8],
+
+    /// Has the decoder finished (or failed)?
+    finished: bool,
+}
+
+impl<'a, D> Explain<'a, D>
+where
+    D: Digest,
+{
+    /// Explain the given encoded message
+    pub fn new(message: &'a [u8]) -> Self {
+        Self::with_config(message, Config::default())
+    }
+
+    /// Explain the given encoded message, decoding it with the given
+    /// configuration
+    pub fn with_config(message: &'a [u8], config: Config) -> Self {
+        Self {
+            decoder: Decoder::with_config(config),
+            message,
+            remaining: message,
+            finished: false,
+        }
+    }
+}
+
+impl<'a, D> Iterator for Explain<'a, D>
+where
+    D: Digest,
+{
+    type Item = Result<Entry<'a>, Error>;
+
+    fn next(&mut self) -> Option<Result<Entry<'a>, Error>> {
+        while !self.finished {
+            let start = self.decoder.position();
+            let depth = self.decoder.depth();
+
+            let event = match self.decoder.decode(&mut self.remaining) {
+                Ok(Some(event)) => event,
+                Ok(None) => {
+                    self.finished = true;
+
+                    if self.decoder.is_complete() {
+                        return None;
+                    } else {
+                        return Some(Err(self.decoder.truncated()));
+                    }
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            };
+
+            let item = match event {
+                Event::FieldHeader(header) => Item::FieldHeader(header),
+                // Booleans are encoded entirely within their field header
+                Event::Bool(_) => continue,
+                Event::UInt64(value) => Item::UInt64(value),
+                Event::SInt64(value) => Item::SInt64(value),
+                Event::ValueStart { wire_type, length } => Item::LengthPrefix { wire_type, length },
+                Event::ValueChunk {
+                    wire_type,
+                    bytes,
+                    remaining,
+                } => Item::ValueChunk {
+                    wire_type,
+                    length: bytes.len(),
+                    remaining,
+                },
+                Event::MessageStart { length } => Item::LengthPrefix {
+                    wire_type: WireType::Message,
+                    length,
+                },
+                Event::MessageEnd => Item::MessageEnd,
+                Event::SequenceStart { wire_type, length } => {
+                    Item::SequenceHeader { wire_type, length }
+                }
+                Event::SequenceEnd => Item::SequenceEnd,
+            };
+
+            let end = self.decoder.position();
+
+            // Prefixes of nested messages and sequences belong to the
+            // enclosing message, as do their ends
+            let depth = depth.min(self.decoder.depth());
+
+            return Some(Ok(Entry {
+                range: start..end,
+                depth,
+                bytes: &self.message[start..end],
+                item,
+            }));
+        }
+
+        None
+    }
+}
+
+/// Range of bytes in an encoded message along with their interpretation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry<'a> {
+    /// Range of bytes within the message (empty for entries which don't
+    /// consume any bytes, e.g. the end of a nested message)
+    pub range: Range<usize>,
+
+    /// Nesting depth (0 for the fields of the toplevel message)
+    pub depth: usize,
+
+    /// Bytes in the range
+    pub bytes: &'a [u8],
+
+    /// Interpretation of the bytes
+    pub item: Item,
+}
+
+impl<'a> Display for Entry<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>6}..{:<6} ", self.range.start, self.range.end)?;
+
+        for _ in 0..self.depth {
+            write!(f, "  ")?;
+        }
+
+        write!(f, "{}", self.item)?;
+
+        if !self.bytes.is_empty() {
+            write!(f, " |")?;
+
+            for byte in self.bytes {
+                write!(f, " {:02x}", byte)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Interpretations of ranges of bytes in an encoded message
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Item {
+    /// `vint64` field header
+    FieldHeader(Header),
+
+    /// `vint64` unsigned integer value
+    UInt64(u64),
+
+    /// `vint64` (zigzag) signed integer value
+    SInt64(i64),
+
+    /// `vint64` length prefix of a `bytes`, `string` or `message` value
+    LengthPrefix {
+        /// Wire type of the value
+        wire_type: WireType,
+
+        /// Length of the value
+        length: usize,
+    },
+
+    /// `vint64` header of a sequence
+    SequenceHeader {
+        /// Wire type of the elements of the sequence
+        wire_type: WireType,
+
+        /// Length of the body of the sequence
+        length: usize,
+    },
+
+    /// Chunk of the body of a `bytes` or `string` value
+    ValueChunk {
+        /// Wire type of the value
+        wire_type: WireType,
+
+        /// Length of the chunk
+        length: usize,
+
+        /// Remaining bytes in the value after this chunk
+        remaining: usize,
+    },
+
+    /// End of a nested message
+    MessageEnd,
+
+    /// End of a sequence
+    SequenceEnd,
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::FieldHeader(header) => write!(
+                f,
+                "field header: tag={} critical={} wire_type={}",
+                header.tag, header.critical, header.wire_type
+            ),
+            Item::UInt64(value) => write!(f, "uint64 value: {}", value),
+            Item::SInt64(value) => write!(f, "sint64 value: {}", value),
+            Item::LengthPrefix { wire_type, length } => write!(
+                f,
+                "length prefix: wire_type={} length={}",
+                wire_type, length
+            ),
+            Item::SequenceHeader { wire_type, length } => write!(
+                f,
+                "sequence header: wire_type={} length={}",
+                wire_type, length
+            ),
+            Item::ValueChunk {
+                wire_type,
+                length,
+                remaining,
+            } => write!(
+                f,
+                "value chunk: wire_type={} length={} remaining={}",
+                wire_type, length, remaining
+            ),
+            Item::MessageEnd => write!(f, "end of message"),
+            Item::SequenceEnd => write!(f, "end of sequence"),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc", feature = "sha2"))]
+mod tests {
+    use super::{Explain, Item};
+    use crate::{
+        error,
+        field::{Header, WireType},
+    };
+    use alloc::{string::ToString, vec::Vec};
+
+    type Explainer<'a> = Explain<'a, sha2::Sha256>;
+
+    #[test]
+    fn explain_nested_message() {
+        // Field 1: message containing field 2: "bytes"; field 3: sequence of uint64
+        let input = [45, 15, 73, 11, 98, 121, 116, 101, 115, 127, 69, 3, 5];
+        let entries = Explainer::new(&input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let items: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.range.clone(), entry.depth, entry.item))
+            .collect();
+
+        assert_eq!(
+            items,
+            [
+                (
+                    0..1,
+                    0,
+                    Item::FieldHeader(Header::new(1, false, WireType::Message))
+                ),
+                (
+                    1..2,
+                    0,
+                    Item::LengthPrefix {
+                        wire_type: WireType::Message,
+                        length: 7
+                    }
+                ),
+                (
+                    2..3,
+                    1,
+                    Item::FieldHeader(Header::new(2, false, WireType::Bytes))
+                ),
+                (
+                    3..4,
+                    1,
+                    Item::LengthPrefix {
+                        wire_type: WireType::Bytes,
+                        length: 5
+                    }
+                ),
+                (
+                    4..9,
+                    1,
+                    Item::ValueChunk {
+                        wire_type: WireType::Bytes,
+                        length: 5,
+                        remaining: 0
+                    }
+                ),
+                (9..9, 0, Item::MessageEnd),
+                (
+                    9..10,
+                    0,
+                    Item::FieldHeader(Header::new(3, true, WireType::Sequence))
+                ),
+                (
+                    10..11,
+                    0,
+                    Item::SequenceHeader {
+                        wire_type: WireType::UInt64,
+                        length: 2
+                    }
+                ),
+                (11..12, 1, Item::UInt64(1)),
+                (12..13, 1, Item::UInt64(2)),
+                (13..13, 0, Item::SequenceEnd),
+            ]
+        );
+
+        assert_eq!(
+            entries[2].to_string(),
+            "     2..3        field header: tag=2 critical=false wire_type=bytes | 49"
+        );
+    }
+
+    #[test]
+    fn explain_truncated() {
+        let input = [45, 15, 73, 11, 98];
+        let result = Explainer::new(&input).collect::<Result<Vec<_>, _>>();
+        assert!(matches!(
+            result.unwrap_err().kind(),
+            error::Kind::Truncated { .. }
+        ));
+    }
+}
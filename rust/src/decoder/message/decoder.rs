@@ -6,7 +6,7 @@ use crate::{
     error::{self, Error},
     field::{Header, Tag, WireType},
     message::Element,
-    verihash::DigestOutput,
+    verihash::{DigestOutput, VerihashVersion},
 };
 use core::fmt::{self, Debug};
 use digest::Digest;
@@ -36,15 +36,42 @@ where
 {
     /// Create a new decoder in an initial state
     pub fn new() -> Self {
+        Self::with_verihash_version(VerihashVersion::default())
+    }
+
+    /// Create a new decoder which computes the given version of Verihash
+    pub fn with_verihash_version(version: VerihashVersion) -> Self {
         Self {
             state: Some(State::default()),
             last_tag: None,
             position: 0,
-            hasher: Some(Hasher::new()), // TODO(tarcieri): support for disabling hasher
+            hasher: Some(Hasher::new(version)), // TODO(tarcieri): support for disabling hasher
             cached_digest: None,
         }
     }
 
+    /// Get the current position within the message (i.e. total bytes consumed)
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Has the decoder finished decoding the header of the current field and
+    /// not yet finished decoding its value?
+    pub fn in_field(&self) -> bool {
+        match &self.state {
+            Some(State::Header(_)) | None => false,
+            Some(_) => true,
+        }
+    }
+
+    /// Is the decoder at a field boundary (i.e. not partway through a field)?
+    pub fn is_complete(&self) -> bool {
+        match &self.state {
+            Some(State::Header(header)) => header.is_empty(),
+            _ => false,
+        }
+    }
+
     /// Decode an expected field header, returning an error for anything else
     pub fn decode_header(&mut self, input: &mut &[u8]) -> Result<Header, Error> {
         match self.decode(input)? {
@@ -58,7 +85,10 @@ where
     }
 
     /// Decode an expected field header, skipping (in-order) unknown fields,
-    /// and returning an error if the field is missing or unexpected
+    /// and returning an error if the field is missing or unexpected.
+    ///
+    /// Booleans are encoded as either the `False` or `True` wire type, so
+    /// expecting either of them will match a field containing a boolean.
     pub fn expect_header(
         &mut self,
         input: &mut &[u8],
@@ -70,8 +100,8 @@ where
                 tag: Some(tag),
                 wire_type: Some(wire_type),
             }
-            .position(self.position),
-            _ => unreachable!("unexpected decode_header error: {:?}", e),
+            .into(),
+            _ => e,
         })?;
 
         // TODO(tarcieri): actually skip unrecognized/unknown fields
@@ -83,7 +113,7 @@ where
             .into());
         }
 
-        if header.wire_type != wire_type {
+        if header.wire_type != wire_type && !(is_bool(header.wire_type) && is_bool(wire_type)) {
             return Err(error::Kind::UnexpectedWireType {
                 actual: header.wire_type,
                 wanted: wire_type,
@@ -168,6 +198,11 @@ where
     }
 }
 
+/// Is the given wire type one of the boolean wire types?
+fn is_bool(wire_type: WireType) -> bool {
+    wire_type == WireType::False || wire_type == WireType::True
+}
+
 impl<D> Default for Decoder<D>
 where
     D: Digest,
@@ -183,6 +218,7 @@ where
 {
     fn decode<'a>(&mut self, input: &mut &'a [u8]) -> Result<Option<Event<'a>>, Error> {
         if let Some(state) = self.state.take() {
+            let orig_input_len = input.len();
             let (new_state, event) = state.decode(input, self.last_tag)?;
 
             if let Some(Event::FieldHeader(header)) = &event {
@@ -190,7 +226,8 @@ where
             }
 
             self.state = Some(new_state);
-            self.position = self.position.checked_add(input.len()).unwrap();
+            let consumed = orig_input_len.checked_sub(input.len()).unwrap();
+            self.position = self.position.checked_add(consumed).unwrap();
 
             if let Some(ev) = &event {
                 if let Some(hasher) = &mut self.hasher {
@@ -211,6 +248,11 @@ where
     ) -> Result<&'a [u8], Error> {
         let length = self.decode_length_delimiter(input, expected_type)?;
 
+        // Empty values have no body
+        if length == 0 {
+            return Ok(&[]);
+        }
+
         match self.decode(input)? {
             Some(Event::ValueChunk {
                 wire_type,
@@ -2,20 +2,14 @@
 //!
 //! WARNING: this is an experimental PoC-quality implementation!
 //! It is NOT suitable for production use!
-//!
-//! # TODO
-//!
-//! - Nested message hashing
-//! - Sequence hashing
 
-// TODO(tarcieri): tests and test vectors!!!
 // TODO(tarcieri): DRY out repeated logic in sequence hasher
 
 use crate::{
     decoder::Event,
     error::{self, Error},
     field::{self, Tag, WireType},
-    verihash::{self, DigestOutput},
+    verihash::{self, DigestOutput, VerihashVersion},
 };
 use core::fmt::{self, Debug};
 use digest::Digest;
@@ -36,10 +30,10 @@ impl<D> Hasher<D>
 where
     D: Digest,
 {
-    /// Create a new [`Hasher`]
-    pub fn new() -> Self {
+    /// Create a new [`Hasher`] for the given version of Verihash
+    pub fn new(version: VerihashVersion) -> Self {
         Self {
-            verihash: verihash::Hasher::new(),
+            verihash: verihash::Hasher::for_message(version),
             state: Some(State::default()),
         }
     }
@@ -60,7 +54,7 @@ where
         match self.state {
             Some(State::Message { remaining }) if remaining == 0 => {
                 self.verihash.tag(tag);
-                self.verihash.fixed_size_value(WireType::Message, digest);
+                self.verihash.nested_digest(WireType::Message, digest);
                 self.state = Some(State::Initial);
                 Ok(())
             }
@@ -77,7 +71,7 @@ where
         match self.state {
             Some(State::Sequence { remaining, .. }) if remaining == 0 => {
                 self.verihash.tag(tag);
-                self.verihash.fixed_size_value(WireType::Sequence, digest);
+                self.verihash.nested_digest(WireType::Sequence, digest);
                 self.state = Some(State::Initial);
                 Ok(())
             }
@@ -100,7 +94,7 @@ where
     D: Digest,
 {
     fn default() -> Self {
-        Self::new()
+        Self::new(VerihashVersion::default())
     }
 }
 
@@ -191,13 +185,19 @@ impl State {
             }
 
             let new_state = match wire_type {
+                // Empty values have no body, so they're complete once delimited
+                WireType::Bytes | WireType::String if length == 0 => State::Initial,
                 WireType::Bytes => State::Bytes { remaining: length },
                 WireType::String => State::String { remaining: length },
                 WireType::Message => State::Message { remaining: length },
                 _ => unreachable!(),
             };
 
-            verihash.tag(header.tag);
+            // V1 hashes the tag of a nested message along with its digest
+            if verihash.version() == VerihashVersion::V0 || wire_type != WireType::Message {
+                verihash.tag(header.tag);
+            }
+
             verihash.dynamically_sized_value(wire_type, length);
 
             Ok(new_state)
@@ -289,7 +289,7 @@ impl State {
             }
         };
 
-        verihash.update(bytes);
+        verihash.value_chunk(bytes);
         Ok(new_state)
     }
 
@@ -12,6 +12,11 @@ use crate::{
 pub(super) struct Decoder(vint64::Decoder);
 
 impl Decoder {
+    /// Has this decoder not yet consumed any data?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Process the given input data, advancing the slice for the amount of
     /// data processed, and returning the new state.
     pub fn decode<'a>(
@@ -32,34 +32,38 @@ impl Decoder {
     /// Process the given input data, advancing the slice for the amount of
     /// data processed, and returning the new state.
     pub fn decode<'a>(mut self, input: &mut &'a [u8]) -> Result<(State, Option<Event<'a>>), Error> {
-        if let Some(value) = self.decoder.decode(input)? {
-            let event = match self.wire_type {
-                WireType::False => Event::Bool(false),
-                WireType::True => Event::Bool(true),
-                WireType::UInt64 => Event::UInt64(value),
-                WireType::SInt64 => Event::SInt64(zigzag::decode(value)),
-                WireType::Sequence => Event::SequenceHeader {
-                    wire_type: WireType::from_unmasked(value),
-                    length: (value >> 4) as usize,
-                },
-                wire_type => {
-                    debug_assert!(
-                        wire_type.is_dynamically_sized(),
-                        "not a dynamically sized wire type: {:?}",
-                        wire_type
-                    );
+        let event = match self.wire_type {
+            // Booleans are encoded entirely within the field header
+            WireType::False => Event::Bool(false),
+            WireType::True => Event::Bool(true),
+            wire_type => match self.decoder.decode(input)? {
+                Some(value) => decode_value(wire_type, value)?,
+                None => return Ok((State::Value(self), None)),
+            },
+        };
 
-                    Event::LengthDelimiter {
-                        wire_type,
-                        length: value as usize,
-                    }
-                }
-            };
+        let new_state = State::transition(&event);
+        Ok((new_state, Some(event)))
+    }
+}
 
-            let new_state = State::transition(&event);
-            Ok((new_state, Some(event)))
-        } else {
-            Ok((State::Value(self), None))
+/// Decode a `vint64` value (either length delimiter or uint64/sint64 value)
+fn decode_value<'a>(wire_type: WireType, value: u64) -> Result<Event<'a>, Error> {
+    Ok(match wire_type {
+        WireType::UInt64 => Event::UInt64(value),
+        WireType::SInt64 => Event::SInt64(zigzag::decode(value)),
+        WireType::Sequence => Event::sequence_header(value)?,
+        wire_type => {
+            debug_assert!(
+                wire_type.is_dynamically_sized(),
+                "not a dynamically sized wire type: {:?}",
+                wire_type
+            );
+
+            Event::LengthDelimiter {
+                wire_type,
+                length: value as usize,
+            }
         }
-    }
+    })
 }
@@ -6,7 +6,7 @@ use crate::{
     error::{self, Error},
     field::WireType,
     message::Element,
-    verihash::DigestOutput,
+    verihash::{DigestOutput, VerihashVersion},
 };
 use digest::Digest;
 
@@ -21,6 +21,9 @@ pub(crate) struct Decoder<D: Digest> {
     /// Remaining length in the sequence body
     remaining: usize,
 
+    /// Number of elements which have been completely decoded
+    elements: usize,
+
     /// Current decoding state
     state: State,
 
@@ -32,14 +35,16 @@ impl<D> Decoder<D>
 where
     D: Digest,
 {
-    /// Create a new sequence decoder for the given wire type
-    pub fn new(wire_type: WireType, length: usize) -> Self {
+    /// Create a new sequence decoder for the given wire type which computes
+    /// the given version of Verihash
+    pub fn new(version: VerihashVersion, wire_type: WireType, length: usize) -> Self {
         Self {
             wire_type,
             length,
             remaining: length,
+            elements: 0,
             state: State::default(),
-            hasher: Some(Hasher::new(wire_type)), // TODO(tarcieri): support for disabling hasher
+            hasher: Some(Hasher::new(version, wire_type)), // TODO(tarcieri): support for disabling hasher
         }
     }
 
@@ -54,14 +59,44 @@ where
         self.remaining
     }
 
+    /// Get the number of elements which have been completely decoded, i.e.
+    /// the index of the element presently being decoded
+    pub fn elements(&self) -> usize {
+        self.elements
+    }
+
+    /// Get the number of elements whose decoding has begun, i.e. including
+    /// the element presently being decoded once its value or length
+    /// delimiter has been consumed
+    pub fn elements_started(&self) -> usize {
+        match &self.state {
+            State::Value(_) => self.elements,
+            State::Body { .. } => self.elements.saturating_add(1),
+        }
+    }
+
+    /// Has the entire sequence been decoded?
+    pub fn is_complete(&self) -> bool {
+        match &self.state {
+            State::Value(decoder) => self.remaining == 0 && decoder.is_empty(),
+            State::Body { .. } => false,
+        }
+    }
+
     /// Perform a state transition after receiving an event
     fn transition<'a>(&mut self, event: &Event<'a>) {
         self.state = match &event {
             Event::LengthDelimiter { wire_type, length }
-            | Event::SequenceHeader { wire_type, length } => State::Body {
-                wire_type: *wire_type,
-                remaining: *length,
-            },
+            | Event::SequenceHeader { wire_type, length } => {
+                if *length > 0 {
+                    State::Body {
+                        wire_type: *wire_type,
+                        remaining: *length,
+                    }
+                } else {
+                    State::default()
+                }
+            }
             Event::UInt64(_) | Event::SInt64(_) => State::Value(vint64::Decoder::new()),
             Event::ValueChunk {
                 wire_type,
@@ -79,6 +114,12 @@ where
             }
             other => unreachable!("unexpected event: {:?}", other),
         };
+
+        if let State::Value(decoder) = &self.state {
+            if decoder.is_empty() {
+                self.elements = self.elements.checked_add(1).unwrap();
+            }
+        }
     }
 
     /// Hash a digest of a nested message within this sequence
@@ -107,6 +148,13 @@ where
         self.remaining = self.remaining.checked_sub(consumed).unwrap();
 
         if let Some(event) = &maybe_event {
+            // Values must be contained entirely within the sequence body
+            if let Event::LengthDelimiter { length, .. } = event {
+                if *length > self.remaining {
+                    return Err(error::Kind::Length.into());
+                }
+            }
+
             if let Some(hasher) = &mut self.hasher {
                 hasher.hash_event(event)?;
             }
@@ -137,13 +185,20 @@ where
         );
 
         let length = match self.decode(input)? {
-            Some(Event::LengthDelimiter { length, .. }) => Ok(length),
-            _ => Err(error::Kind::Decode {
-                element: Element::LengthDelimiter,
-                wire_type: self.wire_type,
+            Some(Event::LengthDelimiter { length, .. }) => length,
+            _ => {
+                return Err(error::Kind::Decode {
+                    element: Element::LengthDelimiter,
+                    wire_type: self.wire_type,
+                }
+                .into())
             }
-            .position(self.length.checked_sub(self.remaining).unwrap())),
-        }?;
+        };
+
+        // Empty values have no body
+        if length == 0 {
+            return Ok(&[]);
+        }
 
         match self.decode(input)? {
             Some(Event::ValueChunk {
@@ -171,14 +226,15 @@ where
 
 #[cfg(all(test, features = "sha2"))]
 mod tests {
-    use super::{Decodable, Decoder, WireType};
+    use super::{Decodable, Decoder, VerihashVersion, WireType};
     use sha2::Sha256;
 
     #[test]
     fn decode_uint64_sequence() {
         let input = [3, 5, 7];
         let mut input_ref = &input[..];
-        let mut decoder: Decoder<Sha256> = Decoder::new(WireType::UInt64, input.len());
+        let mut decoder: Decoder<Sha256> =
+            Decoder::new(VerihashVersion::V0, WireType::UInt64, input.len());
 
         assert_eq!(1, decoder.decode_uint64(&mut input_ref).unwrap());
         assert_eq!(2, decoder.decode_uint64(&mut input_ref).unwrap());
@@ -190,7 +246,8 @@ mod tests {
     fn decode_sint64_sequence() {
         let input = [3, 7, 11];
         let mut input_ref = &input[..];
-        let mut decoder: Decoder<Sha256> = Decoder::new(WireType::SInt64, input.len());
+        let mut decoder: Decoder<Sha256> =
+            Decoder::new(VerihashVersion::V0, WireType::SInt64, input.len());
 
         for n in &[-1, -2, -3] {
             assert_eq!(*n, decoder.decode_sint64(&mut input_ref).unwrap());
@@ -203,7 +260,8 @@ mod tests {
     fn decode_bytes_sequence() {
         let input = [7, 102, 111, 111, 7, 98, 97, 114, 7, 98, 97, 122];
         let mut input_ref = &input[..];
-        let mut decoder: Decoder<Sha256> = Decoder::new(WireType::Bytes, input.len());
+        let mut decoder: Decoder<Sha256> =
+            Decoder::new(VerihashVersion::V0, WireType::Bytes, input.len());
 
         for &b in &[b"foo", b"bar", b"baz"] {
             assert_eq!(b, decoder.decode_bytes(&mut input_ref).unwrap());
@@ -216,7 +274,8 @@ mod tests {
     fn decode_string_sequence() {
         let input = [7, 102, 111, 111, 7, 98, 97, 114, 7, 98, 97, 122];
         let mut input_ref = &input[..];
-        let mut decoder: Decoder<Sha256> = Decoder::new(WireType::String, input.len());
+        let mut decoder: Decoder<Sha256> =
+            Decoder::new(VerihashVersion::V0, WireType::String, input.len());
 
         for &s in &["foo", "bar", "baz"] {
             assert_eq!(s, decoder.decode_string(&mut input_ref).unwrap());
@@ -3,14 +3,13 @@
 //! WARNING: this is an experimental PoC-quality implementation!
 //! It is NOT suitable for production use!
 
-// TODO(tarcieri): tests and test vectors!!!
 // TODO(tarcieri): DRY out repeated message/sequence code into `verihash::Hasher`
 
 use crate::{
     decoder::Event,
     error::{self, Error},
     field::WireType,
-    verihash::{self, DigestOutput},
+    verihash::{self, DigestOutput, VerihashVersion},
 };
 use core::fmt::{self, Debug};
 use digest::Digest;
@@ -31,15 +30,10 @@ impl<D> Hasher<D>
 where
     D: Digest,
 {
-    /// Create a new [`Hasher`]
-    pub fn new(wire_type: WireType) -> Self {
-        let mut verihash = verihash::Hasher::new();
-
-        // Domain separate sequence hashes by their contained wire type
-        verihash.update(&[wire_type.to_u8()]);
-
+    /// Create a new [`Hasher`] for the given version of Verihash
+    pub fn new(version: VerihashVersion, wire_type: WireType) -> Self {
         Self {
-            verihash,
+            verihash: verihash::Hasher::for_sequence(version, wire_type),
             state: Some(State::default()),
         }
     }
@@ -140,6 +134,8 @@ impl State {
         }
 
         let new_state = match wire_type {
+            // Empty values have no body, so they're complete once delimited
+            WireType::Bytes | WireType::String if length == 0 => State::Initial,
             WireType::Bytes => State::Bytes { remaining: length },
             WireType::String => State::String { remaining: length },
             WireType::Message => State::Message { remaining: length },
@@ -225,7 +221,7 @@ impl State {
             _ => return Err(error::Kind::Hashing.into()),
         };
 
-        verihash.update(bytes);
+        verihash.value_chunk(bytes);
         Ok(new_state)
     }
 }
@@ -2,15 +2,16 @@
 
 use crate::{
     decoder::{sequence, Decodable, Decoder},
-    field::Tag,
-    Error, Message,
+    error::{self, Error},
+    field::{Tag, WireType},
+    Message,
 };
 use core::marker::PhantomData;
 use digest::Digest;
 
 /// Sequence iterator: iterates over a sequence of values in a Veriform
 /// message, decoding each one.
-pub struct Iter<'a, 'b, T, D: Digest> {
+pub struct Iter<'a, 'b, T: ?Sized, D: Digest> {
     /// Sequence decoder
     decoder: &'a mut Decoder<D>,
 
@@ -21,60 +22,175 @@ pub struct Iter<'a, 'b, T, D: Digest> {
     /// Input data
     data: &'b [u8],
 
+    /// Offset of the start of the sequence body within the toplevel message
+    offset: usize,
+
+    /// Position (within the sequence body) and index of the most recently
+    /// decoded element
+    element: (usize, usize),
+
+    /// Error which occurred while decoding an element (if any)
+    error: Option<Error>,
+
     /// Type to decode
     decodable: PhantomData<T>,
 }
 
 impl<'a, 'b, T, D> Iter<'a, 'b, T, D>
 where
+    T: ?Sized,
     D: Digest,
 {
     /// Create a new sequence iterator from a sequence decoder
-    pub(crate) fn new(decoder: &'a mut Decoder<D>, tag: Tag, data: &'b [u8]) -> Self {
+    pub(crate) fn new(
+        decoder: &'a mut Decoder<D>,
+        tag: Tag,
+        data: &'b [u8],
+        offset: usize,
+    ) -> Self {
         Self {
             decoder,
             tag,
             data,
+            offset,
+            element: (0, 0),
+            error: None,
             decodable: PhantomData,
         }
     }
 
-    /// Borrow the sequence decoder
-    fn seq_decoder(&mut self) -> &mut sequence::Decoder<D> {
-        self.decoder.peek_seq()
+    /// Decode the next element in the sequence using the provided function,
+    /// annotating any errors which occur with the element's location
+    fn next_with<V, F>(&mut self, f: F) -> Option<Result<V, Error>>
+    where
+        F: FnOnce(&mut sequence::Decoder<D>, &mut &'b [u8]) -> Result<V, Error>,
+    {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let result = self.decode_element(f).transpose()?;
+        self.error = result.as_ref().err().copied();
+        Some(result)
     }
-}
 
-impl<'a, 'b, T, D> Iterator for Iter<'a, 'b, T, D>
-where
-    T: Message,
-    D: Digest,
-{
-    type Item = Result<T, Error>;
+    /// Decode the next element in the sequence (if there is one)
+    fn decode_element<V, F>(&mut self, f: F) -> Result<Option<V>, Error>
+    where
+        F: FnOnce(&mut sequence::Decoder<D>, &mut &'b [u8]) -> Result<V, Error>,
+    {
+        let data = self.data;
+        let config = self.decoder.config;
+        let seq_decoder = match self.decoder.peek_seq() {
+            Ok(seq_decoder) => seq_decoder,
+            Err(e) => return Err(self.decoder.annotate_field(e, self.tag)),
+        };
 
-    fn next(&mut self) -> Option<Result<T, Error>> {
-        if self.seq_decoder().remaining() == 0 {
-            return None;
+        if seq_decoder.remaining() == 0 {
+            return Ok(None);
         }
 
-        let mut input = &self.data[self.seq_decoder().position()..];
+        let start = seq_decoder.position();
+        let index = seq_decoder.elements();
+        self.element = (start, index);
 
-        let result = self
-            .seq_decoder()
-            .decode_message(&mut input)
-            .and_then(|msg_bytes| {
-                self.decoder.push()?;
-                let msg = T::decode(&mut self.decoder, msg_bytes)?;
+        let result = match data.get(start..) {
+            Some(mut input) => config
+                .check_sequence_elements(index.saturating_add(1))
+                .and_then(|_| f(seq_decoder, &mut input)),
+            None => Err(error::Kind::Length.into()),
+        };
 
-                if let Some(digest) = self.decoder.pop() {
-                    self.seq_decoder().hash_message_digest(&digest)?;
-                }
+        result.map(Some).map_err(|e| self.locate(e, start, index))
+    }
+
+    /// Decode the next message in the sequence, using the provided function
+    /// to decode its body
+    pub(crate) fn next_message_with<M, F>(&mut self, f: F) -> Option<Result<M, Error>>
+    where
+        F: FnOnce(&mut Decoder<D>, &'b [u8]) -> Result<M, Error>,
+    {
+        let config = self.decoder.config;
+        let element = self.next_with(|seq_decoder, input| {
+            let start = seq_decoder.position();
+            let index = seq_decoder.elements();
+            let msg_bytes = seq_decoder.decode_message(input)?;
+            config.check_message_length(msg_bytes.len())?;
+            let body_start = seq_decoder.position().saturating_sub(msg_bytes.len());
+            Ok((msg_bytes, start, body_start, index))
+        })?;
+
+        let (msg_bytes, start, body_start, index) = match element {
+            Ok(element) => element,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let offset = self.offset.saturating_add(body_start);
+
+        if let Err(e) = self.decoder.push(offset, self.tag, Some(index)) {
+            let err = self.locate(e, start, index);
+            self.error = Some(err);
+            return Some(Err(err));
+        }
+
+        let result = f(self.decoder, msg_bytes)
+            .map_err(|e| self.decoder.discard(e))
+            .and_then(|msg| {
+                self.decoder
+                    .pop()
+                    .and_then(|digest| match digest {
+                        Some(digest) => self.decoder.peek_seq()?.hash_message_digest(&digest),
+                        None => Ok(()),
+                    })
+                    .map_err(|e| self.locate(e, start, index))?;
 
                 Ok(msg)
             });
 
+        self.error = result.as_ref().err().copied();
         Some(result)
     }
+
+    /// Reject the most recently decoded element (e.g. a map entry whose key
+    /// is out of order), annotating the given error with its location.
+    ///
+    /// The sequence is discarded without being hashed, as with elements
+    /// which fail to decode.
+    pub(crate) fn reject(&mut self, err: Error) -> Error {
+        let (start, index) = self.element;
+        let err = self.locate(err, start, index);
+        self.error = Some(err);
+        err
+    }
+
+    /// Finish decoding the sequence, hashing its digest into the message
+    /// containing it
+    fn finish(&mut self) -> Result<(), Error> {
+        match self.decoder.pop_seq()? {
+            Some(digest) => self.decoder.peek().hash_sequence_digest(self.tag, &digest),
+            None => Ok(()),
+        }
+    }
+
+    /// Annotate an error with the location of the element at the given
+    /// position within the sequence
+    fn locate(&self, err: Error, start: usize, index: usize) -> Error {
+        let position = self.offset.saturating_add(start);
+        self.decoder
+            .locate(err, position, Some(self.tag), Some(index))
+    }
+}
+
+impl<'a, 'b, T, D> Iterator for Iter<'a, 'b, T, D>
+where
+    T: Message,
+    D: Digest,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Result<T, Error>> {
+        self.next_message_with(T::decode)
+    }
 }
 
 impl<'a, 'b, D> Iterator for Iter<'a, 'b, u64, D>
@@ -84,12 +200,7 @@ where
     type Item = Result<u64, Error>;
 
     fn next(&mut self) -> Option<Result<u64, Error>> {
-        if self.seq_decoder().remaining() == 0 {
-            return None;
-        }
-
-        let mut input = &self.data[self.seq_decoder().position()..];
-        Some(self.seq_decoder().decode_uint64(&mut input))
+        self.next_with(|seq, input| seq.decode_uint64(input))
     }
 }
 
@@ -100,25 +211,68 @@ where
     type Item = Result<i64, Error>;
 
     fn next(&mut self) -> Option<Result<i64, Error>> {
-        if self.seq_decoder().remaining() == 0 {
-            return None;
-        }
+        self.next_with(|seq, input| seq.decode_sint64(input))
+    }
+}
+
+impl<'a, 'b, D> Iterator for Iter<'a, 'b, [u8], D>
+where
+    D: Digest,
+{
+    type Item = Result<&'b [u8], Error>;
 
-        let mut input = &self.data[self.seq_decoder().position()..];
-        Some(self.seq_decoder().decode_sint64(&mut input))
+    fn next(&mut self) -> Option<Result<&'b [u8], Error>> {
+        let config = self.decoder.config;
+        self.next_with(|seq, input| {
+            let bytes = seq.decode_bytes(input)?;
+            config.check_value_length(WireType::Bytes, bytes.len())?;
+            Ok(bytes)
+        })
+    }
+}
+
+impl<'a, 'b, D> Iterator for Iter<'a, 'b, str, D>
+where
+    D: Digest,
+{
+    type Item = Result<&'b str, Error>;
+
+    fn next(&mut self) -> Option<Result<&'b str, Error>> {
+        let config = self.decoder.config;
+        self.next_with(|seq, input| {
+            let string = seq.decode_string(input)?;
+            config.check_value_length(WireType::String, string.len())?;
+            Ok(string)
+        })
     }
 }
 
 impl<'a, 'b, T, D> Drop for Iter<'a, 'b, T, D>
 where
+    T: ?Sized,
     D: Digest,
 {
     fn drop(&mut self) {
-        if let Some(digest) = self.decoder.pop_seq() {
-            self.decoder
-                .peek()
-                .hash_sequence_digest(self.tag, &digest)
-                .unwrap();
+        // Sequences which failed to decode are discarded without hashing.
+        // The error is also recorded by the decoder, so the digest of the
+        // message containing the sequence can't be computed without it even
+        // if the caller ignored the error (e.g. by skipping failed elements)
+        if let Some(err) = self.error {
+            self.decoder.discard_seq();
+            self.decoder.defer(err);
+            return;
+        }
+
+        let position = match self.decoder.peek_seq() {
+            Ok(seq_decoder) => self.offset.saturating_add(seq_decoder.position()),
+            Err(_) => self.offset,
+        };
+
+        // Errors can't be returned from `drop`, so they're recorded by the
+        // decoder and returned by subsequent calls to it instead
+        if let Err(e) = self.finish() {
+            let err = self.decoder.locate(e, position, Some(self.tag), None);
+            self.decoder.defer(err);
         }
     }
 }
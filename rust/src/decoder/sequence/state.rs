@@ -66,10 +66,7 @@ fn decode_value<'a>(wire_type: WireType, value: u64) -> Result<Event<'a>, Error>
     Ok(match wire_type {
         WireType::UInt64 => Event::UInt64(value),
         WireType::SInt64 => Event::SInt64(zigzag::decode(value)),
-        WireType::Sequence => Event::SequenceHeader {
-            wire_type: WireType::from_unmasked(value),
-            length: (value >> 4) as usize,
-        },
+        WireType::Sequence => Event::sequence_header(value)?,
         WireType::False | WireType::True => {
             // TODO(tarcieri): support boolean sequences?
             return Err(error::Kind::Decode {
@@ -0,0 +1,845 @@
+//! Streaming decoder which emits events incrementally as data arrives.
+//!
+//! Unlike [`Message::decode`][`crate::Message::decode`], which requires the
+//! entire message to be resident in memory, the streaming [`Decoder`] can be
+//! fed input in arbitrarily sized chunks. The contents of `bytes` and
+//! `string` fields are emitted as [`Event::ValueChunk`]s and fed to the
+//! Verihash hasher as they arrive, so large values never need to be buffered.
+//!
+//! When the `async` feature is enabled, [`Reader`] drives a [`Decoder`] using
+//! data pulled from a `tokio::io::AsyncRead`.
+
+#[cfg(feature = "async")]
+mod reader;
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use self::reader::{Reader, DEFAULT_BUFFER_SIZE};
+
+use super::{message, sequence, Config, Decodable, Event as RawEvent, MAX_DEPTH};
+use crate::{
+    error::{self, Error, Path, Segment},
+    field::{Header, Tag, WireType},
+    message::Element,
+    verihash::VerihashVersion,
+};
+use digest::Digest;
+use heapless::consts::U32;
+
+/// Events emitted by the streaming [`Decoder`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event<'a> {
+    /// Field header with the given tag and wire type
+    FieldHeader(Header),
+
+    /// Boolean value
+    Bool(bool),
+
+    /// Unsigned 64-bit integer value
+    UInt64(u64),
+
+    /// Signed 64-bit integer value
+    SInt64(i64),
+
+    /// Start of a `bytes` or `string` value. Empty values are not followed
+    /// by any [`Event::ValueChunk`]s.
+    ValueStart {
+        /// Wire type of the value
+        wire_type: WireType,
+
+        /// Length of the value
+        length: usize,
+    },
+
+    /// Chunk of a `bytes` or `string` value
+    ValueChunk {
+        /// Wire type of the value
+        wire_type: WireType,
+
+        /// Bytes in this chunk
+        bytes: &'a [u8],
+
+        /// Remaining bytes in the value
+        remaining: usize,
+    },
+
+    /// Start of a nested message
+    MessageStart {
+        /// Length of the nested message
+        length: usize,
+    },
+
+    /// End of a nested message
+    MessageEnd,
+
+    /// Start of a sequence
+    SequenceStart {
+        /// Wire type of the values in the sequence
+        wire_type: WireType,
+
+        /// Length of the sequence body
+        length: usize,
+    },
+
+    /// End of a sequence
+    SequenceEnd,
+}
+
+#[cfg(any(feature = "async", test))]
+impl<'a> Event<'a> {
+    /// Replace the bytes in a [`Event::ValueChunk`]
+    fn with_bytes(self, bytes: &[u8]) -> Event<'_> {
+        match self {
+            Event::FieldHeader(header) => Event::FieldHeader(header),
+            Event::Bool(value) => Event::Bool(value),
+            Event::UInt64(value) => Event::UInt64(value),
+            Event::SInt64(value) => Event::SInt64(value),
+            Event::ValueStart { wire_type, length } => Event::ValueStart { wire_type, length },
+            Event::ValueChunk {
+                wire_type,
+                remaining,
+                ..
+            } => Event::ValueChunk {
+                wire_type,
+                bytes,
+                remaining,
+            },
+            Event::MessageStart { length } => Event::MessageStart { length },
+            Event::MessageEnd => Event::MessageEnd,
+            Event::SequenceStart { wire_type, length } => {
+                Event::SequenceStart { wire_type, length }
+            }
+            Event::SequenceEnd => Event::SequenceEnd,
+        }
+    }
+}
+
+/// Streaming Veriform decoder: incremental pull parser which accepts input
+/// in arbitrarily sized chunks, emitting [`Event`]s and computing the
+/// Verihash digest of the message as it goes.
+pub struct Decoder<D: Digest> {
+    /// Decoder configuration
+    config: Config,
+
+    /// Stack of messages and sequences being decoded (at most
+    /// [`MAX_DEPTH`][`super::MAX_DEPTH`] messages, each of which may contain
+    /// a sequence)
+    stack: heapless::Vec<Frame<D>, U32>,
+
+    /// Current position within the stream (i.e. total bytes consumed)
+    position: usize,
+
+    /// Total number of fields decoded so far
+    fields: usize,
+}
+
+/// Message or sequence being decoded
+enum Frame<D: Digest> {
+    /// Message (either the toplevel message or a nested one)
+    Message {
+        /// Tag of the field containing this message (if it's in a field)
+        tag: Option<Tag>,
+
+        /// Header of the field presently being decoded in this message
+        header: Option<Header>,
+
+        /// Message decoder
+        decoder: message::Decoder<D>,
+
+        /// Remaining bytes in this message (`None` for the toplevel message)
+        remaining: Option<usize>,
+    },
+
+    /// Sequence within a message
+    Sequence {
+        /// Tag of the field containing this sequence
+        tag: Tag,
+
+        /// Sequence decoder
+        decoder: sequence::Decoder<D>,
+    },
+}
+
+impl<D> Frame<D>
+where
+    D: Digest,
+{
+    /// Create a new message frame
+    fn message(version: VerihashVersion, tag: Option<Tag>, remaining: Option<usize>) -> Self {
+        Frame::Message {
+            tag,
+            header: None,
+            decoder: message::Decoder::with_verihash_version(version),
+            remaining,
+        }
+    }
+
+    /// Get the number of bytes remaining in this frame (if known)
+    fn remaining(&self) -> Option<usize> {
+        match self {
+            Frame::Message { remaining, .. } => *remaining,
+            Frame::Sequence { decoder, .. } => Some(decoder.remaining()),
+        }
+    }
+
+    /// Is this frame at a boundary between fields or values?
+    fn is_complete(&self) -> bool {
+        match self {
+            Frame::Message { decoder, .. } => decoder.is_complete(),
+            Frame::Sequence { decoder, .. } => decoder.is_complete(),
+        }
+    }
+
+    /// Decode an event from the given input
+    fn decode<'a>(&mut self, input: &mut &'a [u8]) -> Result<Option<RawEvent<'a>>, Error> {
+        let event = match self {
+            Frame::Message { decoder, .. } => decoder.decode(input)?,
+            Frame::Sequence { decoder, .. } => decoder.decode(input)?,
+        };
+
+        if let (Frame::Message { header, .. }, Some(RawEvent::FieldHeader(h))) = (self, &event) {
+            *header = Some(*h);
+        }
+
+        Ok(event)
+    }
+
+    /// Consume bytes contained in a nested message or sequence
+    fn consume_nested(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let mut input = bytes;
+
+        match self.decode(&mut input)? {
+            Some(RawEvent::ValueChunk { .. }) if input.is_empty() => (),
+            _ => return Err(error::Kind::Hashing.into()),
+        }
+
+        if let Frame::Message {
+            remaining: Some(remaining),
+            ..
+        } = self
+        {
+            *remaining = remaining
+                .checked_sub(bytes.len())
+                .ok_or(error::Kind::Length)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<D> Decoder<D>
+where
+    D: Digest,
+{
+    /// Initialize streaming decoder
+    pub fn new() -> Self {
+        Self::with_verihash_version(VerihashVersion::default())
+    }
+
+    /// Initialize streaming decoder which computes the given version of
+    /// Verihash
+    pub fn with_verihash_version(version: VerihashVersion) -> Self {
+        Self::with_config(Config {
+            verihash_version: version,
+            ..Config::default()
+        })
+    }
+
+    /// Initialize streaming decoder with the given configuration
+    pub fn with_config(config: Config) -> Self {
+        let mut stack = heapless::Vec::new();
+
+        // The stack is empty, so pushing the toplevel message can't fail
+        let _ = stack.push(Frame::message(config.verihash_version, None, None));
+
+        Self {
+            config,
+            stack,
+            position: 0,
+            fields: 0,
+        }
+    }
+
+    /// Get the configuration of this decoder
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Get the version of Verihash computed by this decoder
+    pub fn verihash_version(&self) -> VerihashVersion {
+        self.config.verihash_version
+    }
+
+    /// Get the current position within the stream (i.e. total bytes consumed)
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the current nesting depth (0 when decoding toplevel fields)
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    /// Is the decoder at the end of a complete toplevel message, i.e. not
+    /// partway through a field or nested message?
+    pub fn is_complete(&self) -> bool {
+        self.stack.len() == 1 && self.stack[0].is_complete()
+    }
+
+    /// Process the given input data, advancing the slice for the amount of
+    /// data processed, and returning the next event (if any).
+    ///
+    /// Returns `Ok(None)` once all of the input has been consumed without
+    /// completing an event. Call this method with an empty slice at the end
+    /// of the stream to flush any remaining events.
+    pub fn decode<'a>(&mut self, input: &mut &'a [u8]) -> Result<Option<Event<'a>>, Error> {
+        // Report errors relative to the start of the stream
+        self.decode_event(input)
+            .map_err(|e| e.locate(self.position, self.path()))
+    }
+
+    /// Fill the provided slice with the digest of the toplevel message if it
+    /// fits and the message is complete
+    pub fn fill_digest(&mut self, output: &mut [u8]) -> Result<(), Error> {
+        if !self.is_complete() {
+            return Err(self.truncated());
+        }
+
+        let digest = match &mut self.stack[0] {
+            Frame::Message { decoder, .. } => decoder.compute_digest()?,
+            Frame::Sequence { .. } => None,
+        }
+        .ok_or(error::Kind::Hashing)?;
+
+        if digest.len() != output.len() {
+            return Err(error::Kind::Hashing.into());
+        }
+
+        output.copy_from_slice(&digest);
+        Ok(())
+    }
+
+    /// Get an error indicating the stream ended before the message was
+    /// complete
+    pub(crate) fn truncated(&self) -> Error {
+        Error::from(error::Kind::Truncated {
+            remaining: self.stack.get(1).and_then(Frame::remaining).unwrap_or(0),
+            wire_type: WireType::Message,
+        })
+        .locate(self.position, self.path())
+    }
+
+    /// Get the path to the field presently being decoded
+    fn path(&self) -> Path {
+        let mut path = Path::new();
+        let top = self.stack.len().saturating_sub(1);
+
+        for (i, frame) in self.stack.iter().enumerate() {
+            match frame {
+                Frame::Message {
+                    header: Some(header),
+                    decoder,
+                    ..
+                } if i < top || decoder.in_field() => path.push(Segment::Field(header.tag)),
+                Frame::Message { .. } => (),
+                Frame::Sequence { decoder, .. } => {
+                    // Elements are counted as soon as they've been consumed,
+                    // which may be before their frame has been finished
+                    let consumed =
+                        matches!(self.stack.get(i + 1).map(Frame::remaining), Some(Some(0)));
+
+                    path.push(Segment::Element(
+                        decoder.elements().saturating_sub(consumed as usize),
+                    ));
+                }
+            }
+        }
+
+        path
+    }
+
+    /// Decode the next event
+    fn decode_event<'a>(&mut self, input: &mut &'a [u8]) -> Result<Option<Event<'a>>, Error> {
+        loop {
+            if let Some(event) = self.finish_frame()? {
+                return Ok(Some(event));
+            }
+
+            let top = self.stack.last_mut().ok_or(error::Kind::Failed)?;
+            let exhausted = top.remaining() == Some(0);
+            let limit = top.remaining().unwrap_or(input.len()).min(input.len());
+            let mut chunk = &input[..limit];
+            let raw_event = top.decode(&mut chunk)?;
+
+            // A nested frame with no bytes left which can't make progress
+            // is shorter than its contents
+            if exhausted && raw_event.is_none() {
+                return Err(error::Kind::Length.into());
+            }
+
+            let consumed = limit - chunk.len();
+            let (bytes, rest) = input.split_at(consumed);
+            *input = rest;
+            self.position += consumed;
+
+            // The length of the toplevel message isn't known in advance, so
+            // it's checked as data is consumed
+            self.config.check_message_length(self.position)?;
+
+            if consumed > 0 {
+                self.consume(bytes)?;
+            }
+
+            match raw_event {
+                Some(event) => {
+                    if let Some(event) = self.handle_event(event)? {
+                        return Ok(Some(event));
+                    }
+                }
+                None if consumed == 0 || input.is_empty() => return Ok(None),
+                None => (),
+            }
+        }
+    }
+
+    /// Update the frames on the stack after the topmost one consumed the
+    /// given bytes
+    fn consume(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let (top, ancestors) = self.stack.split_last_mut().ok_or(error::Kind::Failed)?;
+
+        if let Frame::Message {
+            remaining: Some(remaining),
+            ..
+        } = top
+        {
+            *remaining = remaining
+                .checked_sub(bytes.len())
+                .ok_or(error::Kind::Length)?;
+        }
+
+        // Ancestors see the bytes as part of the nested message or sequence
+        for frame in ancestors.iter_mut().rev() {
+            frame.consume_nested(bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate an event emitted by the topmost frame
+    fn handle_event<'a>(&mut self, event: RawEvent<'a>) -> Result<Option<Event<'a>>, Error> {
+        let in_sequence = match self.stack.last() {
+            Some(Frame::Sequence { .. }) => true,
+            Some(Frame::Message { .. }) => false,
+            None => return Err(error::Kind::Failed.into()),
+        };
+
+        self.check_limits(&event)?;
+
+        Ok(Some(match event {
+            RawEvent::FieldHeader(header) => Event::FieldHeader(header),
+            RawEvent::Bool(value) => Event::Bool(value),
+            RawEvent::UInt64(value) => Event::UInt64(value),
+            RawEvent::SInt64(value) => Event::SInt64(value),
+            RawEvent::LengthDelimiter {
+                wire_type: WireType::Message,
+                length,
+            } => {
+                let tag = if in_sequence {
+                    None
+                } else {
+                    Some(self.current_tag()?)
+                };
+
+                self.push(Frame::message(
+                    self.config.verihash_version,
+                    tag,
+                    Some(length),
+                ))?;
+                Event::MessageStart { length }
+            }
+            RawEvent::LengthDelimiter { wire_type, length } => {
+                Event::ValueStart { wire_type, length }
+            }
+            RawEvent::SequenceHeader { wire_type, length } => {
+                match wire_type {
+                    WireType::False | WireType::True => {
+                        return Err(error::Kind::Decode {
+                            element: Element::SequenceHeader,
+                            wire_type,
+                        }
+                        .into())
+                    }
+                    WireType::Sequence => return Err(error::Kind::NestedSequence.into()),
+                    _ if in_sequence => return Err(error::Kind::NestedSequence.into()),
+                    _ => (),
+                }
+
+                let tag = self.current_tag()?;
+                self.push(Frame::Sequence {
+                    tag,
+                    decoder: sequence::Decoder::new(
+                        self.config.verihash_version,
+                        wire_type,
+                        length,
+                    ),
+                })?;
+
+                Event::SequenceStart { wire_type, length }
+            }
+            RawEvent::ValueChunk {
+                wire_type,
+                bytes,
+                remaining,
+            } => {
+                // Strings are presently limited to ASCII (see `string.rs`),
+                // which allows them to be checked a chunk at a time
+                if wire_type == WireType::String && !bytes.is_ascii() {
+                    return Err(error::Kind::UnicodeNormalization.into());
+                }
+
+                Event::ValueChunk {
+                    wire_type,
+                    bytes,
+                    remaining,
+                }
+            }
+        }))
+    }
+
+    /// Ensure the configured limits allow the given event emitted by the
+    /// topmost frame
+    fn check_limits(&mut self, event: &RawEvent<'_>) -> Result<(), Error> {
+        match event {
+            RawEvent::FieldHeader(_) => {
+                self.fields = self.fields.saturating_add(1);
+                self.config.check_fields(self.fields)?;
+            }
+            RawEvent::LengthDelimiter {
+                wire_type: WireType::Message,
+                length,
+            } => self.config.check_message_length(*length)?,
+            RawEvent::LengthDelimiter { wire_type, length } => {
+                self.config.check_value_length(*wire_type, *length)?
+            }
+            _ => (),
+        }
+
+        // Elements of sequences begin with a value or a length delimiter
+        if let Some(Frame::Sequence { decoder, .. }) = self.stack.last() {
+            if let RawEvent::UInt64(_) | RawEvent::SInt64(_) | RawEvent::LengthDelimiter { .. } =
+                event
+            {
+                self.config
+                    .check_sequence_elements(decoder.elements_started())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pop the topmost frame if it's a nested message or sequence which has
+    /// been completely decoded, hashing its digest into its parent
+    fn finish_frame(&mut self) -> Result<Option<Event<'static>>, Error> {
+        if self.stack.len() < 2 {
+            return Ok(None);
+        }
+
+        match self.stack.last() {
+            // Frames which have consumed all of their bytes may still have
+            // events pending (e.g. the value of a trailing boolean field)
+            Some(frame) if frame.remaining() == Some(0) && frame.is_complete() => (),
+            _ => return Ok(None),
+        }
+
+        let frame = self.stack.pop().ok_or(error::Kind::Failed)?;
+        let parent = self.stack.last_mut().ok_or(error::Kind::Failed)?;
+
+        match frame {
+            Frame::Message {
+                tag, mut decoder, ..
+            } => {
+                if let Some(digest) = decoder.compute_digest()? {
+                    match (parent, tag) {
+                        (Frame::Message { decoder, .. }, Some(tag)) => {
+                            decoder.hash_message_digest(tag, &digest)?
+                        }
+                        (Frame::Sequence { decoder, .. }, None) => {
+                            decoder.hash_message_digest(&digest)?
+                        }
+                        _ => return Err(error::Kind::Hashing.into()),
+                    }
+                }
+
+                Ok(Some(Event::MessageEnd))
+            }
+            Frame::Sequence { tag, decoder } => {
+                if let Some(digest) = decoder.compute_digest()? {
+                    match parent {
+                        Frame::Message { decoder, .. } => {
+                            decoder.hash_sequence_digest(tag, &digest)?
+                        }
+                        Frame::Sequence { .. } => return Err(error::Kind::Hashing.into()),
+                    }
+                }
+
+                Ok(Some(Event::SequenceEnd))
+            }
+        }
+    }
+
+    /// Push a nested message or sequence onto the stack
+    fn push(&mut self, frame: Frame<D>) -> Result<(), Error> {
+        // Only messages count towards the nesting depth, as with the slice
+        // decoder (where each message can contain a sequence)
+        if let Frame::Message { .. } = frame {
+            let depth = self
+                .stack
+                .iter()
+                .filter(|frame| matches!(frame, Frame::Message { .. }))
+                .count();
+
+            self.config.check_depth(depth + 1)?;
+        }
+
+        self.stack
+            .push(frame)
+            .map_err(|_| error::Kind::NestingDepth { max: MAX_DEPTH }.into())
+    }
+
+    /// Get the tag of the field presently being decoded in the topmost frame
+    fn current_tag(&self) -> Result<Tag, Error> {
+        match self.stack.last() {
+            Some(Frame::Message {
+                header: Some(header),
+                ..
+            }) => Ok(header.tag),
+            _ => Err(error::Kind::Failed.into()),
+        }
+    }
+}
+
+impl<D> Default for Decoder<D>
+where
+    D: Digest,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "alloc", feature = "sha2"))]
+mod tests {
+    use super::{Config, Decoder, Event};
+    use crate::{
+        error,
+        field::{Header, WireType},
+    };
+    use alloc::{string::ToString, vec::Vec};
+
+    type StreamDecoder = Decoder<sha2::Sha256>;
+
+    /// Decode the given input one byte at a time, collecting the events
+    fn decode_bytewise(decoder: &mut StreamDecoder, input: &[u8]) -> Vec<Event<'static>> {
+        let mut events = vec![];
+
+        for byte in input {
+            let mut chunk = core::slice::from_ref(byte);
+
+            while let Some(event) = decoder.decode(&mut chunk).unwrap() {
+                // Chunks are single bytes, so only their length is checked
+                events.push(event.with_bytes(&[]));
+            }
+        }
+
+        while let Some(event) = decoder.decode(&mut &[][..]).unwrap() {
+            events.push(event.with_bytes(&[]));
+        }
+
+        events
+    }
+
+    #[test]
+    fn decode_nested_message() {
+        // Field 1: message containing field 2: "bytes"; field 3: true
+        let input = [45, 15, 73, 11, 98, 121, 116, 101, 115, 99];
+        let mut decoder = StreamDecoder::new();
+        let events = decode_bytewise(&mut decoder, &input);
+
+        let chunk = |remaining| Event::ValueChunk {
+            wire_type: WireType::Bytes,
+            bytes: &[],
+            remaining,
+        };
+
+        assert_eq!(
+            events,
+            vec![
+                Event::FieldHeader(Header::new(1, false, WireType::Message)),
+                Event::MessageStart { length: 7 },
+                Event::FieldHeader(Header::new(2, false, WireType::Bytes)),
+                Event::ValueStart {
+                    wire_type: WireType::Bytes,
+                    length: 5
+                },
+                chunk(4),
+                chunk(3),
+                chunk(2),
+                chunk(1),
+                chunk(0),
+                Event::MessageEnd,
+                Event::FieldHeader(Header::new(3, false, WireType::True)),
+                Event::Bool(true),
+            ]
+        );
+
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.position(), input.len());
+    }
+
+    #[test]
+    fn decode_nested_trailing_bool() {
+        // Field 1: message containing field 2: true
+        let input = [45, 3, 67];
+        let mut decoder = StreamDecoder::new();
+        let events = decode_bytewise(&mut decoder, &input);
+
+        assert_eq!(
+            events,
+            vec![
+                Event::FieldHeader(Header::new(1, false, WireType::Message)),
+                Event::MessageStart { length: 1 },
+                Event::FieldHeader(Header::new(2, false, WireType::True)),
+                Event::Bool(true),
+                Event::MessageEnd,
+            ]
+        );
+
+        assert!(decoder.is_complete());
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let input = [45, 15, 73, 11, 98];
+        let mut decoder = StreamDecoder::new();
+        decode_bytewise(&mut decoder, &input);
+
+        assert!(!decoder.is_complete());
+        assert_eq!(decoder.depth(), 1);
+
+        let err = decoder.fill_digest(&mut [0u8; 32]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            error::Kind::Truncated {
+                remaining: 4,
+                wire_type: WireType::Message
+            }
+        );
+        assert_eq!(err.position(), Some(input.len()));
+    }
+
+    #[test]
+    fn decode_bad_nested_length() {
+        // Nested message claims a length of 2, but its field is 3 bytes long
+        let input = [45, 5, 73, 11, 98];
+        let mut decoder = StreamDecoder::new();
+        let mut input_ref = &input[..];
+
+        let err = loop {
+            match decoder.decode(&mut input_ref) {
+                Ok(Some(_)) => continue,
+                Ok(None) => panic!("expected error"),
+                Err(err) => break err,
+            }
+        };
+
+        assert_eq!(err.kind(), error::Kind::Length);
+        assert_eq!(err.position(), Some(4));
+        assert_eq!(err.path().unwrap().to_string(), "1.2");
+    }
+
+    #[test]
+    fn decode_bad_sequence_element_length() {
+        // Sequence body is 2 bytes long, but its element claims a length of 5
+        let input = [47, 77, 11, 99, 99, 99, 99];
+        let mut decoder = StreamDecoder::new();
+        let mut input_ref = &input[..];
+
+        let err = loop {
+            match decoder.decode(&mut input_ref) {
+                Ok(Some(_)) => continue,
+                Ok(None) => panic!("expected error"),
+                Err(err) => break err,
+            }
+        };
+
+        assert_eq!(err.kind(), error::Kind::Length);
+    }
+
+    #[test]
+    fn decode_limits() {
+        // Field 1: message containing field 2: "bytes"; field 3: true
+        let input = [45, 15, 73, 11, 98, 121, 116, 101, 115, 99];
+
+        let decode = |config| {
+            let mut decoder = StreamDecoder::with_config(config);
+            let mut input_ref = &input[..];
+
+            loop {
+                match decoder.decode(&mut input_ref) {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => return Ok(()),
+                    Err(err) => return Err(err.kind()),
+                }
+            }
+        };
+
+        assert_eq!(decode(Config::default()), Ok(()));
+
+        assert_eq!(
+            decode(Config {
+                max_depth: 1,
+                ..Config::default()
+            }),
+            Err(error::Kind::NestingDepth { max: 1 })
+        );
+
+        assert_eq!(
+            decode(Config {
+                max_bytes_length: 4,
+                ..Config::default()
+            }),
+            Err(error::Kind::ValueLength {
+                wire_type: WireType::Bytes,
+                length: 5,
+                max: 4
+            })
+        );
+
+        assert_eq!(
+            decode(Config {
+                max_message_length: 9,
+                ..Config::default()
+            }),
+            Err(error::Kind::MessageLength { length: 10, max: 9 })
+        );
+
+        assert_eq!(
+            decode(Config {
+                max_fields: 2,
+                ..Config::default()
+            }),
+            Err(error::Kind::FieldCount { max: 2 })
+        );
+    }
+
+    #[test]
+    fn sequences_dont_count_towards_depth() {
+        // Field 2: sequence of uint64s: 1, 2
+        let input = [79, 69, 3, 5];
+        let mut decoder = StreamDecoder::with_config(Config::default().max_depth(1));
+        let events = decode_bytewise(&mut decoder, &input);
+
+        assert_eq!(events.last(), Some(&Event::SequenceEnd));
+        assert!(decoder.is_complete());
+    }
+}
@@ -0,0 +1,135 @@
+//! Asynchronous event reader for `tokio::io::AsyncRead`
+
+use super::{Decoder, Event};
+use crate::error::{self, Error};
+use digest::Digest;
+use std::vec::Vec;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Default size of the [`Reader`] buffer: 8 KiB
+pub const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Reads a Veriform message from a `tokio::io::AsyncRead`, emitting
+/// streaming decoder [`Event`]s as data arrives.
+///
+/// At most one buffer's worth of data is resident at a time: `bytes` and
+/// `string` values larger than the buffer are emitted as several
+/// [`Event::ValueChunk`]s.
+///
+/// The message is expected to extend to the end of the stream. To read
+/// streams of length-delimited messages, see `veriform::framing`.
+pub struct Reader<R, D: Digest> {
+    /// Underlying reader
+    reader: R,
+
+    /// Streaming decoder
+    decoder: Decoder<D>,
+
+    /// Buffer for data read from the underlying reader
+    buffer: Vec<u8>,
+
+    /// Start of the unconsumed data in the buffer
+    start: usize,
+
+    /// End of the unconsumed data in the buffer
+    end: usize,
+
+    /// Have we reached the end of the stream?
+    eof: bool,
+}
+
+impl<R, D> Reader<R, D>
+where
+    R: AsyncRead + Unpin,
+    D: Digest,
+{
+    /// Create a new reader which decodes using the default streaming decoder
+    pub fn new(reader: R) -> Self {
+        Self::with_decoder(reader, Decoder::new())
+    }
+
+    /// Create a new reader which decodes using the given streaming decoder
+    pub fn with_decoder(reader: R, decoder: Decoder<D>) -> Self {
+        Self::with_capacity(reader, decoder, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Create a new reader with a buffer of the given size.
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(reader: R, decoder: Decoder<D>, capacity: usize) -> Self {
+        assert!(capacity > 0, "buffer capacity must be non-zero");
+
+        Self {
+            reader,
+            decoder,
+            buffer: vec![0; capacity],
+            start: 0,
+            end: 0,
+            eof: false,
+        }
+    }
+
+    /// Read the next event, returning `Ok(None)` once the end of the stream
+    /// has been reached and the message is complete.
+    ///
+    /// Returns a [`Kind::Truncated`][`error::Kind::Truncated`] error if the
+    /// stream ends partway through the message.
+    pub async fn next_event(&mut self) -> Result<Option<Event<'_>>, Error> {
+        let (event, chunk_start) = loop {
+            let mut input = &self.buffer[self.start..self.end];
+            let available = input.len();
+            let event = self.decoder.decode(&mut input)?;
+            self.start += available - input.len();
+
+            if let Some(event) = event {
+                // Chunks are always the last bytes consumed by the decoder.
+                // Detach the event from the buffer so it can be refilled
+                // in subsequent iterations, then reattach it below.
+                let chunk_len = match &event {
+                    Event::ValueChunk { bytes, .. } => bytes.len(),
+                    _ => 0,
+                };
+
+                break (event.with_bytes(&[]), self.start - chunk_len);
+            }
+
+            if self.eof {
+                return if self.decoder.is_complete() {
+                    Ok(None)
+                } else {
+                    Err(self.decoder.truncated())
+                };
+            }
+
+            let n = self
+                .reader
+                .read(&mut self.buffer)
+                .await
+                .map_err(|_| error::Kind::Io.position(self.decoder.position()))?;
+
+            self.start = 0;
+            self.end = n;
+            self.eof = n == 0;
+        };
+
+        Ok(Some(
+            event.with_bytes(&self.buffer[chunk_start..self.start]),
+        ))
+    }
+
+    /// Fill the provided slice with the digest of the message once it has
+    /// been read in its entirety
+    pub fn fill_digest(&mut self, output: &mut [u8]) -> Result<(), Error> {
+        self.decoder.fill_digest(output)
+    }
+
+    /// Borrow the streaming decoder
+    pub fn decoder(&self) -> &Decoder<D> {
+        &self.decoder
+    }
+
+    /// Get the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
@@ -27,7 +27,7 @@ pub trait DecodeRef<T: ?Sized> {
 /// Decode a sequence of values to a [`sequence::Iter`].
 ///
 /// This trait is intended to be impl'd by the `Decoder` type.
-pub trait DecodeSeq<T, D>
+pub trait DecodeSeq<T: ?Sized, D>
 where
     D: Digest,
 {
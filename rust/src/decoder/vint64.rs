@@ -23,6 +23,11 @@ impl Decoder {
         Self::default()
     }
 
+    /// Has this decoder not yet consumed any data?
+    pub fn is_empty(&self) -> bool {
+        self.pos == 0
+    }
+
     /// Decode a `vint64` from the incoming data
     pub fn decode(&mut self, input: &mut &[u8]) -> Result<Option<u64>, Error> {
         if let Some(length) = self.length {
@@ -2,49 +2,86 @@
 
 use crate::{
     decoder::sequence,
-    decoder::{DecodeSeq, Decoder},
-    encoder::Encoder,
+    decoder::{self, Decode, DecodeSeq, Decoder},
+    encoder::{Encoder, LengthTable},
     error::{self, Error},
     field::{self, Tag, WireType},
+    map::{self, Map},
     message::{Element, Message},
 };
 use digest::Digest;
 use heapless::ArrayLength;
 
 /// Make sure input has been consumed
-pub fn check_input_consumed(input: &[u8]) -> Result<(), Error> {
+pub fn check_input_consumed<D: Digest>(decoder: &Decoder<D>, input: &[u8]) -> Result<(), Error> {
     if input.is_empty() {
         Ok(())
     } else {
-        Err(error::Kind::TrailingData.into())
+        Err(decoder.annotate(error::Kind::TrailingData.into()))
     }
 }
 
-/// Decode a sequence of messages
+/// Decode an optional field, returning `None` if the next field in the input
+/// doesn't have the given tag (including when there are no more fields)
+pub fn decode_optional<T, D>(
+    decoder: &mut Decoder<D>,
+    tag: Tag,
+    input: &mut &[u8],
+) -> Result<Option<T>, Error>
+where
+    Decoder<D>: Decode<T>,
+    D: Digest,
+{
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    let header = decoder::peek_header(input).map_err(|e| decoder.annotate_field(e, tag))?;
+
+    if header.tag == tag {
+        decoder.decode(tag, input).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Decode a sequence of messages into any collection which impls
+/// [`TryExtend`] (e.g. `heapless::Vec` or, with `alloc`, `Vec`)
 // TODO(tarcieri): support other sequence types
-pub fn decode_message_seq<T, N, D>(
+pub fn decode_message_seq<T, C, D>(
     decoder: &mut Decoder<D>,
     tag: Tag,
     input: &mut &[u8],
-) -> Result<heapless::Vec<T, N>, Error>
+) -> Result<C, Error>
 where
     T: Message,
-    N: ArrayLength<T>,
+    C: Default + TryExtend<T>,
     D: Digest,
 {
-    let mut result = heapless::Vec::new();
+    let mut result = C::default();
     let seq_iter: sequence::Iter<'_, '_, T, D> = decoder.decode_seq(tag, input)?;
 
     for elem in seq_iter {
-        result.push(elem?).map_err(|_| error::Kind::Decode {
-            element: Element::Value,
-            wire_type: WireType::Sequence,
-        })?
+        result
+            .try_extend(core::iter::once(elem?))
+            .map_err(|_| error::Kind::Decode {
+                element: Element::Value,
+                wire_type: WireType::Sequence,
+            })?
     }
 
     Ok(result)
 }
 
+/// Decode a map field, ensuring its keys are in strictly ascending order
+pub fn decode_map<M, D>(decoder: &mut Decoder<D>, tag: Tag, input: &mut &[u8]) -> Result<M, Error>
+where
+    M: Map,
+    D: Digest,
+{
+    map::decode(decoder, tag, input)
+}
+
 /// Encode a sequence of messages
 pub fn encode_message_seq<T>(
     encoder: &mut Encoder<'_>,
@@ -55,38 +92,75 @@ pub fn encode_message_seq<T>(
 where
     T: Message,
 {
-    let body_len: usize = seq
-        .iter()
-        .map(|msg| {
-            // compute length with additional length prefix
-            let encoded_len = msg.encoded_len();
-            vint64::encoded_len(encoded_len as u64)
-                .checked_add(encoded_len)
-                .unwrap()
-        })
-        .sum();
-
-    encoder.message_seq(
+    encoder.messages(tag, critical, seq.iter().map(|elem| elem as &dyn Message))
+}
+
+/// Encode a sequence of messages whose lengths were recorded in the given
+/// [`LengthTable`]
+pub fn encode_message_seq_with<T>(
+    encoder: &mut Encoder<'_>,
+    tag: Tag,
+    critical: bool,
+    seq: &[T],
+    lengths: &mut LengthTable<'_>,
+) -> Result<(), Error>
+where
+    T: Message,
+{
+    encoder.messages_with(
         tag,
         critical,
-        body_len,
         seq.iter().map(|elem| elem as &dyn Message),
+        lengths,
     )
 }
 
 /// Decode tag (which identifies an enum variant)
-pub fn decode_tag(mut input: &[u8]) -> Result<Tag, Error> {
-    let header = vint64::decode(&mut input)?;
+pub fn decode_tag<D: Digest>(decoder: &Decoder<D>, mut input: &[u8]) -> Result<Tag, Error> {
+    let header = vint64::decode(&mut input).map_err(|e| decoder.annotate(e.into()))?;
     Ok(field::Header::from(header).tag)
 }
 
 /// Unknown tag in enum
-pub fn unknown_tag(tag: Tag) -> Error {
-    error::Kind::FieldHeader {
-        tag: Some(tag),
-        wire_type: None,
+pub fn unknown_tag<D: Digest>(decoder: &Decoder<D>, tag: Tag) -> Error {
+    decoder.annotate(
+        error::Kind::FieldHeader {
+            tag: Some(tag),
+            wire_type: None,
+        }
+        .into(),
+    )
+}
+
+/// Integer types which are encoded as the 64-bit integer type `W` (i.e.
+/// `u64` for `uint64` fields and `i64` for `sint64` fields), and can be
+/// losslessly widened to it
+pub trait Widen<W>: Copy {
+    /// Widen this integer to the type it's encoded as
+    fn widen(self) -> W;
+}
+
+/// Impl [`Widen`] for integer types which impl `Into` the wide type
+macro_rules! impl_widen {
+    ($wide:ty, $($int:ty),+) => {
+        $(
+            impl Widen<$wide> for $int {
+                fn widen(self) -> $wide {
+                    self.into()
+                }
+            }
+        )+
+    };
+}
+
+impl_widen!(u64, u8, u16, u32, u64);
+impl_widen!(i64, i8, i16, i32, i64);
+
+impl Widen<u64> for usize {
+    fn widen(self) -> u64 {
+        // `usize` is at most 64 bits on all supported targets
+        self as u64
     }
-    .into()
 }
 
 /// Fallible version of the `Extend` trait used for consuming Veriform
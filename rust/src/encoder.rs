@@ -1,8 +1,25 @@
 //! Veriform encoder
+//!
+//! Nested messages and sequences are prefixed with the length of their body,
+//! which is encoded as a variable-width `vint64`. Rather than computing the
+//! length of each body in advance, the encoder reserves a single byte for
+//! the prefix, writes the body, then back-patches the prefix, moving the body
+//! forward if the prefix turns out to be longer than one byte. This never
+//! needs more space in the buffer than the final encoded message.
+//!
+//! Moving the body costs a copy at every level of nesting, so for deeply
+//! nested messages the lengths can instead be computed in a single pass into
+//! a [`LengthTable`] using [`Message::encoded_len_with`], then written up
+//! front by [`Message::encode_with`].
+
+mod length_table;
+
+pub use self::length_table::LengthTable;
 
 use crate::{
     error::{self, Error},
     field::{Header, Tag, WireType},
+    map::{self, Map},
     message::Message,
     string,
 };
@@ -22,6 +39,18 @@ impl<'a> Encoder<'a> {
         Self { buffer, length: 0 }
     }
 
+    /// Write a field containing a boolean
+    pub fn boolean(&mut self, tag: Tag, critical: bool, value: bool) -> Result<(), Error> {
+        // Booleans are encoded entirely within the field header's wire type
+        let wire_type = if value {
+            WireType::True
+        } else {
+            WireType::False
+        };
+
+        self.write_header(tag, critical, wire_type)
+    }
+
     /// Write a field containing an unsigned 64-bit integer
     pub fn uint64(&mut self, tag: Tag, critical: bool, value: u64) -> Result<(), Error> {
         self.write_header(tag, critical, WireType::UInt64)?;
@@ -41,24 +70,29 @@ impl<'a> Encoder<'a> {
         critical: bool,
         message: &dyn Message,
     ) -> Result<(), Error> {
-        let encoded_len = message.encoded_len();
-
         self.write_header(tag, critical, WireType::Message)?;
-        self.write(vint64::encode(encoded_len as u64))?;
-
-        // Ensure there's remaining space in the buffer
-        if encoded_len > (self.buffer.len() - self.length) {
-            return Err(error::Kind::Length.into());
-        }
-
-        let new_length = self.length.checked_add(encoded_len).unwrap();
-        message.encode(&mut self.buffer[self.length..new_length])?;
-        self.length = new_length;
+        self.write_message(message)
+    }
 
-        Ok(())
+    /// Write a message (nested inside of a field) whose length was recorded
+    /// in the given [`LengthTable`] by [`Message::encoded_len_with`]
+    pub fn message_with(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        message: &dyn Message,
+        lengths: &mut LengthTable<'_>,
+    ) -> Result<(), Error> {
+        self.write_header(tag, critical, WireType::Message)?;
+        self.write_message_with(message, lengths)
     }
 
-    /// Write a sequence of messages (nested inside of a field)
+    /// Write a sequence of messages (nested inside of a field) whose body
+    /// is expected to be `length` bytes long.
+    ///
+    /// Returns an error if the encoded body is a different length. Use
+    /// [`Encoder::messages`] to write a sequence of messages without
+    /// computing its length in advance.
     pub fn message_seq<'m>(
         &mut self,
         tag: Tag,
@@ -66,32 +100,72 @@ impl<'a> Encoder<'a> {
         length: usize,
         messages: impl Iterator<Item = &'m dyn Message>,
     ) -> Result<(), Error> {
-        self.write_header(tag, critical, WireType::Sequence)?;
+        let body_len = self.write_message_seq(tag, critical, messages)?;
 
-        // sequence header (type + length)
-        self.write(vint64::encode(
-            (length as u64) << 4 | WireType::Message as u64,
-        ))?;
+        // Ensure we wrote the expected number of bytes
+        if body_len != length {
+            return Err(error::Kind::Length.into());
+        }
 
-        let orig_length = self.length;
+        Ok(())
+    }
 
-        for message in messages {
-            let encoded_len = message.encoded_len();
-            self.write(vint64::encode(encoded_len as u64))?;
+    /// Write a sequence of messages (nested inside of a field), computing
+    /// the length of the sequence body as it's written
+    pub fn messages<'m>(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        messages: impl Iterator<Item = &'m dyn Message>,
+    ) -> Result<(), Error> {
+        self.write_message_seq(tag, critical, messages).map(|_| ())
+    }
 
-            // Ensure there's remaining space in the buffer
-            if encoded_len > self.buffer.len().checked_sub(self.length).unwrap() {
-                return Err(error::Kind::Length.into());
-            }
+    /// Write a sequence of messages (nested inside of a field) whose lengths
+    /// were recorded in the given [`LengthTable`] by
+    /// [`field::length::message_seq_with`]
+    ///
+    /// [`field::length::message_seq_with`]: crate::field::length::message_seq_with
+    pub fn messages_with<'m>(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        messages: impl Iterator<Item = &'m dyn Message>,
+        lengths: &mut LengthTable<'_>,
+    ) -> Result<(), Error> {
+        self.write_sequence_with(
+            tag,
+            critical,
+            WireType::Message,
+            lengths,
+            |encoder, lengths| {
+                for message in messages {
+                    encoder.write_message_with(message, lengths)?;
+                }
+
+                Ok(())
+            },
+        )
+    }
 
-            let new_length = self.length.checked_add(encoded_len).unwrap();
-            message.encode(&mut self.buffer[self.length..new_length])?;
-            self.length = new_length;
-        }
+    /// Write a map (nested inside of a field) as a sequence of entry
+    /// messages in ascending key order. See the [`map`] module for details.
+    pub fn map<M: Map>(&mut self, tag: Tag, critical: bool, map: &M) -> Result<(), Error> {
+        map::encode(self, tag, critical, map)
+    }
 
-        // Ensure we wrote the expected number of bytes
-        debug_assert_eq!(length, self.length.checked_sub(orig_length).unwrap());
-        Ok(())
+    /// Write a map (nested inside of a field) whose lengths were recorded in
+    /// the given [`LengthTable`] by [`field::length::map_with`]
+    ///
+    /// [`field::length::map_with`]: crate::field::length::map_with
+    pub fn map_with<M: Map>(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        map: &M,
+        lengths: &mut LengthTable<'_>,
+    ) -> Result<(), Error> {
+        map::encode_with(self, tag, critical, map, lengths)
     }
 
     /// Write a field containing bytes
@@ -114,12 +188,198 @@ impl<'a> Encoder<'a> {
     }
 
     /// Write a field header to the underlying buffer
-    fn write_header(&mut self, tag: Tag, critical: bool, wire_type: WireType) -> Result<(), Error> {
+    pub(crate) fn write_header(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        wire_type: WireType,
+    ) -> Result<(), Error> {
         self.write(Header::new(tag, critical, wire_type).encode())
     }
 
+    /// Write a sequence of values of the given wire type (nested inside of a
+    /// field), whose elements are written by the provided function.
+    ///
+    /// Returns the length of the sequence body.
+    pub(crate) fn write_sequence<F>(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        wire_type: WireType,
+        f: F,
+    ) -> Result<usize, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<(), Error>,
+    {
+        self.write_header(tag, critical, WireType::Sequence)?;
+        let start = self.begin_prefixed()?;
+        f(self)?;
+        self.end_prefixed(start, |length| (length as u64) << 4 | wire_type as u64)
+    }
+
+    /// Write a sequence of values of the given wire type (nested inside of a
+    /// field) whose body length was recorded in the given [`LengthTable`],
+    /// and whose elements are written by the provided function.
+    pub(crate) fn write_sequence_with<F>(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        wire_type: WireType,
+        lengths: &mut LengthTable<'_>,
+        f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Self, &mut LengthTable<'_>) -> Result<(), Error>,
+    {
+        self.write_header(tag, critical, WireType::Sequence)?;
+
+        let length = lengths.next_length()?;
+        self.write(vint64::encode((length as u64) << 4 | wire_type as u64))?;
+
+        let body_start = self.length;
+        f(self, lengths)?;
+
+        // Ensure we wrote the expected number of bytes
+        if self.length - body_start != length {
+            return Err(error::Kind::Length.into());
+        }
+
+        Ok(())
+    }
+
+    /// Write a sequence of messages, returning the length of its body
+    fn write_message_seq<'m>(
+        &mut self,
+        tag: Tag,
+        critical: bool,
+        messages: impl Iterator<Item = &'m dyn Message>,
+    ) -> Result<usize, Error> {
+        self.write_sequence(tag, critical, WireType::Message, |encoder| {
+            for message in messages {
+                encoder.write_message(message)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Write a length-prefixed message to the underlying buffer
+    pub(crate) fn write_message(&mut self, message: &dyn Message) -> Result<(), Error> {
+        let start = self.begin_prefixed()?;
+        let body_len = message.encode(&mut self.buffer[self.length..])?.len();
+        self.length = self
+            .length
+            .checked_add(body_len)
+            .filter(|&length| length <= self.buffer.len())
+            .ok_or(error::Kind::Length)?;
+
+        self.end_prefixed(start, |length| length as u64).map(|_| ())
+    }
+
+    /// Write a message prefixed with the length recorded for it in the given
+    /// [`LengthTable`] to the underlying buffer
+    fn write_message_with(
+        &mut self,
+        message: &dyn Message,
+        lengths: &mut LengthTable<'_>,
+    ) -> Result<(), Error> {
+        let length = lengths.next_length()?;
+        self.write(vint64::encode(length as u64))?;
+
+        let body_len = message
+            .encode_with(&mut self.buffer[self.length..], lengths)?
+            .len();
+
+        // Ensure we wrote the expected number of bytes
+        if body_len != length {
+            return Err(error::Kind::Length.into());
+        }
+
+        self.length += body_len;
+        Ok(())
+    }
+
+    /// Write a length-prefixed value whose body is written by the provided
+    /// function
+    pub(crate) fn write_prefixed<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Self) -> Result<(), Error>,
+    {
+        let start = self.begin_prefixed()?;
+        f(self)?;
+        self.end_prefixed(start, |length| length as u64).map(|_| ())
+    }
+
+    /// Write a value prefixed with the length recorded for it in the given
+    /// [`LengthTable`], whose body is written by the provided function
+    pub(crate) fn write_prefixed_with<F>(
+        &mut self,
+        lengths: &mut LengthTable<'_>,
+        f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Self, &mut LengthTable<'_>) -> Result<(), Error>,
+    {
+        let length = lengths.next_length()?;
+        self.write(vint64::encode(length as u64))?;
+
+        let body_start = self.length;
+        f(self, lengths)?;
+
+        // Ensure we wrote the expected number of bytes
+        if self.length - body_start != length {
+            return Err(error::Kind::Length.into());
+        }
+
+        Ok(())
+    }
+
+    /// Begin writing a value prefixed with a `vint64` computed from the
+    /// length of its body, returning the position of the prefix.
+    ///
+    /// A single byte is reserved for the prefix until the body has been
+    /// written and [`Encoder::end_prefixed`] is called.
+    fn begin_prefixed(&mut self) -> Result<usize, Error> {
+        let start = self.length;
+        self.write([0])?;
+        Ok(start)
+    }
+
+    /// Finish writing a value begun with [`Encoder::begin_prefixed`],
+    /// back-patching its prefix with the `vint64` the given function
+    /// computes from the length of its body.
+    ///
+    /// Returns the length of the body.
+    fn end_prefixed<F>(&mut self, start: usize, prefix: F) -> Result<usize, Error>
+    where
+        F: FnOnce(usize) -> u64,
+    {
+        let body_start = start.checked_add(1).ok_or(error::Kind::Length)?;
+        let body_len = self
+            .length
+            .checked_sub(body_start)
+            .ok_or(error::Kind::Length)?;
+
+        let encoded_prefix = vint64::encode(prefix(body_len));
+        let prefix_bytes = encoded_prefix.as_ref();
+        let new_body_start = start + prefix_bytes.len();
+
+        // Ensure there's space to move the body after a multi-byte prefix
+        let new_length = new_body_start
+            .checked_add(body_len)
+            .filter(|&length| length <= self.buffer.len())
+            .ok_or(error::Kind::Length)?;
+
+        self.buffer
+            .copy_within(body_start..self.length, new_body_start);
+        self.buffer[start..new_body_start].copy_from_slice(prefix_bytes);
+        self.length = new_length;
+
+        Ok(body_len)
+    }
+
     /// Write a dynamically sized value to the underlying buffer
-    fn write_value(&mut self, bytes: &[u8]) -> Result<(), Error> {
+    pub(crate) fn write_value(&mut self, bytes: &[u8]) -> Result<(), Error> {
         self.write(vint64::encode(bytes.len() as u64))?;
         self.write(bytes)
     }
@@ -127,7 +387,7 @@ impl<'a> Encoder<'a> {
     /// Write the given bytes to the underlying buffer.
     ///
     /// Returns an error if the buffer has insufficient space.
-    fn write(&mut self, bytes: impl AsRef<[u8]>) -> Result<(), Error> {
+    pub(crate) fn write(&mut self, bytes: impl AsRef<[u8]>) -> Result<(), Error> {
         let bytes = bytes.as_ref();
 
         // Ensure there's remaining space in the buffer
@@ -0,0 +1,158 @@
+//! Precomputed lengths of nested messages and sequences
+
+use crate::error::{self, Error};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Table of the body lengths of the messages and sequences nested within a
+/// message.
+///
+/// The table is filled in a single pass over the message by
+/// [`Message::encoded_len_with`], then consumed in the same order by
+/// [`Message::encode_with`], which writes each length prefix up front rather
+/// than reserving space for it and moving the body once its length is known.
+///
+/// Entries are stored in the order their length prefixes appear in the
+/// encoded message, i.e. the entry for a nested message precedes the entries
+/// for the messages nested within it.
+///
+/// [`Message::encoded_len_with`]: crate::Message::encoded_len_with
+/// [`Message::encode_with`]: crate::Message::encode_with
+#[derive(Debug)]
+pub struct LengthTable<'a> {
+    /// Storage for the entries of the table
+    storage: Storage<'a>,
+
+    /// Number of entries recorded in the table
+    length: usize,
+
+    /// Position of the next entry to be consumed by the encoder
+    position: usize,
+}
+
+/// Storage for the entries of a [`LengthTable`]
+#[derive(Debug)]
+enum Storage<'a> {
+    /// Fixed-size slice provided by the caller
+    Slice(&'a mut [usize]),
+
+    /// Heap-allocated vector which grows as needed
+    #[cfg(feature = "alloc")]
+    Vec(Vec<usize>),
+}
+
+impl<'a> LengthTable<'a> {
+    /// Create a new [`LengthTable`] which stores its entries in the provided
+    /// slice.
+    ///
+    /// A message needs one entry for every message and sequence nested
+    /// within it (including the elements of sequences of messages).
+    pub fn new(slice: &'a mut [usize]) -> Self {
+        Self {
+            storage: Storage::Slice(slice),
+            length: 0,
+            position: 0,
+        }
+    }
+
+    /// Get the number of entries recorded in this table
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Is this table empty?
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Consume entries from the beginning of the table again, allowing
+    /// the same message to be encoded more than once
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+
+    /// Remove all entries from the table so it can be reused for another
+    /// message
+    pub fn clear(&mut self) {
+        #[cfg(feature = "alloc")]
+        {
+            if let Storage::Vec(vec) = &mut self.storage {
+                vec.clear();
+            }
+        }
+
+        self.length = 0;
+        self.position = 0;
+    }
+
+    /// Reserve an entry for a length which hasn't been computed yet,
+    /// returning its index.
+    ///
+    /// Returns an error if the table is full.
+    pub(crate) fn reserve(&mut self) -> Result<usize, Error> {
+        let index = self.length;
+
+        match &mut self.storage {
+            Storage::Slice(slice) => {
+                if index >= slice.len() {
+                    return Err(error::Kind::Length.into());
+                }
+            }
+            #[cfg(feature = "alloc")]
+            Storage::Vec(vec) => vec.push(0),
+        }
+
+        self.length = index + 1;
+        Ok(index)
+    }
+
+    /// Set the length at the given index
+    pub(crate) fn set(&mut self, index: usize, length: usize) {
+        self.entries_mut()[index] = length;
+    }
+
+    /// Consume the next length in the table.
+    ///
+    /// Returns an error if all entries have already been consumed.
+    pub(crate) fn next_length(&mut self) -> Result<usize, Error> {
+        let length = *self
+            .entries()
+            .get(self.position)
+            .ok_or(error::Kind::Length)?;
+
+        self.position += 1;
+        Ok(length)
+    }
+
+    /// Get the entries recorded in the table
+    fn entries(&self) -> &[usize] {
+        match &self.storage {
+            Storage::Slice(slice) => &slice[..self.length],
+            #[cfg(feature = "alloc")]
+            Storage::Vec(vec) => vec,
+        }
+    }
+
+    /// Get the entries recorded in the table mutably
+    fn entries_mut(&mut self) -> &mut [usize] {
+        match &mut self.storage {
+            Storage::Slice(slice) => &mut slice[..self.length],
+            #[cfg(feature = "alloc")]
+            Storage::Vec(vec) => vec,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+impl Default for LengthTable<'_> {
+    /// Create a new heap-allocated [`LengthTable`] which grows as needed
+    fn default() -> Self {
+        Self {
+            storage: Storage::Vec(Vec::new()),
+            length: 0,
+            position: 0,
+        }
+    }
+}
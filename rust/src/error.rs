@@ -1,5 +1,9 @@
 //! Error types
 
+mod path;
+
+pub use self::path::{Path, Segment, MAX_SEGMENTS};
+
 use crate::{
     field::{Tag, WireType},
     message::Element,
@@ -15,6 +19,9 @@ pub struct Error {
 
     /// Position inside of message where error occurred
     position: Option<usize>,
+
+    /// Path to the field where the error occurred (if known)
+    path: Option<Path>,
 }
 
 impl Error {
@@ -24,26 +31,55 @@ impl Error {
     }
 
     /// Get the position inside of the message where the error occurred
-    /// (if available)
+    /// (if available).
     ///
-    /// NOTE: support for this is an unreliable work-in-progress. Most of the
-    /// time this will return `None`.
+    /// For errors returned by the [`Decoder`][`crate::decoder::Decoder`]
+    /// this is the absolute byte offset, from the start of the toplevel
+    /// message, of the field in which the error occurred.
     pub fn position(self) -> Option<usize> {
         self.position
     }
 
-    /// Extend the position within a message (for nested messages)
-    // TODO(tarcieri): remove `#[allow(dead_code)]` attrs once this method is used
-    #[allow(dead_code)]
+    /// Get the [`Path`] from the toplevel message to the field in which the
+    /// error occurred (if available)
+    pub fn path(self) -> Option<Path> {
+        self.path
+    }
+
+    /// Offset the position of an error by the given position, e.g. the
+    /// position of a framed message within a stream (saturating on overflow)
+    #[cfg(feature = "std")]
     pub(crate) fn extend_position(self, pos: usize) -> Self {
         let new_position = self
             .position
-            .map(|old_pos| old_pos.checked_add(pos).unwrap())
+            .map(|old_pos| old_pos.saturating_add(pos))
             .unwrap_or(pos);
 
         Self {
-            kind: self.kind,
             position: Some(new_position),
+            ..self
+        }
+    }
+
+    /// Prepend a segment to the path of an error which occurred within it,
+    /// e.g. while serializing the value of a field
+    #[cfg(feature = "serde")]
+    pub(crate) fn within(mut self, segment: Segment) -> Self {
+        self.path.get_or_insert_with(Path::new).prepend(segment);
+        self
+    }
+
+    /// Set the position and path of an error which doesn't already have a
+    /// path (i.e. the innermost location an error is annotated at wins)
+    pub(crate) fn locate(self, pos: usize, path: Path) -> Self {
+        if self.path.is_some() {
+            return self;
+        }
+
+        Self {
+            kind: self.kind,
+            position: Some(pos),
+            path: Some(path),
         }
     }
 }
@@ -56,6 +92,10 @@ impl Display for Error {
             write!(f, " position={}", pos)?;
         }
 
+        if let Some(path) = self.path.filter(|path| !path.is_empty()) {
+            write!(f, " path={}", path)?;
+        }
+
         Ok(())
     }
 }
@@ -66,9 +106,44 @@ impl From<vint64::Error> for Error {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::de::Error for Error {
+    fn custom<T: Display>(_msg: T) -> Error {
+        Kind::Serde.into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(_msg: T) -> Error {
+        Kind::Serde.into()
+    }
+}
+
+#[cfg(feature = "tjson")]
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Kind::Tjson {
+            line: err.line(),
+            column: err.column(),
+        }
+        .into()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(_: std::io::Error) -> Error {
+        Kind::Io.into()
+    }
+}
+
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+#[cfg(all(feature = "serde", not(feature = "std")))]
+impl serde::de::StdError for Error {}
+
 /// Kinds of errors
 #[derive(Copy, Clone, Debug, Display, Eq, PartialEq)]
 pub enum Kind {
@@ -87,6 +162,12 @@ pub enum Kind {
     /// operation failed
     Failed,
 
+    /// too many fields: max={max:?}
+    FieldCount {
+        /// maximum total number of fields allowed
+        max: usize,
+    },
+
     /// invalid field header: tag={tag:?} wire_type={wire_type:?}
     FieldHeader {
         /// tag which identifies this field
@@ -96,18 +177,48 @@ pub enum Kind {
         wire_type: Option<WireType>,
     },
 
+    /// frame too large: size={size:?} max={max:?}
+    FrameSize {
+        /// size of the message in the frame
+        size: usize,
+
+        /// maximum allowed frame size
+        max: usize,
+    },
+
     /// hashing operation failed
     // TODO(tarcieri): collect more info
     Hashing,
 
+    /// sequence not completely decoded: remaining={remaining:?}
+    IncompleteSequence {
+        /// number of bytes remaining in the sequence
+        remaining: usize,
+    },
+
     /// invalid wire type
     InvalidWireType,
 
+    /// I/O error
+    Io,
+
     /// bad length
     Length,
 
-    /// maximum message nesting depth exceeded
-    NestingDepth,
+    /// message too long: length={length:?} max={max:?}
+    MessageLength {
+        /// length of the message
+        length: usize,
+
+        /// maximum allowed message length
+        max: usize,
+    },
+
+    /// maximum message nesting depth exceeded: max={max:?}
+    NestingDepth {
+        /// maximum allowed nesting depth, including the toplevel message
+        max: usize,
+    },
 
     /// nested sequences not presently allowed
     NestedSequence,
@@ -118,6 +229,42 @@ pub enum Kind {
         tag: Tag,
     },
 
+    /// integer value out of range for field {tag:?}
+    Range {
+        /// tag of the field containing the value
+        tag: Tag,
+    },
+
+    /// too many elements in sequence: max={max:?}
+    SequenceElements {
+        /// maximum number of elements allowed in a sequence
+        max: usize,
+    },
+
+    /// serde (de)serialization failed
+    Serde,
+
+    /// malformed text: line={line:?} column={column:?}
+    Text {
+        /// line at which the error occurred
+        line: usize,
+
+        /// column at which the error occurred
+        column: usize,
+    },
+
+    /// malformed TJSON: line={line:?} column={column:?}
+    Tjson {
+        /// line at which the error occurred
+        line: usize,
+
+        /// column at which the error occurred
+        column: usize,
+    },
+
+    /// Verihash trailer does not match message digest
+    Trailer,
+
     /// unexpected trailing data
     TrailingData,
 
@@ -148,16 +295,30 @@ pub enum Kind {
         valid_up_to: usize,
     },
 
+    /// value too long: wire_type={wire_type:?} length={length:?} max={max:?}
+    ValueLength {
+        /// wire type of the value
+        wire_type: WireType,
+
+        /// length of the value
+        length: usize,
+
+        /// maximum allowed length for values of this wire type
+        max: usize,
+    },
+
     /// `vint64` encoding error
     VInt64,
 }
 
 impl Kind {
     /// Create an error with the given position
+    #[cfg(feature = "std")]
     pub(crate) fn position(self, pos: usize) -> Error {
         Error {
             kind: self,
             position: Some(pos),
+            path: None,
         }
     }
 }
@@ -167,6 +328,7 @@ impl From<Kind> for Error {
         Self {
             kind,
             position: None,
+            path: None,
         }
     }
 }
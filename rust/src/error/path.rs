@@ -0,0 +1,184 @@
+//! Paths to the field where an error occurred
+
+use crate::{decoder::MAX_DEPTH, field::Tag};
+use core::fmt::{self, Display};
+
+/// Maximum number of segments stored in a [`Path`].
+///
+/// Every message nested inside the toplevel one adds at most two segments
+/// (its field and its index within a sequence), so this is enough to store
+/// the path to any field the decoders accept in full.
+pub const MAX_SEGMENTS: usize = 2 * MAX_DEPTH;
+
+/// Flag set on stored segments which are sequence element indexes
+const ELEMENT_FLAG: u64 = 1 << 63;
+
+/// Path from the toplevel message to the field in which an error occurred,
+/// e.g. `3.sequence[7].1` (field 1 of the message at index 7 of the sequence
+/// in field 3).
+///
+/// Paths are stored inline in a fixed-size buffer. When nesting exceeds
+/// [`MAX_SEGMENTS`], the outermost segments are discarded and the path is
+/// marked as truncated.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Path {
+    /// Segments of the path, with sequence element indexes flagged
+    segments: [u64; MAX_SEGMENTS],
+
+    /// Number of segments in the path
+    length: u8,
+
+    /// Were outer segments discarded?
+    truncated: bool,
+}
+
+impl Path {
+    /// Create a new empty path (i.e. the toplevel message)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of segments in the path
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    /// Is the path empty?
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Were the outermost segments of this path discarded because it
+    /// exceeded [`MAX_SEGMENTS`]?
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Iterate over the segments of this path, from the outermost inward
+    pub fn iter(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.segments[..self.len()]
+            .iter()
+            .map(|&segment| Segment::from_u64(segment))
+    }
+
+    /// Append a segment to the path, discarding the outermost segment if
+    /// the path is full
+    pub(crate) fn push(&mut self, segment: Segment) {
+        if self.len() == MAX_SEGMENTS {
+            self.segments.rotate_left(1);
+            self.truncated = true;
+        } else {
+            self.length += 1;
+        }
+
+        self.segments[self.len() - 1] = segment.to_u64();
+    }
+
+    /// Prepend a segment to the path, discarding it if the path is full
+    /// (i.e. it's the outermost segment)
+    #[cfg(feature = "serde")]
+    pub(crate) fn prepend(&mut self, segment: Segment) {
+        if self.len() == MAX_SEGMENTS {
+            self.truncated = true;
+            return;
+        }
+
+        let len = self.len();
+        self.segments.copy_within(..len, 1);
+        self.segments[0] = segment.to_u64();
+        self.length += 1;
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.truncated {
+            write!(f, "...")?;
+        }
+
+        for (i, segment) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+
+            write!(f, "{}", segment)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Segment of a [`Path`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Segment {
+    /// Field of a message with the given tag
+    Field(Tag),
+
+    /// Element of a sequence at the given index
+    Element(usize),
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Field(tag) => write!(f, "{}", tag),
+            Segment::Element(index) => write!(f, "sequence[{}]", index),
+        }
+    }
+}
+
+impl Segment {
+    /// Decode a segment from its stored representation
+    fn from_u64(segment: u64) -> Self {
+        if segment & ELEMENT_FLAG == 0 {
+            Segment::Field(segment)
+        } else {
+            Segment::Element((segment & !ELEMENT_FLAG) as usize)
+        }
+    }
+
+    /// Encode a segment as its stored representation
+    fn to_u64(self) -> u64 {
+        match self {
+            Segment::Field(tag) => tag & !ELEMENT_FLAG,
+            Segment::Element(index) => index as u64 | ELEMENT_FLAG,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::{Path, Segment, MAX_SEGMENTS};
+    use alloc::{string::ToString, vec::Vec};
+
+    #[test]
+    fn display() {
+        let mut path = Path::new();
+        assert_eq!(path.to_string(), "");
+
+        path.push(Segment::Field(3));
+        path.push(Segment::Element(7));
+        path.push(Segment::Field(1));
+
+        assert_eq!(path.len(), 3);
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            [Segment::Field(3), Segment::Element(7), Segment::Field(1)]
+        );
+        assert_eq!(path.to_string(), "3.sequence[7].1");
+    }
+
+    #[test]
+    fn truncated() {
+        let mut path = Path::new();
+
+        for tag in 0..=(MAX_SEGMENTS as u64) {
+            path.push(Segment::Field(tag));
+        }
+
+        assert!(path.is_truncated());
+        assert_eq!(path.len(), MAX_SEGMENTS);
+        assert_eq!(path.iter().next(), Some(Segment::Field(1)));
+        assert!(path.to_string().starts_with("...1.2.3."));
+    }
+}
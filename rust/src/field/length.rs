@@ -1,7 +1,18 @@
 //! Field length calculations for various types
 
 use super::{Header, Tag, WireType};
-use crate::message::Message;
+use crate::{
+    encoder::LengthTable,
+    map::{self, Map},
+    message::Message,
+    Error,
+};
+
+/// Compute length of a `bool` field
+pub fn boolean(tag: Tag) -> usize {
+    // Booleans are encoded entirely within the field header
+    header(tag, WireType::True)
+}
 
 /// Compute length of a `uint64` field
 pub fn uint64(tag: Tag, value: u64) -> usize {
@@ -37,9 +48,63 @@ pub fn message_seq<'a>(tag: Tag, messages: impl Iterator<Item = &'a dyn Message>
         })
         .sum();
 
-    header(tag, WireType::Sequence)
-        + vint64::encoded_len((body_len as u64) << 4 | WireType::Message as u64)
-        + body_len
+    message_seq_len(tag, body_len)
+}
+
+/// Compute length of a `message` field including the tag and delimiter,
+/// recording the length of the message in the given [`LengthTable`]
+pub fn message_with(
+    tag: Tag,
+    message: &dyn Message,
+    lengths: &mut LengthTable<'_>,
+) -> Result<usize, Error> {
+    let body_len = nested_message(message, lengths)?;
+    Ok(dynamically_sized(tag, WireType::Message, body_len))
+}
+
+/// Compute length of a `sequence` of `message` values including the tag and
+/// delimiter, recording the lengths of the sequence and each of its messages
+/// in the given [`LengthTable`]
+pub fn message_seq_with<'a>(
+    tag: Tag,
+    messages: impl Iterator<Item = &'a dyn Message>,
+    lengths: &mut LengthTable<'_>,
+) -> Result<usize, Error> {
+    let index = lengths.reserve()?;
+    let mut body_len = 0;
+
+    for msg in messages {
+        let encoded_len = nested_message(msg, lengths)?;
+        body_len += vint64::encoded_len(encoded_len as u64) + encoded_len;
+    }
+
+    lengths.set(index, body_len);
+    Ok(message_seq_len(tag, body_len))
+}
+
+/// Compute length of a map field (i.e. a `sequence` of entry messages)
+/// including the tag and delimiter
+pub fn map<M: Map>(tag: Tag, map: &M) -> usize {
+    message_seq_len(tag, map::body_len(map))
+}
+
+/// Compute length of a map field including the tag and delimiter, recording
+/// the lengths of the sequence and each of its entries in the given
+/// [`LengthTable`]
+pub fn map_with<M: Map>(tag: Tag, map: &M, lengths: &mut LengthTable<'_>) -> Result<usize, Error> {
+    let index = lengths.reserve()?;
+    let body_len = map::body_len_with(map, lengths)?;
+    lengths.set(index, body_len);
+    Ok(message_seq_len(tag, body_len))
+}
+
+/// Compute length of the body of a nested message, recording it in the given
+/// [`LengthTable`] ahead of the lengths of any messages nested within it
+fn nested_message(message: &dyn Message, lengths: &mut LengthTable<'_>) -> Result<usize, Error> {
+    let index = lengths.reserve()?;
+    let encoded_len = message.encoded_len_with(lengths)?;
+    lengths.set(index, encoded_len);
+    Ok(encoded_len)
 }
 
 /// Compute length of a field header
@@ -48,6 +113,14 @@ fn header(tag: Tag, wire_type: WireType) -> usize {
     Header::new(tag, false, wire_type).encoded_len()
 }
 
+/// Compute length of a `sequence` of `message` values with the given body
+/// length including the tag and delimiter
+fn message_seq_len(tag: Tag, body_len: usize) -> usize {
+    header(tag, WireType::Sequence)
+        + vint64::encoded_len((body_len as u64) << 4 | WireType::Message as u64)
+        + body_len
+}
+
 /// Compute length of a dynamically sized field
 fn dynamically_sized(tag: Tag, wire_type: WireType, length: usize) -> usize {
     header(tag, wire_type) + vint64::encoded_len(length as u64) + length
@@ -57,6 +130,11 @@ fn dynamically_sized(tag: Tag, wire_type: WireType, length: usize) -> usize {
 mod tests {
     use super::*;
 
+    #[test]
+    fn boolean_length() {
+        assert_eq!(boolean(42), 2);
+    }
+
     #[test]
     fn uint64_length() {
         assert_eq!(uint64(1, 42), 2);
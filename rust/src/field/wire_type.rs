@@ -4,7 +4,10 @@ pub use crate::{
     error::{self, Error},
     message::Element,
 };
-use core::convert::TryFrom;
+use core::{
+    convert::TryFrom,
+    fmt::{self, Display},
+};
 
 /// Wire type identifiers for Veriform types
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -67,6 +70,21 @@ impl WireType {
     }
 }
 
+impl Display for WireType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WireType::False => "false",
+            WireType::True => "true",
+            WireType::UInt64 => "uint64",
+            WireType::SInt64 => "sint64",
+            WireType::Bytes => "bytes",
+            WireType::String => "string",
+            WireType::Message => "message",
+            WireType::Sequence => "sequence",
+        })
+    }
+}
+
 impl TryFrom<u64> for WireType {
     type Error = Error;
 
@@ -0,0 +1,328 @@
+//! Length-delimited framing for streams of Veriform messages.
+//!
+//! Veriform messages aren't self-delimiting, so sending several of them
+//! back-to-back over a stream (e.g. a TCP connection or a pipe) requires a
+//! framing layer. Each frame consists of:
+//!
+//! ```text
+//! vint64(message length) || message [|| Verihash trailer]
+//! ```
+//!
+//! When [`Config::verihash_trailer`] is enabled, every message is followed by
+//! its Verihash digest, which is checked when the frame is decoded.
+//!
+//! Frames can be read using [`Reader`], a blocking iterator over a
+//! `std::io::Read`, and written using [`Writer`]. When the `async` feature is
+//! enabled, [`Codec`] provides a `tokio_util::codec` [`Encoder`] and
+//! [`Decoder`] for use with e.g. `tokio_util::codec::Framed`.
+//!
+//! Errors which occur while decoding a message report their position
+//! relative to the start of the stream, rather than the start of the message.
+//!
+//! [`Encoder`]: https://docs.rs/tokio-util/latest/tokio_util/codec/trait.Encoder.html
+//! [`Decoder`]: https://docs.rs/tokio-util/latest/tokio_util/codec/trait.Decoder.html
+
+#[cfg(feature = "async")]
+mod codec;
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use self::codec::Codec;
+
+use crate::{
+    decoder::Decoder,
+    encoder::LengthTable,
+    error::{self, Error},
+    field::WireType,
+    value::Object,
+    verihash::{DigestOutput, VerihashVersion},
+    Message,
+};
+use core::{convert::TryFrom, marker::PhantomData};
+use digest::Digest;
+use std::{io, vec::Vec};
+
+/// Default maximum size of a message in a frame: 1 MiB
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// Framing configuration
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Maximum size of a message in a frame (excluding the length prefix and
+    /// trailer). Frames containing larger messages are rejected.
+    pub max_frame_size: usize,
+
+    /// Append the Verihash digest of each message to its frame, and check it
+    /// when the frame is decoded
+    pub verihash_trailer: bool,
+
+    /// Version of Verihash used to compute trailers
+    pub verihash_version: VerihashVersion,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            verihash_trailer: false,
+            verihash_version: VerihashVersion::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Get the length of the trailer of each frame
+    fn trailer_len<D: Digest>(&self) -> usize {
+        if self.verihash_trailer {
+            D::output_size()
+        } else {
+            0
+        }
+    }
+
+    /// Ensure a message of the given length can be framed
+    fn check_frame_size(&self, size: usize, position: usize) -> Result<(), Error> {
+        if size > self.max_frame_size {
+            Err(error::Kind::FrameSize {
+                size,
+                max: self.max_frame_size,
+            }
+            .position(position))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Encode a message as a frame, appending it to the given buffer
+    fn encode_frame<M, D>(&self, message: &M, buffer: &mut Vec<u8>) -> Result<(), Error>
+    where
+        M: Message,
+        D: Digest,
+    {
+        let mut lengths = LengthTable::default();
+        let body_len = message.encoded_len_with(&mut lengths)?;
+        self.check_frame_size(body_len, 0)?;
+
+        buffer.extend_from_slice(vint64::encode(body_len as u64).as_ref());
+
+        let body_start = buffer.len();
+        buffer.resize(body_start + body_len, 0);
+        message.encode_with(&mut buffer[body_start..], &mut lengths)?;
+
+        if self.verihash_trailer {
+            let mut decoder = Decoder::<D>::with_verihash_version(self.verihash_version);
+            Object::decode(&mut decoder, &buffer[body_start..])?;
+
+            let mut digest = DigestOutput::<D>::default();
+            decoder.fill_digest(&mut digest)?;
+            buffer.extend_from_slice(&digest);
+        }
+
+        Ok(())
+    }
+
+    /// Decode the body of a frame located at the given position in the
+    /// stream, checking its trailer (if enabled)
+    fn decode_frame<M, D>(&self, frame: &[u8], position: usize) -> Result<M, Error>
+    where
+        M: Message,
+        D: Digest,
+    {
+        let (body, trailer) = frame.split_at(frame.len() - self.trailer_len::<D>());
+        let mut decoder = Decoder::<D>::with_verihash_version(self.verihash_version);
+        let message = M::decode(&mut decoder, body).map_err(|e| e.extend_position(position))?;
+
+        if self.verihash_trailer {
+            let mut digest = DigestOutput::<D>::default();
+            decoder
+                .fill_digest(&mut digest)
+                .map_err(|e| e.extend_position(position))?;
+
+            if &digest[..] != trailer {
+                return Err(error::Kind::Trailer.position(position + body.len()));
+            }
+        }
+
+        Ok(message)
+    }
+}
+
+/// Blocking iterator over the messages in a stream of frames
+pub struct Reader<R, M, D> {
+    /// Underlying reader
+    reader: R,
+
+    /// Framing configuration
+    config: Config,
+
+    /// Position within the stream (i.e. total bytes consumed)
+    position: usize,
+
+    /// Buffer for the current frame
+    buffer: Vec<u8>,
+
+    /// Did an error occur? (the stream can't be resynchronized afterward)
+    failed: bool,
+
+    /// Message and digest types
+    types: PhantomData<fn() -> (M, D)>,
+}
+
+impl<R, M, D> Reader<R, M, D>
+where
+    R: io::Read,
+    M: Message,
+    D: Digest,
+{
+    /// Create a new frame reader
+    pub fn new(reader: R, config: Config) -> Self {
+        Self {
+            reader,
+            config,
+            position: 0,
+            buffer: Vec::new(),
+            failed: false,
+            types: PhantomData,
+        }
+    }
+
+    /// Get the current position within the stream
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read the next frame, returning `None` at the end of the stream
+    fn read_frame(&mut self) -> Result<Option<M>, Error> {
+        let mut prefix = [0u8; vint64::MAX_BYTES];
+
+        loop {
+            match self.reader.read(&mut prefix[..1]) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return Err(error::Kind::Io.position(self.position)),
+            }
+        }
+
+        let frame_start = self.position;
+        let prefix_len = vint64::decoded_len(prefix[0]);
+        self.read_exact(&mut prefix[1..prefix_len], frame_start + 1)?;
+
+        let body_len = vint64::decode(&mut &prefix[..prefix_len])
+            .map_err(|_| error::Kind::VInt64.position(frame_start))?;
+
+        let body_len = usize::try_from(body_len).unwrap_or(usize::MAX);
+        self.config.check_frame_size(body_len, frame_start)?;
+        self.position = frame_start + prefix_len;
+
+        let mut buffer = core::mem::take(&mut self.buffer);
+        let frame_len = body_len + self.config.trailer_len::<D>();
+        buffer.resize(frame_len, 0);
+
+        let result = self
+            .read_exact(&mut buffer, self.position)
+            .and_then(|_| self.config.decode_frame::<M, D>(&buffer, self.position));
+
+        self.position += frame_len;
+        self.buffer = buffer;
+        result.map(Some)
+    }
+
+    /// Read exactly enough bytes to fill the buffer, which begins at the given
+    /// position within the stream
+    fn read_exact(&mut self, buffer: &mut [u8], position: usize) -> Result<(), Error> {
+        let mut filled = 0;
+
+        while filled < buffer.len() {
+            match self.reader.read(&mut buffer[filled..]) {
+                Ok(0) => {
+                    return Err(error::Kind::Truncated {
+                        remaining: buffer.len() - filled,
+                        wire_type: WireType::Message,
+                    }
+                    .position(position + filled))
+                }
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return Err(error::Kind::Io.position(position + filled)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R, M, D> Iterator for Reader<R, M, D>
+where
+    R: io::Read,
+    M: Message,
+    D: Digest,
+{
+    type Item = Result<M, Error>;
+
+    fn next(&mut self) -> Option<Result<M, Error>> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self.read_frame().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Writer for streams of frames
+pub struct Writer<W, D> {
+    /// Underlying writer
+    writer: W,
+
+    /// Framing configuration
+    config: Config,
+
+    /// Buffer for the current frame
+    buffer: Vec<u8>,
+
+    /// Digest type
+    digest: PhantomData<fn() -> D>,
+}
+
+impl<W, D> Writer<W, D>
+where
+    W: io::Write,
+    D: Digest,
+{
+    /// Create a new frame writer
+    pub fn new(writer: W, config: Config) -> Self {
+        Self {
+            writer,
+            config,
+            buffer: Vec::new(),
+            digest: PhantomData,
+        }
+    }
+
+    /// Write a message as a frame
+    pub fn write<M: Message>(&mut self, message: &M) -> Result<(), Error> {
+        self.buffer.clear();
+        self.config
+            .encode_frame::<M, D>(message, &mut self.buffer)?;
+        self.writer
+            .write_all(&self.buffer)
+            .map_err(|_| error::Kind::Io.into())
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(|_| error::Kind::Io.into())
+    }
+
+    /// Get the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
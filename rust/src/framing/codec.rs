@@ -0,0 +1,158 @@
+//! `tokio_util::codec` support for Veriform frames
+
+use super::Config;
+use crate::{
+    error::{self, Error},
+    field::WireType,
+    Message,
+};
+use bytes::{Buf, BytesMut};
+use core::{convert::TryFrom, marker::PhantomData};
+use digest::Digest;
+use std::vec::Vec;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec for encoding and decoding Veriform frames with `tokio_util::codec`,
+/// e.g. using `FramedRead`, `FramedWrite`, or `Framed`.
+pub struct Codec<M, D> {
+    /// Framing configuration
+    config: Config,
+
+    /// Position within the decoded stream (i.e. total bytes consumed)
+    position: usize,
+
+    /// Message and digest types
+    types: PhantomData<fn() -> (M, D)>,
+}
+
+impl<M, D> Codec<M, D>
+where
+    M: Message,
+    D: Digest,
+{
+    /// Create a new codec with the given configuration
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            position: 0,
+            types: PhantomData,
+        }
+    }
+
+    /// Get the current position within the decoded stream
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the length of the length prefix and total length of the frame at
+    /// the start of the buffer, if enough of it has been received
+    fn frame_len(&self, src: &[u8]) -> Result<Option<(usize, usize)>, Error> {
+        let prefix_len = match src.first() {
+            Some(&byte) => vint64::decoded_len(byte),
+            None => return Ok(None),
+        };
+
+        if src.len() < prefix_len {
+            return Ok(None);
+        }
+
+        let body_len = vint64::decode(&mut &src[..prefix_len])
+            .map_err(|_| error::Kind::VInt64.position(self.position))?;
+
+        let body_len = usize::try_from(body_len).unwrap_or(usize::MAX);
+        self.config.check_frame_size(body_len, self.position)?;
+
+        Ok(Some((
+            prefix_len,
+            prefix_len + body_len + self.config.trailer_len::<D>(),
+        )))
+    }
+}
+
+impl<M, D> Default for Codec<M, D>
+where
+    M: Message,
+    D: Digest,
+{
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl<M, D> Decoder for Codec<M, D>
+where
+    M: Message,
+    D: Digest,
+{
+    type Item = M;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<M>, Error> {
+        let (prefix_len, frame_len) = match self.frame_len(src)? {
+            Some(lengths) => lengths,
+            None => return Ok(None),
+        };
+
+        if src.len() < frame_len {
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(prefix_len);
+        let frame = src.split_to(frame_len - prefix_len);
+        let body_position = self.position + prefix_len;
+        self.position += frame_len;
+
+        self.config
+            .decode_frame::<M, D>(&frame, body_position)
+            .map(Some)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<M>, Error> {
+        if let Some(message) = self.decode(src)? {
+            return Ok(Some(message));
+        }
+
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        let expected = match self.frame_len(src)? {
+            Some((_, frame_len)) => frame_len,
+            None => vint64::decoded_len(src[0]),
+        };
+
+        Err(error::Kind::Truncated {
+            remaining: expected - src.len(),
+            wire_type: WireType::Message,
+        }
+        .position(self.position + src.len()))
+    }
+}
+
+impl<M, D> Encoder<M> for Codec<M, D>
+where
+    M: Message,
+    D: Digest,
+{
+    type Error = Error;
+
+    fn encode(&mut self, message: M, dst: &mut BytesMut) -> Result<(), Error> {
+        Encoder::<&M>::encode(self, &message, dst)
+    }
+}
+
+impl<'a, M, D> Encoder<&'a M> for Codec<M, D>
+where
+    M: Message,
+    D: Digest,
+{
+    type Error = Error;
+
+    fn encode(&mut self, message: &'a M, dst: &mut BytesMut) -> Result<(), Error> {
+        let mut frame = Vec::new();
+        self.config.encode_frame::<M, D>(message, &mut frame)?;
+        dst.extend_from_slice(&frame);
+        Ok(())
+    }
+}
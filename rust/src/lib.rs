@@ -14,6 +14,35 @@
 //! is enabled, custom derive is available for this trait for both structs
 //! and enums.
 //!
+//! # Reflection
+//!
+//! Message types can also impl the [`reflect::Reflect`] trait, which
+//! provides a descriptor of their schema (i.e. the name, tag and type of each
+//! field) for inspecting messages at runtime. When the `veriform_derive`
+//! feature is enabled, it can be derived using `#[derive(Reflect)]`
+//! alongside `#[derive(Message)]`.
+//!
+//! # Serde
+//!
+//! When the `serde` and `alloc` features of this crate are enabled, types
+//! which impl `Serialize` and `Deserialize` can be encoded as Veriform
+//! messages using the functions in the [`serde`] module.
+//!
+//! # Text Format
+//!
+//! When the `alloc` and `sha2` features of this crate are enabled, messages
+//! can be converted to and from a human-readable text format suitable for
+//! configuration files and test fixtures using the functions in the
+//! [`text`] module.
+//!
+//! # TJSON
+//!
+//! When the `tjson` feature of this crate is enabled, messages can be
+//! converted to and from [TJSON], a tagged superset of JSON, using the
+//! functions in the [`tjson`] module.
+//!
+//! [TJSON]: https://www.tjson.org/
+//!
 //! # Built-in Types
 //!
 //! Veriform has a small "standard library" of so-called "built-in types" which
@@ -40,14 +69,30 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
-#[cfg(any(feature = "tai64", feature = "uuid"))]
 pub mod builtins;
 pub mod decoder;
 pub mod encoder;
 pub mod error;
 pub mod field;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod framing;
+pub mod map;
 pub mod message;
+pub mod reflect;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "serde", feature = "alloc"))))]
+pub mod serde;
 mod string;
+#[cfg(all(feature = "alloc", feature = "sha2"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "sha2"))))]
+pub mod text;
+#[cfg(feature = "tjson")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tjson")))]
+pub mod tjson;
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub mod value;
 mod verihash;
 
 #[cfg(feature = "veriform_derive")]
@@ -59,7 +104,7 @@ pub use digest;
 // Re-export the `vint64` crate
 pub use vint64;
 
-pub use crate::{encoder::Encoder, error::Error, message::Message};
+pub use crate::{encoder::Encoder, error::Error, message::Message, verihash::VerihashVersion};
 
 /// Veriform decoder with the default SHA-256 hash
 #[cfg(feature = "sha2")]
@@ -0,0 +1,669 @@
+//! Map fields: collections of key/value pairs.
+//!
+//! Maps are encoded as a `sequence` of entry messages, each of which
+//! contains the key of the entry as field `0` and its value as field `1`:
+//!
+//! ```text
+//! message Entry {
+//!     key[0]: K,
+//!     value[1]: V
+//! }
+//! ```
+//!
+//! Entries MUST be sorted by their keys in strictly ascending order (which
+//! also means keys are unique). This is enforced when encoding and decoding,
+//! so the encoding (and therefore the Verihash digest) of a map is
+//! independent of the order in which its entries were inserted.
+//!
+//! Integer keys are ordered numerically, and `bytes`/`string` keys are
+//! ordered lexicographically by their bytes. Keys can be `u64`, `i64`, or
+//! (with the `alloc` feature) `Vec<u8>` and `String`, and values can be any
+//! of these types along with `bool`, `heapless::Vec<u8, N>`,
+//! `heapless::String<N>` or any [`Message`].
+//!
+//! Maps can be stored in a [`BTreeMap`] (with the `alloc` feature) or in a
+//! [`heapless::FnvIndexMap`] (or any other [`heapless::IndexMap`]).
+
+use crate::{
+    decoder::{sequence, Decode, DecodeRef, DecodeSeq, Decoder},
+    encoder::{Encoder, LengthTable},
+    error::{self, Error},
+    field::{self, Tag, WireType},
+    message::Message,
+};
+use digest::Digest;
+use hash32::{BuildHasher, Hash};
+use heapless::{ArrayLength, Bucket, IndexMap, Pos};
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Tag of the key field of a map entry
+pub const KEY_TAG: Tag = 0;
+
+/// Tag of the value field of a map entry
+pub const VALUE_TAG: Tag = 1;
+
+/// Types which can be the value of an entry in a map
+pub trait MapValue: Sized {
+    /// Encode this value as a (non-critical) field with the given tag
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error>;
+
+    /// Compute the length of this value when encoded as a field with the
+    /// given tag
+    fn encoded_len(&self, tag: Tag) -> usize;
+
+    /// Encode this value as a field with the given tag, using the lengths of
+    /// any nested messages recorded in the given [`LengthTable`]
+    fn encode_field_with(
+        &self,
+        encoder: &mut Encoder<'_>,
+        tag: Tag,
+        _lengths: &mut LengthTable<'_>,
+    ) -> Result<(), Error> {
+        self.encode_field(encoder, tag)
+    }
+
+    /// Compute the length of this value when encoded as a field with the
+    /// given tag, recording the lengths of any nested messages in the given
+    /// [`LengthTable`]
+    fn encoded_len_with(&self, tag: Tag, _lengths: &mut LengthTable<'_>) -> Result<usize, Error> {
+        Ok(self.encoded_len(tag))
+    }
+
+    /// Decode a value from the field with the given tag
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error>;
+}
+
+/// Types which can be the key of an entry in a map.
+///
+/// The [`Ord`] impl of a key type MUST order keys as described in the
+/// [module documentation](self).
+pub trait MapKey: MapValue + Ord {}
+
+/// Collections of key/value pairs which can be encoded as map fields
+pub trait Map: Default {
+    /// Type of the keys of the map
+    type Key: MapKey;
+
+    /// Type of the values of the map
+    type Value: MapValue;
+
+    /// Call the given function with each entry of the map, in ascending
+    /// order of their keys
+    fn for_each_entry<'a, F>(&'a self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(&'a Self::Key, &'a Self::Value) -> Result<(), Error>;
+
+    /// Call the given function with each entry of the map, in no particular
+    /// order (e.g. when computing the map's length, which doesn't depend on
+    /// the order of its entries)
+    fn for_each_entry_unordered<'a, F>(&'a self, f: F)
+    where
+        F: FnMut(&'a Self::Key, &'a Self::Value);
+
+    /// Get the greatest key in the map (if it's non-empty)
+    fn last_key(&self) -> Option<&Self::Key>;
+
+    /// Insert an entry into the map whose key is greater than any key
+    /// already present, returning an error if the map is full
+    fn push_entry(&mut self, key: Self::Key, value: Self::Value) -> Result<(), Error>;
+}
+
+/// Entry in a map
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry<K, V> {
+    /// Key of the entry
+    pub key: K,
+
+    /// Value of the entry
+    pub value: V,
+}
+
+impl<K, V> Entry<K, V>
+where
+    K: MapKey,
+    V: MapValue,
+{
+    /// Write the fields of an entry with the given key and value
+    fn encode_fields(encoder: &mut Encoder<'_>, key: &K, value: &V) -> Result<(), Error> {
+        key.encode_field(encoder, KEY_TAG)?;
+        value.encode_field(encoder, VALUE_TAG)
+    }
+
+    /// Compute the length of the body of an entry with the given key and
+    /// value
+    fn fields_len(key: &K, value: &V) -> usize {
+        key.encoded_len(KEY_TAG) + value.encoded_len(VALUE_TAG)
+    }
+}
+
+impl<K, V> Message for Entry<K, V>
+where
+    K: MapKey,
+    V: MapValue,
+{
+    fn decode<D>(decoder: &mut Decoder<D>, mut input: &[u8]) -> Result<Self, Error>
+    where
+        D: Digest,
+    {
+        let key = K::decode_field(decoder, KEY_TAG, &mut input)?;
+        let value = V::decode_field(decoder, VALUE_TAG, &mut input)?;
+
+        if !input.is_empty() {
+            return Err(decoder.annotate(error::Kind::TrailingData.into()));
+        }
+
+        Ok(Self { key, value })
+    }
+
+    fn encode<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        let mut encoder = Encoder::new(buffer);
+        Self::encode_fields(&mut encoder, &self.key, &self.value)?;
+        Ok(encoder.finish())
+    }
+
+    fn encoded_len(&self) -> usize {
+        Self::fields_len(&self.key, &self.value)
+    }
+}
+
+/// Decode a map (nested inside of a field with the given tag), ensuring its
+/// keys are in strictly ascending order
+#[cfg_attr(not(feature = "veriform_derive"), allow(dead_code))]
+pub(crate) fn decode<M, D>(
+    decoder: &mut Decoder<D>,
+    tag: Tag,
+    input: &mut &[u8],
+) -> Result<M, Error>
+where
+    M: Map,
+    D: Digest,
+{
+    let mut result = M::default();
+    let mut seq_iter: sequence::Iter<'_, '_, Entry<M::Key, M::Value>, D> =
+        decoder.decode_seq(tag, input)?;
+
+    while let Some(entry) = seq_iter.next() {
+        let entry = entry?;
+
+        if result
+            .last_key()
+            .map(|last| *last >= entry.key)
+            .unwrap_or(false)
+        {
+            return Err(seq_iter.reject(error::Kind::Order { tag }.into()));
+        }
+
+        result
+            .push_entry(entry.key, entry.value)
+            .map_err(|e| seq_iter.reject(e))?;
+    }
+
+    Ok(result)
+}
+
+/// Write a map (nested inside of a field)
+pub(crate) fn encode<M: Map>(
+    encoder: &mut Encoder<'_>,
+    tag: Tag,
+    critical: bool,
+    map: &M,
+) -> Result<(), Error> {
+    encoder
+        .write_sequence(tag, critical, WireType::Message, |encoder| {
+            let mut last_key = None;
+
+            map.for_each_entry(|key, value| {
+                ensure_ascending(tag, &mut last_key, key)?;
+                encoder.write_prefixed(|encoder| Entry::encode_fields(encoder, key, value))
+            })
+        })
+        .map(|_| ())
+}
+
+/// Write a map (nested inside of a field) whose lengths were recorded in the
+/// given [`LengthTable`] by [`body_len_with`]
+pub(crate) fn encode_with<M: Map>(
+    encoder: &mut Encoder<'_>,
+    tag: Tag,
+    critical: bool,
+    map: &M,
+    lengths: &mut LengthTable<'_>,
+) -> Result<(), Error> {
+    encoder.write_sequence_with(
+        tag,
+        critical,
+        WireType::Message,
+        lengths,
+        |encoder, lengths| {
+            let mut last_key = None;
+
+            map.for_each_entry(|key, value| {
+                ensure_ascending(tag, &mut last_key, key)?;
+
+                encoder.write_prefixed_with(lengths, |encoder, lengths| {
+                    key.encode_field_with(encoder, KEY_TAG, lengths)?;
+                    value.encode_field_with(encoder, VALUE_TAG, lengths)
+                })
+            })
+        },
+    )
+}
+
+/// Compute the length of the body of a map
+pub(crate) fn body_len<M: Map>(map: &M) -> usize {
+    let mut body_len = 0;
+
+    map.for_each_entry_unordered(|key, value| {
+        let entry_len = Entry::fields_len(key, value);
+        body_len += vint64::encoded_len(entry_len as u64) + entry_len;
+    });
+
+    body_len
+}
+
+/// Compute the length of the body of a map, recording the lengths of each of
+/// its entries (along with any messages nested in them) in the given
+/// [`LengthTable`]
+pub(crate) fn body_len_with<M: Map>(
+    map: &M,
+    lengths: &mut LengthTable<'_>,
+) -> Result<usize, Error> {
+    let mut body_len = 0;
+
+    map.for_each_entry(|key, value| {
+        let index = lengths.reserve()?;
+        let entry_len =
+            key.encoded_len_with(KEY_TAG, lengths)? + value.encoded_len_with(VALUE_TAG, lengths)?;
+        lengths.set(index, entry_len);
+
+        body_len += vint64::encoded_len(entry_len as u64) + entry_len;
+        Ok(())
+    })?;
+
+    Ok(body_len)
+}
+
+/// Ensure map keys are in strictly ascending order
+fn ensure_ascending<'a, K: Ord>(
+    tag: Tag,
+    last_key: &mut Option<&'a K>,
+    key: &'a K,
+) -> Result<(), Error> {
+    if last_key.map(|last| last >= key).unwrap_or(false) {
+        return Err(error::Kind::Order { tag }.into());
+    }
+
+    *last_key = Some(key);
+    Ok(())
+}
+
+impl MapValue for bool {
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.boolean(tag, false, *self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::boolean(tag)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        decoder.decode(tag, input)
+    }
+}
+
+impl MapValue for u64 {
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.uint64(tag, false, *self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::uint64(tag, *self)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        decoder.decode(tag, input)
+    }
+}
+
+impl MapKey for u64 {}
+
+impl MapValue for i64 {
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.sint64(tag, false, *self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::sint64(tag, *self)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        decoder.decode(tag, input)
+    }
+}
+
+impl MapKey for i64 {}
+
+impl<N> MapValue for heapless::Vec<u8, N>
+where
+    N: ArrayLength<u8>,
+{
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.bytes(tag, false, self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::bytes(tag, self)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        let bytes: &[u8] = decoder.decode_ref(tag, input)?;
+        heapless::Vec::from_slice(bytes).map_err(|_| WireType::Bytes.decoding_error())
+    }
+}
+
+impl<N> MapValue for heapless::String<N>
+where
+    N: ArrayLength<u8>,
+{
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.string(tag, false, self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::string(tag, self)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        let string: &str = decoder.decode_ref(tag, input)?;
+        let mut result = heapless::String::new();
+        result
+            .push_str(string)
+            .map_err(|_| WireType::String.decoding_error())?;
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl MapValue for Vec<u8> {
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.bytes(tag, false, self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::bytes(tag, self)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        let bytes: &[u8] = decoder.decode_ref(tag, input)?;
+        Ok(bytes.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl MapKey for Vec<u8> {}
+
+#[cfg(feature = "alloc")]
+impl MapValue for String {
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.string(tag, false, self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::string(tag, self)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        let string: &str = decoder.decode_ref(tag, input)?;
+        Ok(string.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl MapKey for String {}
+
+impl<M: Message> MapValue for M {
+    fn encode_field(&self, encoder: &mut Encoder<'_>, tag: Tag) -> Result<(), Error> {
+        encoder.message(tag, false, self)
+    }
+
+    fn encoded_len(&self, tag: Tag) -> usize {
+        field::length::message(tag, self)
+    }
+
+    fn encode_field_with(
+        &self,
+        encoder: &mut Encoder<'_>,
+        tag: Tag,
+        lengths: &mut LengthTable<'_>,
+    ) -> Result<(), Error> {
+        encoder.message_with(tag, false, self, lengths)
+    }
+
+    fn encoded_len_with(&self, tag: Tag, lengths: &mut LengthTable<'_>) -> Result<usize, Error> {
+        field::length::message_with(tag, self, lengths)
+    }
+
+    fn decode_field<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        decoder.decode(tag, input)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> Map for BTreeMap<K, V>
+where
+    K: MapKey,
+    V: MapValue,
+{
+    type Key = K;
+    type Value = V;
+
+    fn for_each_entry<'a, F>(&'a self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&'a K, &'a V) -> Result<(), Error>,
+    {
+        for (key, value) in self {
+            f(key, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn for_each_entry_unordered<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(&'a K, &'a V),
+    {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+
+    fn last_key(&self) -> Option<&K> {
+        self.keys().next_back()
+    }
+
+    fn push_entry(&mut self, key: K, value: V) -> Result<(), Error> {
+        self.insert(key, value);
+        Ok(())
+    }
+}
+
+/// Maps which preserve insertion order (e.g. [`heapless::FnvIndexMap`]) are
+/// encoded in ascending key order by repeatedly selecting the next smallest
+/// key, which takes quadratic time but doesn't require allocating.
+impl<K, V, N, S> Map for IndexMap<K, V, N, S>
+where
+    K: MapKey + Hash,
+    V: MapValue,
+    N: ArrayLength<Bucket<K, V>> + ArrayLength<Option<Pos>>,
+    S: BuildHasher + Default,
+{
+    type Key = K;
+    type Value = V;
+
+    fn for_each_entry<'a, F>(&'a self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&'a K, &'a V) -> Result<(), Error>,
+    {
+        let mut last_key: Option<&K> = None;
+
+        for _ in 0..self.len() {
+            let (key, value) = self
+                .iter()
+                .filter(|(key, _)| last_key.map(|last| *key > last).unwrap_or(true))
+                .min_by(|(a, _), (b, _)| a.cmp(b))
+                .ok_or(error::Kind::Order { tag: KEY_TAG })?;
+
+            f(key, value)?;
+            last_key = Some(key);
+        }
+
+        Ok(())
+    }
+
+    fn for_each_entry_unordered<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(&'a K, &'a V),
+    {
+        for (key, value) in self {
+            f(key, value);
+        }
+    }
+
+    fn last_key(&self) -> Option<&K> {
+        self.keys().max()
+    }
+
+    fn push_entry(&mut self, key: K, value: V) -> Result<(), Error> {
+        self.insert(key, value)
+            .map(|_| ())
+            .map_err(|_| WireType::Sequence.decoding_error())
+    }
+}
+
+#[cfg(all(test, feature = "alloc", feature = "sha2"))]
+mod tests {
+    use super::{decode as decode_map, Entry};
+    use crate::{
+        encoder::{Encoder, LengthTable},
+        error,
+        field::length,
+        Decoder, Message,
+    };
+    use alloc::{
+        collections::BTreeMap,
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use heapless::{consts::U8, FnvIndexMap};
+
+    /// Encode a map as field 1 of a message
+    fn encode_map<M: super::Map>(map: &M) -> Vec<u8> {
+        let mut buffer = vec![0u8; length::map(1, map)];
+        let mut encoder = Encoder::new(&mut buffer);
+        encoder.map(1, false, map).unwrap();
+        assert_eq!(encoder.finish().len(), buffer.len());
+        buffer
+    }
+
+    #[test]
+    fn insertion_order_independent() {
+        let mut btree = BTreeMap::new();
+        let mut index: FnvIndexMap<i64, String, U8> = FnvIndexMap::new();
+
+        for (key, value) in &[(3, "c"), (-1, "a"), (2, "b")] {
+            btree.insert(*key, String::from(*value));
+            index.insert(*key, String::from(*value)).unwrap();
+        }
+
+        let encoded = encode_map(&btree);
+        assert_eq!(encoded, encode_map(&index));
+
+        let mut decoder = Decoder::new();
+        let mut input = encoded.as_slice();
+        let decoded: BTreeMap<i64, String> = decode_map(&mut decoder, 1, &mut input).unwrap();
+        assert_eq!(btree, decoded);
+    }
+
+    #[test]
+    fn encode_with_lengths() {
+        let mut map = BTreeMap::new();
+        map.insert(-1i64, String::from("x"));
+        map.insert(1i64, String::from("y"));
+
+        let mut entries = [0usize; 3];
+        let mut lengths = LengthTable::new(&mut entries);
+        let len = length::map_with(1, &map, &mut lengths).unwrap();
+        assert_eq!(len, length::map(1, &map));
+
+        let mut buffer = vec![0u8; len];
+        let mut encoder = Encoder::new(&mut buffer);
+        encoder.map_with(1, false, &map, &mut lengths).unwrap();
+        assert_eq!(encoder.finish(), encode_map(&map).as_slice());
+    }
+
+    #[test]
+    fn reject_unordered_keys() {
+        let entries = [
+            Entry {
+                key: 2u64,
+                value: 2u64,
+            },
+            Entry {
+                key: 1u64,
+                value: 1u64,
+            },
+        ];
+
+        let mut buffer = [0u8; 32];
+        let mut encoder = Encoder::new(&mut buffer);
+        encoder
+            .messages(1, false, entries.iter().map(|e| e as &dyn Message))
+            .unwrap();
+        let encoded = encoder.finish();
+
+        let mut decoder = Decoder::new();
+        let mut input = encoded;
+        let err = decode_map::<BTreeMap<u64, u64>, _>(&mut decoder, 1, &mut input).unwrap_err();
+        assert_eq!(err.kind(), error::Kind::Order { tag: 1 });
+        assert_eq!(err.position(), Some(8));
+        assert_eq!(err.path().unwrap().to_string(), "1.sequence[1]");
+    }
+}
@@ -5,11 +5,14 @@
 //
 // Copyright (c) 2017 Dan Burkert and released under the Apache 2.0 license.
 
-use crate::{decoder::Decoder, Error};
+use crate::{decoder::Decoder, encoder::LengthTable, Error};
 use digest::Digest;
 
 #[cfg(feature = "alloc")]
-use alloc::vec::Vec;
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
 
 /// Veriform messages.
 ///
@@ -35,16 +38,92 @@ pub trait Message {
     /// Get the length of a message after being encoded as Veriform.
     fn encoded_len(&self) -> usize;
 
+    /// Encode this message as Veriform into the provided buffer using the
+    /// lengths of its nested messages and sequences recorded in the given
+    /// [`LengthTable`] by [`Message::encoded_len_with`].
+    ///
+    /// The default implementation ignores the table and calls
+    /// [`Message::encode`].
+    fn encode_with<'a>(
+        &self,
+        buffer: &'a mut [u8],
+        lengths: &mut LengthTable<'_>,
+    ) -> Result<&'a [u8], Error> {
+        let _ = lengths;
+        self.encode(buffer)
+    }
+
+    /// Get the length of a message after being encoded as Veriform,
+    /// recording the lengths of its nested messages and sequences in the
+    /// given [`LengthTable`] for use by [`Message::encode_with`].
+    ///
+    /// The default implementation records nothing and calls
+    /// [`Message::encoded_len`].
+    fn encoded_len_with(&self, lengths: &mut LengthTable<'_>) -> Result<usize, Error> {
+        let _ = lengths;
+        Ok(self.encoded_len())
+    }
+
     /// Encode this message as Veriform, allocating returning a byte vector
     /// on success.
     #[cfg(feature = "alloc")]
     fn encode_vec(&self) -> Result<Vec<u8>, Error> {
-        let mut encoded = vec![0; self.encoded_len()];
-        self.encode(&mut encoded)?;
+        let mut lengths = LengthTable::default();
+        let mut encoded = vec![0; self.encoded_len_with(&mut lengths)?];
+        self.encode_with(&mut encoded, &mut lengths)?;
         Ok(encoded)
     }
 }
 
+/// Impl [`Message`] for smart pointers to messages, which are encoded
+/// transparently as the message they point to.
+///
+/// These allow recursive message types, e.g. an `enum` with a `Box<Self>`
+/// variant.
+#[cfg(feature = "alloc")]
+macro_rules! impl_message_for_pointer {
+    ($($pointer:ident),+) => {
+        $(
+            #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+            impl<M: Message> Message for $pointer<M> {
+                fn decode<D>(decoder: &mut Decoder<D>, input: &[u8]) -> Result<Self, Error>
+                where
+                    D: Digest,
+                {
+                    M::decode(decoder, input).map($pointer::new)
+                }
+
+                fn encode<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
+                    M::encode(self, buffer)
+                }
+
+                fn encoded_len(&self) -> usize {
+                    M::encoded_len(self)
+                }
+
+                fn encode_with<'a>(
+                    &self,
+                    buffer: &'a mut [u8],
+                    lengths: &mut LengthTable<'_>,
+                ) -> Result<&'a [u8], Error> {
+                    M::encode_with(self, buffer, lengths)
+                }
+
+                fn encoded_len_with(&self, lengths: &mut LengthTable<'_>) -> Result<usize, Error> {
+                    M::encoded_len_with(self, lengths)
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_message_for_pointer!(Box, Rc);
+
+// `Arc` is only available on targets with atomic pointers
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl_message_for_pointer!(Arc);
+
 /// Elements of a message (used for errors)
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Element {
@@ -0,0 +1,199 @@
+//! Runtime reflection: descriptors of the schemas of message types.
+//!
+//! Types which impl [`Reflect`] provide a [`MessageDescriptor`] describing
+//! each of their fields, which can be used to inspect messages (e.g. by
+//! generic validators or pretty printers) without knowing their Rust type.
+//!
+//! When the `veriform_derive` feature is enabled, [`Reflect`] can be derived
+//! for types which derive [`Message`]:
+//!
+//! ```ignore
+//! use veriform::{reflect::Reflect, Message};
+//!
+//! #[derive(Message, Reflect)]
+//! pub struct Example {
+//!     #[field(tag = 0, wire_type = "uint64")]
+//!     pub value: u64,
+//! }
+//! ```
+//!
+//! The descriptors of messages nested in a derived type are referenced from
+//! its own, so every nested message type MUST impl [`Reflect`] as well.
+//! `Box`, `Rc` and `Arc` share the descriptor of the message they point to.
+//!
+//! Descriptors contain only `'static` data and are usable in `no_std`
+//! environments.
+
+use crate::{
+    field::{Tag, WireType},
+    message::Message,
+};
+
+/// Message types which can describe their own schema
+pub trait Reflect: Message {
+    /// Descriptor of this message type
+    const DESCRIPTOR: MessageDescriptor;
+}
+
+#[cfg(feature = "veriform_derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "veriform_derive")))]
+pub use veriform_derive::Reflect;
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+impl<M: Reflect> Reflect for alloc::boxed::Box<M> {
+    const DESCRIPTOR: MessageDescriptor = M::DESCRIPTOR;
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+impl<M: Reflect> Reflect for alloc::rc::Rc<M> {
+    const DESCRIPTOR: MessageDescriptor = M::DESCRIPTOR;
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+impl<M: Reflect> Reflect for alloc::sync::Arc<M> {
+    const DESCRIPTOR: MessageDescriptor = M::DESCRIPTOR;
+}
+
+/// Get the descriptor of a message type.
+///
+/// Function pointers to instantiations of this function are used to
+/// reference nested descriptors, which allows descriptors of recursive types.
+pub fn descriptor<T: Reflect>() -> &'static MessageDescriptor {
+    &T::DESCRIPTOR
+}
+
+/// Descriptor of a message type
+#[derive(Copy, Clone, Debug)]
+pub struct MessageDescriptor {
+    /// Name of the message type
+    pub name: &'static str,
+
+    /// Kind of message type
+    pub kind: MessageKind,
+
+    /// Fields of the message, in the order they're defined
+    pub fields: &'static [FieldDescriptor],
+}
+
+impl MessageDescriptor {
+    /// Get the descriptor of the field with the given tag
+    pub fn field(&self, tag: Tag) -> Option<&'static FieldDescriptor> {
+        self.fields.iter().find(|field| field.tag == tag)
+    }
+
+    /// Get the descriptor of the field with the given name
+    pub fn field_by_name(&self, name: &str) -> Option<&'static FieldDescriptor> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Kinds of message types
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MessageKind {
+    /// Message containing all of its fields (derived from a `struct`)
+    Struct,
+
+    /// Message containing exactly one of its fields (derived from an `enum`)
+    Enum,
+}
+
+/// Descriptor of a field in a message
+#[derive(Copy, Clone, Debug)]
+pub struct FieldDescriptor {
+    /// Name of the field (or enum variant)
+    pub name: &'static str,
+
+    /// Tag which identifies the field
+    pub tag: Tag,
+
+    /// Type of the field
+    pub ty: FieldType,
+
+    /// Is this field critical?
+    pub critical: bool,
+
+    /// Is this field optional?
+    pub optional: bool,
+
+    /// Size of a fixed-size field
+    pub size: Option<usize>,
+
+    /// Minimum size of a variable-sized field
+    pub min: Option<usize>,
+
+    /// Maximum size of a variable-sized field
+    pub max: Option<usize>,
+
+    /// Descriptor of the nested message type (or the type of the elements of
+    /// a sequence of messages)
+    pub nested: Option<fn() -> &'static MessageDescriptor>,
+}
+
+impl FieldDescriptor {
+    /// Get the descriptor of the nested message type, if this field contains
+    /// messages
+    pub fn nested_descriptor(&self) -> Option<&'static MessageDescriptor> {
+        self.nested.map(|nested| nested())
+    }
+}
+
+/// Types of fields.
+///
+/// These correspond to wire types, except booleans, which are encoded as
+/// either [`WireType::False`] or [`WireType::True`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FieldType {
+    /// Boolean values
+    Bool,
+
+    /// 64-bit unsigned integer
+    UInt64,
+
+    /// 64-bit (zigzag) signed integer
+    SInt64,
+
+    /// Binary data
+    Bytes,
+
+    /// Unicode string
+    String,
+
+    /// Nested Veriform message
+    Message,
+
+    /// Sequences of other types
+    Sequence,
+
+    /// Maps, i.e. sequences of key/value entry messages
+    Map,
+}
+
+impl FieldType {
+    /// Can values of this type be encoded with the given wire type?
+    pub fn is_wire_type(self, wire_type: WireType) -> bool {
+        match self {
+            FieldType::Bool => wire_type == WireType::False || wire_type == WireType::True,
+            FieldType::UInt64 => wire_type == WireType::UInt64,
+            FieldType::SInt64 => wire_type == WireType::SInt64,
+            FieldType::Bytes => wire_type == WireType::Bytes,
+            FieldType::String => wire_type == WireType::String,
+            FieldType::Message => wire_type == WireType::Message,
+            FieldType::Sequence | FieldType::Map => wire_type == WireType::Sequence,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldType, WireType};
+
+    #[test]
+    fn bool_wire_types() {
+        assert!(FieldType::Bool.is_wire_type(WireType::False));
+        assert!(FieldType::Bool.is_wire_type(WireType::True));
+        assert!(!FieldType::Bool.is_wire_type(WireType::UInt64));
+    }
+}
@@ -0,0 +1,123 @@
+//! [Serde] support: serialize and deserialize Veriform messages using
+//! `#[derive(Serialize, Deserialize)]` rather than `#[derive(Message)]`.
+//!
+//! Structs are serialized as messages. Field tags are assigned in
+//! declaration order starting from `0`, unless a field is renamed to a
+//! decimal integer using e.g. `#[serde(rename = "3")]`, in which case that
+//! integer is used as its tag:
+//!
+//! ```text
+//! #[derive(Serialize, Deserialize)]
+//! struct Example {
+//!     foo: u64,          // tag 0
+//!     #[serde(rename = "3")]
+//!     bar: String,       // tag 3
+//! }
+//! ```
+//!
+//! Other types are mapped onto the Veriform data model as follows:
+//!
+//! - `bool`, unsigned and signed integers: `bool`, `uint64` and `sint64`
+//! - `str`, `String` and `char`: `string`
+//! - byte slices (e.g. via `serde_bytes`): `bytes`
+//! - `Option`: the field is omitted when `None`
+//! - sequences: `sequence` (all elements must have the same wire type)
+//! - tuples, unit, and unit structs: messages with fields tagged by position
+//! - unit enum variants: `uint64` containing the variant's tag
+//! - all other enum variants: a message with a single field containing the
+//!   variant's value, tagged by the variant's tag
+//!
+//! Floating point numbers and maps are not supported.
+//!
+//! Fields are never marked critical, and empty sequences are encoded with
+//! an element type of `uint64`.
+//!
+//! # Serialization
+//!
+//! [`Serializer`] is a tree builder rather than a streaming encoder: it
+//! serializes values into a schema-less [`Value`], which [`to_vec`] then
+//! encodes. This requires `alloc`, and involves an intermediate copy of the
+//! serialized data.
+//!
+//! # Deserialization
+//!
+//! Unknown fields of structs are ignored, unless the critical bit is set in
+//! their header, in which case deserialization fails.
+//!
+//! # Verihash
+//!
+//! [`Deserializer`] decodes messages using a [`Decoder`], which computes the
+//! Verihash digest of the message as it's being deserialized. After
+//! deserialization has completed, the digest can be obtained using
+//! [`Decoder::fill_digest`].
+//!
+//! # Errors
+//!
+//! Serde reports errors using the Veriform [`Error`] type, with a
+//! [`Kind`](crate::error::Kind) of `Serde` for errors raised by
+//! `Serialize` and `Deserialize` implementations (e.g. missing fields).
+//! These errors don't include the message describing them, but they are
+//! annotated with the location (i.e. position and/or path) at which they
+//! occurred.
+//!
+//! [Serde]: https://serde.rs/
+
+mod de;
+mod ser;
+
+pub use self::{
+    de::Deserializer,
+    ser::{SerializeMessage, SerializeSequence, Serializer},
+};
+
+use crate::{
+    decoder::Decoder,
+    error::{self, Error},
+    field::Tag,
+    value::Value,
+    Message,
+};
+use alloc::vec::Vec;
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+
+/// Serialize a value as a Veriform message, returning a byte vector
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    match value.serialize(Serializer)? {
+        Some(Value::Message(object)) => object.encode_vec(),
+        _ => Err(error::Kind::Serde.into()),
+    }
+}
+
+/// Deserialize a value from a Veriform message using the given [`Decoder`]
+pub fn from_slice_with_decoder<'de, T, D>(
+    decoder: &mut Decoder<D>,
+    input: &'de [u8],
+) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+    D: Digest,
+{
+    T::deserialize(Deserializer::new(decoder, input)).map_err(|e| decoder.annotate(e))
+}
+
+/// Deserialize a value from a Veriform message
+#[cfg(feature = "sha2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sha2")))]
+pub fn from_slice<'de, T: Deserialize<'de>>(input: &'de [u8]) -> Result<T, Error> {
+    from_slice_with_decoder(&mut crate::Decoder::new(), input)
+}
+
+/// Compute the tag of a field (or enum variant) from its position and name
+fn field_tag(index: usize, name: &str) -> Tag {
+    name.parse().unwrap_or(index as Tag)
+}
+
+/// Find the name of the field (or enum variant) with the given tag
+fn field_name(fields: &'static [&'static str], tag: Tag) -> Option<&'static str> {
+    fields
+        .iter()
+        .enumerate()
+        .find(|(index, name)| field_tag(*index, name) == tag)
+        .map(|(_, name)| *name)
+}
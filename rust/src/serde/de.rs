@@ -0,0 +1,660 @@
+//! Serde deserializer
+
+use super::field_name;
+use crate::{
+    decoder::{peek_header, peek_sequence_type, sequence, Decode, DecodeRef, DecodeSeq, Decoder},
+    error::{self, Error},
+    field::{Tag, WireType},
+    message::Element,
+};
+use alloc::string::ToString;
+use digest::Digest;
+use serde::{
+    de::{
+        self,
+        value::{BorrowedBytesDeserializer, BorrowedStrDeserializer},
+        DeserializeSeed, IntoDeserializer, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+/// Serde deserializer for Veriform messages.
+///
+/// Decodes a message using the provided [`Decoder`], computing its Verihash
+/// digest in the process.
+pub struct Deserializer<'a, 'de, D: Digest> {
+    /// Veriform decoder
+    decoder: &'a mut Decoder<D>,
+
+    /// Remaining input in the message being deserialized
+    input: &'de [u8],
+}
+
+impl<'a, 'de, D> Deserializer<'a, 'de, D>
+where
+    D: Digest,
+{
+    /// Create a new deserializer for the message contained in the given input
+    pub fn new(decoder: &'a mut Decoder<D>, input: &'de [u8]) -> Self {
+        Self { decoder, input }
+    }
+
+    /// Ensure the message has been completely consumed
+    fn finish<T>(&self, value: T) -> Result<T, Error> {
+        if self.input.is_empty() {
+            Ok(value)
+        } else {
+            Err(self.decoder.annotate(error::Kind::TrailingData.into()))
+        }
+    }
+
+    /// Get a deserializer for the value of the next field in the message
+    fn field(&mut self) -> Result<FieldDeserializer<'_, 'de, D>, Error> {
+        let header = peek_header(self.input).map_err(|e| self.decoder.annotate(e))?;
+
+        Ok(FieldDeserializer {
+            decoder: self.decoder,
+            input: &mut self.input,
+            tag: header.tag,
+            wire_type: header.wire_type,
+        })
+    }
+
+    /// Deserialize the value of the next field in the message using the
+    /// provided function, annotating any errors with the field's location
+    fn deserialize_field<T, F>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(FieldDeserializer<'_, 'de, D>) -> Result<T, Error>,
+    {
+        let position = self.decoder.position();
+        let field = self.field()?;
+        let tag = field.tag;
+
+        f(field).map_err(|e| self.decoder.annotate_field_at(e, position, tag))
+    }
+}
+
+impl<'a, 'de, D> de::Deserializer<'de> for Deserializer<'a, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value, Error> {
+        let value = visitor.visit_map(MessageAccess::new(&mut self, None))?;
+        self.finish(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        mut self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let value = visitor.visit_map(MessageAccess::new(&mut self, Some(fields)))?;
+        self.finish(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        mut self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let value = visitor.visit_seq(TupleAccess {
+            de: &mut self,
+            index: 0,
+            len,
+        })?;
+
+        self.finish(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        mut self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let value = visitor.visit_enum(VariantAccess {
+            de: &mut self,
+            variants,
+        })?;
+
+        self.finish(value)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.finish(())?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf seq identifier
+    }
+}
+
+/// Deserializer for the value of a field in a message
+struct FieldDeserializer<'r, 'de, D: Digest> {
+    /// Veriform decoder
+    decoder: &'r mut Decoder<D>,
+
+    /// Remaining input in the message containing this field
+    input: &'r mut &'de [u8],
+
+    /// Tag of the field
+    tag: Tag,
+
+    /// Wire type of the field
+    wire_type: WireType,
+}
+
+impl<'r, 'de, D> FieldDeserializer<'r, 'de, D>
+where
+    D: Digest,
+{
+    /// Deserialize a nested message using the provided function
+    fn nested<T, F>(self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(Deserializer<'_, 'de, D>) -> Result<T, Error>,
+    {
+        if self.wire_type != WireType::Message {
+            return Err(error::Kind::UnexpectedWireType {
+                actual: self.wire_type,
+                wanted: WireType::Message,
+            }
+            .into());
+        }
+
+        self.decoder
+            .decode_nested(self.tag, self.input, |decoder, body| {
+                f(Deserializer::new(decoder, body))
+            })
+    }
+}
+
+impl<'r, 'de, D> de::Deserializer<'de> for FieldDeserializer<'r, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.wire_type {
+            WireType::False | WireType::True => {
+                let value: bool = self.decoder.decode(self.tag, self.input)?;
+                visitor.visit_bool(value)
+            }
+            WireType::UInt64 => {
+                let value: u64 = self.decoder.decode(self.tag, self.input)?;
+                visitor.visit_u64(value)
+            }
+            WireType::SInt64 => {
+                let value: i64 = self.decoder.decode(self.tag, self.input)?;
+                visitor.visit_i64(value)
+            }
+            WireType::Bytes => {
+                let bytes: &[u8] = self.decoder.decode_ref(self.tag, self.input)?;
+                visitor.visit_borrowed_bytes(bytes)
+            }
+            WireType::String => {
+                let string: &str = self.decoder.decode_ref(self.tag, self.input)?;
+                visitor.visit_borrowed_str(string)
+            }
+            WireType::Message => self.nested(|de| de.deserialize_map(visitor)),
+            WireType::Sequence => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.wire_type != WireType::Sequence {
+            return self.deserialize_any(visitor);
+        }
+
+        let (decoder, tag, input) = (self.decoder, self.tag, self.input);
+
+        let elements = match peek_sequence_type(input)? {
+            WireType::UInt64 => Elements::UInt64(decoder.decode_seq(tag, input)?),
+            WireType::SInt64 => Elements::SInt64(decoder.decode_seq(tag, input)?),
+            WireType::Bytes => Elements::Bytes(decoder.decode_seq(tag, input)?),
+            WireType::String => Elements::String(decoder.decode_seq(tag, input)?),
+            WireType::Message => {
+                Elements::Message(decoder.decode_seq_of(tag, WireType::Message, input)?)
+            }
+            WireType::Sequence => return Err(error::Kind::NestedSequence.into()),
+            wire_type => {
+                return Err(error::Kind::Decode {
+                    element: Element::SequenceHeader,
+                    wire_type,
+                }
+                .into())
+            }
+        };
+
+        let mut access = SequenceAccess(elements);
+        let value = visitor.visit_seq(&mut access)?;
+
+        if access.is_empty()? {
+            Ok(value)
+        } else {
+            Err(error::Kind::TrailingData.into())
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.nested(|de| de.deserialize_struct(name, fields, visitor))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.nested(|de| de.deserialize_map(visitor))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.nested(|de| de.deserialize_tuple(len, visitor))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if self.wire_type == WireType::UInt64 {
+            let tag: u64 = self.decoder.decode(self.tag, self.input)?;
+            visitor.visit_enum(UnitVariantAccess { tag, variants })
+        } else {
+            self.nested(|de| de.deserialize_enum(name, variants, visitor))
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.nested(|de| de.deserialize_unit(visitor))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf identifier
+    }
+}
+
+impl<'r, 'de, D> de::VariantAccess<'de> for FieldDeserializer<'r, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        self.nested(|de| de.finish(()))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+}
+
+/// Deserializer for field tags and enum variants
+struct TagDeserializer {
+    /// Tag of the field
+    tag: Tag,
+
+    /// Name of the field, if the tag corresponds to a known field
+    name: Option<&'static str>,
+}
+
+impl<'de> de::Deserializer<'de> for TagDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u64(self.tag)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Unknown tags are identified by their decimal representation, which
+        // can't be confused with the name of a field tagged by its position
+        match self.name {
+            Some(name) => visitor.visit_str(name),
+            None => visitor.visit_string(self.tag.to_string()),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum ignored_any
+    }
+}
+
+/// Access to the fields of a message as a map
+struct MessageAccess<'r, 'a, 'de, D: Digest> {
+    /// Message deserializer
+    de: &'r mut Deserializer<'a, 'de, D>,
+
+    /// Names of the fields of the struct being deserialized (if any)
+    fields: Option<&'static [&'static str]>,
+}
+
+impl<'r, 'a, 'de, D> MessageAccess<'r, 'a, 'de, D>
+where
+    D: Digest,
+{
+    /// Create a new message accessor
+    fn new(de: &'r mut Deserializer<'a, 'de, D>, fields: Option<&'static [&'static str]>) -> Self {
+        Self { de, fields }
+    }
+}
+
+impl<'r, 'a, 'de, D> de::MapAccess<'de> for MessageAccess<'r, 'a, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.de.input.is_empty() {
+            return Ok(None);
+        }
+
+        let header = peek_header(self.de.input).map_err(|e| self.de.decoder.annotate(e))?;
+        let tag = header.tag;
+        let name = self.fields.and_then(|fields| field_name(fields, tag));
+
+        // Unknown fields of structs are ignored unless they're critical
+        if self.fields.is_some() && name.is_none() && header.critical {
+            let err = error::Kind::FieldHeader {
+                tag: Some(tag),
+                wire_type: Some(header.wire_type),
+            };
+
+            return Err(self.de.decoder.annotate_field(err.into(), tag));
+        }
+
+        seed.deserialize(TagDeserializer { tag, name }).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        self.de.deserialize_field(|field| seed.deserialize(field))
+    }
+}
+
+/// Access to the fields of a message as a tuple
+struct TupleAccess<'r, 'a, 'de, D: Digest> {
+    /// Message deserializer
+    de: &'r mut Deserializer<'a, 'de, D>,
+
+    /// Position of the next element
+    index: usize,
+
+    /// Number of elements in the tuple
+    len: usize,
+}
+
+impl<'r, 'a, 'de, D> de::SeqAccess<'de> for TupleAccess<'r, 'a, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.index == self.len || self.de.input.is_empty() {
+            return Ok(None);
+        }
+
+        let index = self.index as Tag;
+        self.index += 1;
+
+        self.de
+            .deserialize_field(|field| {
+                if field.tag != index {
+                    return Err(error::Kind::FieldHeader {
+                        tag: Some(field.tag),
+                        wire_type: Some(field.wire_type),
+                    }
+                    .into());
+                }
+
+                seed.deserialize(field)
+            })
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+/// Access to the variant of an enum encoded as a message with a single field
+struct VariantAccess<'r, 'a, 'de, D: Digest> {
+    /// Message deserializer
+    de: &'r mut Deserializer<'a, 'de, D>,
+
+    /// Names of the enum's variants
+    variants: &'static [&'static str],
+}
+
+impl<'r, 'a, 'de, D> de::EnumAccess<'de> for VariantAccess<'r, 'a, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+    type Variant = FieldDeserializer<'r, 'de, D>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let field = self.de.field()?;
+        let name = field_name(self.variants, field.tag);
+        let value = seed.deserialize(TagDeserializer {
+            tag: field.tag,
+            name,
+        })?;
+
+        Ok((value, field))
+    }
+}
+
+/// Access to the variant of a unit-only enum encoded as `uint64`
+struct UnitVariantAccess {
+    /// Tag of the variant
+    tag: Tag,
+
+    /// Names of the enum's variants
+    variants: &'static [&'static str],
+}
+
+impl<'de> de::EnumAccess<'de> for UnitVariantAccess {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let value = seed.deserialize(TagDeserializer {
+            tag: self.tag,
+            name: field_name(self.variants, self.tag),
+        })?;
+
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for UnitVariantAccess {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, _seed: T) -> Result<T::Value, Error> {
+        Err(error::Kind::Serde.into())
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Error> {
+        Err(error::Kind::Serde.into())
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Error> {
+        Err(error::Kind::Serde.into())
+    }
+}
+
+/// Marker type for sequences of messages, which are decoded using the
+/// deserializer rather than [`Message`](crate::Message)
+enum Nested {}
+
+/// Sequence iterators for the supported element types
+enum Elements<'a, 'de, D: Digest> {
+    UInt64(sequence::Iter<'a, 'de, u64, D>),
+    SInt64(sequence::Iter<'a, 'de, i64, D>),
+    Bytes(sequence::Iter<'a, 'de, [u8], D>),
+    String(sequence::Iter<'a, 'de, str, D>),
+    Message(sequence::Iter<'a, 'de, Nested, D>),
+}
+
+/// Access to the elements of a sequence
+struct SequenceAccess<'a, 'de, D: Digest>(Elements<'a, 'de, D>);
+
+impl<'a, 'de, D> SequenceAccess<'a, 'de, D>
+where
+    D: Digest,
+{
+    /// Have all of the elements in the sequence been consumed?
+    fn is_empty(&mut self) -> Result<bool, Error> {
+        de::SeqAccess::next_element::<de::IgnoredAny>(self).map(|elem| elem.is_none())
+    }
+}
+
+impl<'a, 'de, D> de::SeqAccess<'de> for SequenceAccess<'a, 'de, D>
+where
+    D: Digest,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match &mut self.0 {
+            Elements::UInt64(iter) => match iter.next().transpose()? {
+                Some(value) => seed.deserialize(value.into_deserializer()).map(Some),
+                None => Ok(None),
+            },
+            Elements::SInt64(iter) => match iter.next().transpose()? {
+                Some(value) => seed.deserialize(value.into_deserializer()).map(Some),
+                None => Ok(None),
+            },
+            Elements::Bytes(iter) => match iter.next().transpose()? {
+                Some(bytes) => seed
+                    .deserialize(BorrowedBytesDeserializer::new(bytes))
+                    .map(Some),
+                None => Ok(None),
+            },
+            Elements::String(iter) => match iter.next().transpose()? {
+                Some(string) => seed
+                    .deserialize(BorrowedStrDeserializer::new(string))
+                    .map(Some),
+                None => Ok(None),
+            },
+            Elements::Message(iter) => iter
+                .next_message_with(|decoder, body| {
+                    seed.deserialize(Deserializer::new(decoder, body))
+                })
+                .transpose(),
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Serde serializer
+
+use super::field_tag;
+use crate::{
+    error::{self, Error, Segment},
+    field::{Tag, WireType},
+    value::{Object, Sequence, Value},
+};
+use alloc::vec::Vec;
+use serde::ser::{self, Serialize};
+
+/// Serde serializer for Veriform.
+///
+/// This is a tree builder: rather than writing to an [`Encoder`] directly,
+/// it serializes values into the schema-less [`Value`] representation,
+/// which can then be encoded using [`Message::encode`]. Absent values
+/// (i.e. `Option::None`) serialize as `None`.
+///
+/// Errors which occur while serializing the value of a field are annotated
+/// with the [`Path`](crate::error::Path) to that field.
+///
+/// [`Encoder`]: crate::Encoder
+/// [`Message::encode`]: crate::Message::encode
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Serializer;
+
+impl Serializer {
+    /// Serialize a value which must be present
+    fn serialize_present<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+        value
+            .serialize(Serializer)?
+            .ok_or_else(|| error::Kind::Serde.into())
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+    type SerializeSeq = SerializeSequence;
+    type SerializeTuple = SerializeMessage;
+    type SerializeTupleStruct = SerializeMessage;
+    type SerializeTupleVariant = SerializeMessage;
+    type SerializeMap = ser::Impossible<Option<Value>, Error>;
+    type SerializeStruct = SerializeMessage;
+    type SerializeStructVariant = SerializeMessage;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>, Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::SInt64(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Value>, Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Value>, Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Value>, Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::UInt64(v)))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Option<Value>, Error> {
+        Err(error::Kind::Serde.into())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Option<Value>, Error> {
+        Err(error::Kind::Serde.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Value>, Error> {
+        let mut buf = [0u8; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::String(v.into())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::Bytes(v.into())))
+    }
+
+    fn serialize_none(self) -> Result<Option<Value>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Option<Value>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::Message(Object::new())))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Option<Value>, Error> {
+        Ok(Some(Value::UInt64(field_tag(
+            variant_index as usize,
+            variant,
+        ))))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>, Error> {
+        let mut object = Object::new();
+        let tag = field_tag(variant_index as usize, variant);
+        let value = Self::serialize_present(value).map_err(|e| e.within(Segment::Field(tag)))?;
+        object.insert(tag, false, value)?;
+        Ok(Some(Value::Message(object)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeSequence, Error> {
+        Ok(SerializeSequence(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<SerializeMessage, Error> {
+        Ok(SerializeMessage::new(None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SerializeMessage, Error> {
+        Ok(SerializeMessage::new(None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeMessage, Error> {
+        let tag = field_tag(variant_index as usize, variant);
+        Ok(SerializeMessage::new(Some(tag)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(error::Kind::Serde.into())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SerializeMessage, Error> {
+        Ok(SerializeMessage::new(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeMessage, Error> {
+        let tag = field_tag(variant_index as usize, variant);
+        Ok(SerializeMessage::new(Some(tag)))
+    }
+}
+
+/// Serializer for sequences
+#[derive(Debug)]
+pub struct SerializeSequence(Vec<Value>);
+
+impl ser::SerializeSeq for SerializeSequence {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.0.len();
+        let value =
+            Serializer::serialize_present(value).map_err(|e| e.within(Segment::Element(index)))?;
+
+        self.0.push(value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>, Error> {
+        let wire_type = self
+            .0
+            .first()
+            .map(Value::wire_type)
+            .unwrap_or(WireType::UInt64);
+
+        let mut sequence = Sequence::new(wire_type)?;
+
+        for value in self.0 {
+            sequence.push(value)?;
+        }
+
+        Ok(Some(Value::Sequence(sequence)))
+    }
+}
+
+/// Serializer for messages (i.e. structs, tuples, and non-unit enum variants)
+#[derive(Debug)]
+pub struct SerializeMessage {
+    /// Message being serialized
+    object: Object,
+
+    /// Position of the next field
+    index: usize,
+
+    /// Tag of the enum variant this message is wrapped in (if any)
+    variant: Option<Tag>,
+}
+
+impl SerializeMessage {
+    /// Create a new message serializer
+    fn new(variant: Option<Tag>) -> Self {
+        Self {
+            object: Object::new(),
+            index: 0,
+            variant,
+        }
+    }
+
+    /// Serialize a field, omitting it if it's absent
+    fn field<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), Error> {
+        let tag = field_tag(self.index, name);
+        self.index += 1;
+
+        match value
+            .serialize(Serializer)
+            .map_err(|e| e.within(Segment::Field(tag)))?
+        {
+            Some(value) => self.object.insert(tag, false, value),
+            None => Ok(()),
+        }
+    }
+
+    /// Serialize a tuple element, which must be present
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let tag = self.index as Tag;
+        self.index += 1;
+        let value =
+            Serializer::serialize_present(value).map_err(|e| e.within(Segment::Field(tag)))?;
+
+        self.object.insert(tag, false, value)
+    }
+
+    /// Finish serializing the message
+    fn finish(self) -> Result<Option<Value>, Error> {
+        let message = Value::Message(self.object);
+
+        match self.variant {
+            Some(tag) => {
+                let mut object = Object::new();
+                object.insert(tag, false, message)?;
+                Ok(Some(Value::Message(object)))
+            }
+            None => Ok(Some(message)),
+        }
+    }
+}
+
+impl ser::SerializeTuple for SerializeMessage {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeMessage {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeMessage {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Option<Value>, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMessage {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Error> {
+        self.index += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMessage {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<(), Error> {
+        self.index += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>, Error> {
+        self.finish()
+    }
+}
@@ -0,0 +1,736 @@
+//! Human-readable text format for Veriform messages.
+//!
+//! The text format is intended for hand-edited files like configuration and
+//! test fixtures. A message is written as a list of fields, one per line,
+//! each consisting of its tag, an optional `!` indicating the field is
+//! critical, an optional field name, and a typed value:
+//!
+//! ```text
+//! # Comments run until the end of the line
+//! 1 header: {
+//!   24 height: 42
+//! }
+//! 2! payload: x"48656c6c6f"
+//! 3: "Hello, world!"
+//! 4: -42
+//! 5: uint64[1, 2, 3]
+//! 6: message[
+//!   {
+//!     1: true
+//!   },
+//!   {}
+//! ]
+//! ```
+//!
+//! Values are written as follows:
+//!
+//! | Wire Type  | Syntax                                                    |
+//! |------------|-----------------------------------------------------------|
+//! | `bool`     | `true` or `false`                                         |
+//! | `uint64`   | `42`                                                      |
+//! | `sint64`   | `+42` or `-42` (i.e. always with an explicit sign)        |
+//! | `bytes`    | `x"48656c6c6f"` (hex) or `b"Hello\x00"` (escaped ASCII)   |
+//! | `string`   | `"Hello\n"` (escapes: `\\`, `\"`, `\n`, `\r`, `\t`, `\u{...}`) |
+//! | `message`  | `{ ... }`                                                 |
+//! | `sequence` | `<element type>[<value>, ...]`, e.g. `sint64[-1, +1]`      |
+//!
+//! Field names are informational: the binary encoding only contains tags.
+//! [`from_str`] ignores them, whereas [`from_str_with_names`] checks them
+//! against the schema of a message type which impls [`Reflect`].
+//!
+//! Like TJSON, the text format is an exact representation of the fields of a
+//! message, so converting a message to text and back reproduces its binary
+//! encoding (and therefore its Verihash digest) exactly, and the digest can
+//! be computed directly from its text using [`digest()`].
+
+use crate::{
+    decoder::MAX_DEPTH,
+    error::{self, Error},
+    field::{Tag, WireType},
+    reflect::{MessageDescriptor, Reflect},
+    value::{Object, Sequence, Value},
+    verihash::{DigestOutput, VerihashVersion},
+    Decoder, Message,
+};
+use alloc::{string::String, vec::Vec};
+use core::{convert::TryFrom, fmt::Write};
+use digest::Digest;
+
+/// Serialize a message as text
+pub fn to_string<M: Message>(message: &M) -> Result<String, Error> {
+    let object = to_object(message)?;
+    let mut printer = Printer::default();
+    printer.fields(&object, None, 0);
+    Ok(printer.out)
+}
+
+/// Serialize a message as text, annotating each field with its name
+pub fn to_string_with_names<M: Reflect>(message: &M) -> Result<String, Error> {
+    let object = to_object(message)?;
+    let mut printer = Printer::default();
+    printer.fields(&object, Some(&M::DESCRIPTOR), 0);
+    Ok(printer.out)
+}
+
+/// Parse a message from text, ignoring any field names
+pub fn from_str<M: Message>(s: &str) -> Result<M, Error> {
+    let encoded = parse(s, None)?.encode_vec()?;
+    M::decode(&mut Decoder::new(), &encoded)
+}
+
+/// Parse a message from text, ensuring any field names match the names of
+/// the fields in its schema
+pub fn from_str_with_names<M: Reflect>(s: &str) -> Result<M, Error> {
+    let encoded = parse(s, Some(&M::DESCRIPTOR))?.encode_vec()?;
+    M::decode(&mut Decoder::new(), &encoded)
+}
+
+/// Compute the Verihash digest of a message serialized as text
+pub fn digest<D: Digest>(s: &str) -> Result<DigestOutput<D>, Error> {
+    digest_with_version::<D>(s, VerihashVersion::default())
+}
+
+/// Compute the digest of a message serialized as text using the given
+/// version of Verihash
+pub fn digest_with_version<D: Digest>(
+    s: &str,
+    version: VerihashVersion,
+) -> Result<DigestOutput<D>, Error> {
+    Ok(parse(s, None)?.digest_with_version::<D>(version))
+}
+
+/// Convert a message into its schema-less representation
+fn to_object<M: Message>(message: &M) -> Result<Object, Error> {
+    Object::decode(&mut Decoder::new(), &message.encode_vec()?)
+}
+
+/// Parse a message, checking field names against the given descriptor
+fn parse(s: &str, descriptor: Option<&MessageDescriptor>) -> Result<Object, Error> {
+    let mut parser = Parser { input: s, pos: 0 };
+    let object = parser.fields(descriptor, 1)?;
+
+    if parser.pos < s.len() {
+        return Err(parser.error());
+    }
+
+    Ok(object)
+}
+
+/// Printer for the text format
+#[derive(Default)]
+struct Printer {
+    /// Output text
+    out: String,
+}
+
+impl Printer {
+    /// Print the fields of a message, one per line, at the given depth
+    fn fields(&mut self, object: &Object, descriptor: Option<&MessageDescriptor>, depth: usize) {
+        for (&tag, field) in object {
+            self.indent(depth);
+            write!(self.out, "{}", tag).unwrap();
+
+            if field.critical {
+                self.out.push('!');
+            }
+
+            let field_descriptor = descriptor.and_then(|desc| desc.field(tag));
+
+            if let Some(field_descriptor) = field_descriptor {
+                self.out.push(' ');
+                self.out.push_str(field_descriptor.name);
+            }
+
+            self.out.push_str(": ");
+
+            let nested = field_descriptor.and_then(|desc| desc.nested_descriptor());
+            self.value(&field.value, nested, depth);
+            self.out.push('\n');
+        }
+    }
+
+    /// Print a value (which begins on a line indented to the given depth)
+    fn value(&mut self, value: &Value, descriptor: Option<&MessageDescriptor>, depth: usize) {
+        match value {
+            Value::Bool(value) => write!(self.out, "{}", value).unwrap(),
+            Value::UInt64(value) => write!(self.out, "{}", value).unwrap(),
+            Value::SInt64(value) => write!(self.out, "{:+}", value).unwrap(),
+            Value::Bytes(bytes) => {
+                self.out.push_str("x\"");
+
+                for byte in bytes {
+                    write!(self.out, "{:02x}", byte).unwrap();
+                }
+
+                self.out.push('"');
+            }
+            Value::String(string) => self.string(string),
+            Value::Message(object) => self.message(object, descriptor, depth),
+            Value::Sequence(seq) => {
+                write!(self.out, "{}[", seq.wire_type()).unwrap();
+
+                if seq.wire_type() == WireType::Message {
+                    for (i, value) in seq.iter().enumerate() {
+                        self.out.push_str(if i == 0 { "\n" } else { ",\n" });
+                        self.indent(depth + 1);
+                        self.value(value, descriptor, depth + 1);
+                    }
+
+                    if !seq.is_empty() {
+                        self.out.push('\n');
+                        self.indent(depth);
+                    }
+                } else {
+                    for (i, value) in seq.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str(", ");
+                        }
+
+                        self.value(value, None, depth);
+                    }
+                }
+
+                self.out.push(']');
+            }
+        }
+    }
+
+    /// Print a nested message
+    fn message(&mut self, object: &Object, descriptor: Option<&MessageDescriptor>, depth: usize) {
+        if object.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push_str("{\n");
+        self.fields(object, descriptor, depth + 1);
+        self.indent(depth);
+        self.out.push('}');
+    }
+
+    /// Print a quoted, escaped string
+    fn string(&mut self, string: &str) {
+        self.out.push('"');
+
+        for c in string.chars() {
+            match c {
+                '\\' => self.out.push_str("\\\\"),
+                '"' => self.out.push_str("\\\""),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if c.is_control() => write!(self.out, "\\u{{{:x}}}", c as u32).unwrap(),
+                c => self.out.push(c),
+            }
+        }
+
+        self.out.push('"');
+    }
+
+    /// Indent to the given depth
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.out.push_str("  ");
+        }
+    }
+}
+
+/// Recursive descent parser for the text format
+struct Parser<'a> {
+    /// Input text
+    input: &'a str,
+
+    /// Current position within the input
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parse fields until the end of the input or a closing `}`.
+    ///
+    /// `depth` is the nesting depth of the message, including the toplevel.
+    fn fields(
+        &mut self,
+        descriptor: Option<&MessageDescriptor>,
+        depth: usize,
+    ) -> Result<Object, Error> {
+        if depth > MAX_DEPTH {
+            return Err(error::Kind::NestingDepth { max: MAX_DEPTH }.into());
+        }
+
+        let mut object = Object::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None | Some('}') => return Ok(object),
+                _ => (),
+            }
+
+            let start = self.pos;
+            let tag = self.tag()?;
+            let critical = self.eat('!');
+            let mut nested = None;
+
+            if self.skip_whitespace() && self.peek() != Some(':') {
+                let name_pos = self.pos;
+                let name = self.identifier().ok_or_else(|| self.error())?;
+
+                if let Some(descriptor) = descriptor {
+                    match descriptor.field(tag) {
+                        Some(field) if field.name == name => nested = field.nested_descriptor(),
+                        _ => return Err(self.error_at(name_pos)),
+                    }
+                }
+            } else if let Some(field) = descriptor.and_then(|desc| desc.field(tag)) {
+                nested = field.nested_descriptor();
+            }
+
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+
+            let value = self.value(nested, depth)?;
+
+            object
+                .insert(tag, critical, value)
+                .map_err(|_| self.error_at(start))?;
+        }
+    }
+
+    /// Parse a field tag
+    fn tag(&mut self) -> Result<Tag, Error> {
+        let start = self.pos;
+
+        match self.digits() {
+            Some(tag) if tag <= Tag::MAX >> 4 => Ok(tag),
+            _ => Err(self.error_at(start)),
+        }
+    }
+
+    /// Parse a value
+    fn value(
+        &mut self,
+        descriptor: Option<&MessageDescriptor>,
+        depth: usize,
+    ) -> Result<Value, Error> {
+        let start = self.pos;
+
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let object = self.fields(descriptor, depth + 1)?;
+                self.expect('}')?;
+                Ok(Value::Message(object))
+            }
+            Some('"') => self.string().map(Value::String),
+            Some('+') | Some('-') => self.sint64().map(Value::SInt64),
+            Some(c) if c.is_ascii_digit() => self
+                .digits()
+                .map(Value::UInt64)
+                .ok_or_else(|| self.error_at(start)),
+            _ => {
+                let ident = self.identifier().ok_or_else(|| self.error())?;
+
+                match ident {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "x" if self.peek() == Some('"') => self.hex().map(Value::Bytes),
+                    "b" if self.peek() == Some('"') => self.byte_string().map(Value::Bytes),
+                    _ => {
+                        let wire_type = sequence_type(ident).ok_or_else(|| self.error_at(start))?;
+
+                        self.sequence(wire_type, descriptor, depth)
+                            .map(Value::Sequence)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse the body of a sequence of the given type
+    fn sequence(
+        &mut self,
+        wire_type: WireType,
+        descriptor: Option<&MessageDescriptor>,
+        depth: usize,
+    ) -> Result<Sequence, Error> {
+        let mut seq = Sequence::new(wire_type)?;
+        self.expect('[')?;
+
+        loop {
+            self.skip_whitespace();
+
+            if self.eat(']') {
+                return Ok(seq);
+            }
+
+            let start = self.pos;
+
+            // Sequences can't contain sequences, so nested sequence syntax is
+            // rejected before it's parsed (parsing it would recurse without
+            // being bounded by the nesting depth of messages)
+            if self.identifier().and_then(sequence_type).is_some() {
+                return Err(self.error_at(start));
+            }
+
+            self.pos = start;
+            let value = self.value(descriptor, depth)?;
+            seq.push(value).map_err(|_| self.error_at(start))?;
+            self.skip_whitespace();
+
+            if !self.eat(',') {
+                self.expect(']')?;
+                return Ok(seq);
+            }
+        }
+    }
+
+    /// Parse a signed integer (with an explicit sign)
+    fn sint64(&mut self) -> Result<i64, Error> {
+        let start = self.pos;
+        let negative = self.eat('-') || !self.eat('+');
+
+        let value = self.digits().and_then(|n| match n {
+            // Zero is written `+0`
+            0 if negative => None,
+            n if negative && n == 1 << 63 => Some(i64::MIN),
+            n => i64::try_from(n).ok().map(|n| if negative { -n } else { n }),
+        });
+
+        value.ok_or_else(|| self.error_at(start))
+    }
+
+    /// Parse a base 10 integer with no leading zeroes
+    fn digits(&mut self) -> Option<u64> {
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let digits = &rest[..len];
+
+        if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+            return None;
+        }
+
+        let value = digits.parse().ok()?;
+        self.pos += len;
+        Some(value)
+    }
+
+    /// Parse a quoted string
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            let start = self.pos;
+
+            match self.next().ok_or_else(|| self.error())? {
+                '"' => return Ok(string),
+                '\\' => match self.next() {
+                    Some('\\') => string.push('\\'),
+                    Some('"') => string.push('"'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let c = self.unicode_escape().ok_or_else(|| self.error_at(start))?;
+                        string.push(c);
+                    }
+                    _ => return Err(self.error_at(start)),
+                },
+                c if c.is_control() => return Err(self.error_at(start)),
+                c => string.push(c),
+            }
+        }
+    }
+
+    /// Parse the body of a `\u{...}` escape
+    fn unicode_escape(&mut self) -> Option<char> {
+        if !self.eat('{') {
+            return None;
+        }
+
+        let rest = &self.input[self.pos..];
+        let len = rest.find('}')?;
+        let hex = &rest[..len];
+
+        if hex.is_empty() || hex.len() > 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        self.pos += len + 1;
+        u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+    }
+
+    /// Parse a hex-encoded bytes literal (after the `x` prefix)
+    fn hex(&mut self) -> Result<Vec<u8>, Error> {
+        self.expect('"')?;
+        let start = self.pos;
+        let rest = &self.input[self.pos..];
+        let len = rest.find('"').ok_or_else(|| self.error())?;
+
+        let bytes = rest.as_bytes()[..len]
+            .chunks(2)
+            .map(|pair| match pair {
+                [hi, lo] => Some(hex_digit(*hi)? << 4 | hex_digit(*lo)?),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| self.error_at(start))?;
+
+        self.pos += len + 1;
+        Ok(bytes)
+    }
+
+    /// Parse an escaped ASCII bytes literal (after the `b` prefix)
+    fn byte_string(&mut self) -> Result<Vec<u8>, Error> {
+        self.expect('"')?;
+        let mut bytes = Vec::new();
+
+        loop {
+            let start = self.pos;
+
+            match self.next().ok_or_else(|| self.error())? {
+                '"' => return Ok(bytes),
+                '\\' => match self.next() {
+                    Some('\\') => bytes.push(b'\\'),
+                    Some('"') => bytes.push(b'"'),
+                    Some('n') => bytes.push(b'\n'),
+                    Some('r') => bytes.push(b'\r'),
+                    Some('t') => bytes.push(b'\t'),
+                    Some('x') => {
+                        let byte = match self.input.as_bytes().get(self.pos..self.pos + 2) {
+                            Some([hi, lo]) => {
+                                hex_digit(*hi).and_then(|hi| Some(hi << 4 | hex_digit(*lo)?))
+                            }
+                            _ => None,
+                        }
+                        .ok_or_else(|| self.error_at(start))?;
+
+                        self.pos += 2;
+                        bytes.push(byte);
+                    }
+                    _ => return Err(self.error_at(start)),
+                },
+                c if c.is_ascii() && !c.is_ascii_control() => bytes.push(c as u8),
+                _ => return Err(self.error_at(start)),
+            }
+        }
+    }
+
+    /// Parse an identifier (i.e. a field name or keyword)
+    fn identifier(&mut self) -> Option<&'a str> {
+        let rest = &self.input[self.pos..];
+
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    /// Skip whitespace and comments, returning whether anything was skipped
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                let rest = &self.input[self.pos..];
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if c.is_whitespace() {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+
+        self.pos > start
+    }
+
+    /// Peek at the next character of input
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// Consume the next character of input
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Consume the next character of input if it's the given one
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume the given character, or return an error
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Error at the current position
+    fn error(&self) -> Error {
+        self.error_at(self.pos)
+    }
+
+    /// Error at the given position
+    fn error_at(&self, pos: usize) -> Error {
+        let preceding = &self.input[..pos];
+        let line = preceding.matches('\n').count() + 1;
+        let column = match preceding.rfind('\n') {
+            Some(newline) => preceding[newline + 1..].chars().count() + 1,
+            None => preceding.chars().count() + 1,
+        };
+
+        error::Kind::Text { line, column }.into()
+    }
+}
+
+/// Get the wire type of the elements of a sequence from the identifier which
+/// begins it (e.g. `uint64` in `uint64[1, 2, 3]`)
+fn sequence_type(ident: &str) -> Option<WireType> {
+    match ident {
+        "uint64" => Some(WireType::UInt64),
+        "sint64" => Some(WireType::SInt64),
+        "bytes" => Some(WireType::Bytes),
+        "string" => Some(WireType::String),
+        "message" => Some(WireType::Message),
+        _ => None,
+    }
+}
+
+/// Decode a hexadecimal digit
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+    use super::{digest, from_str, to_string};
+    use crate::{error, value::Object, Decoder, Message};
+    use sha2::Sha256;
+
+    /// Example message containing every supported type
+    const EXAMPLE_TEXT: &str = r#"# Example message
+1 flag: true
+2!: 18446744073709551615
+3: -42
+4: x"48656c6c6f2c20776f726c6421"
+5 greeting: "Hello,\tworld!\n\u{7f}"
+6: {
+  24: +42
+  25: {}
+}
+7: message[
+  {
+    1: 1
+  },
+  {}
+]
+8: uint64[1, 2, 3]
+9: bytes[b"\x00\"hi\"", x""]
+10: string[]
+"#;
+
+    #[test]
+    fn round_trip() {
+        let object: Object = from_str(EXAMPLE_TEXT).unwrap();
+        let text = to_string(&object).unwrap();
+        let reparsed: Object = from_str(&text).unwrap();
+        assert_eq!(object, reparsed);
+        assert_eq!(to_string(&reparsed).unwrap(), text);
+        assert!(text.starts_with("1: true\n2!: 18446744073709551615\n3: -42\n"));
+        assert!(text.contains("9: bytes[x\"0022686922\", x\"\"]\n"));
+    }
+
+    #[test]
+    fn binary_parity() {
+        let object: Object = from_str(EXAMPLE_TEXT).unwrap();
+        let binary = object.encode_vec().unwrap();
+        let text = to_string(&Object::decode(&mut Decoder::new(), &binary).unwrap()).unwrap();
+        assert_eq!(
+            from_str::<Object>(&text).unwrap().encode_vec().unwrap(),
+            binary
+        );
+    }
+
+    #[test]
+    fn digest_parity() {
+        let object: Object = from_str(EXAMPLE_TEXT).unwrap();
+        let binary = object.encode_vec().unwrap();
+
+        let mut decoder = Decoder::new();
+        Object::decode(&mut decoder, &binary).unwrap();
+
+        let mut expected = [0u8; 32];
+        decoder.fill_digest(&mut expected).unwrap();
+
+        let actual = digest::<Sha256>(EXAMPLE_TEXT).unwrap();
+        assert_eq!(&expected[..], &actual[..]);
+    }
+
+    #[test]
+    fn error_location() {
+        let err = from_str::<Object>("1: 42\n2: {\n  3: 0x42\n}").unwrap_err();
+        assert_eq!(err.kind(), error::Kind::Text { line: 3, column: 7 });
+    }
+
+    #[test]
+    fn reject_malformed() {
+        for text in &[
+            "1: 42\n1: 24",
+            "01: 42",
+            "1: 042",
+            "1: -0",
+            "1: 18446744073709551616",
+            "1: +9223372036854775808",
+            "1: x\"abc\"",
+            "1: x\"zz\"",
+            "1: \"\\q\"",
+            "1: bool[]",
+            "1: sequence[]",
+            "1: uint64[-1]",
+            "1: uint64[1 2]",
+            "1 foo-bar: 42",
+            "1: {",
+            "1: 42 }",
+            "1 2: 42",
+            "1152921504606846976: 42",
+        ] {
+            assert!(from_str::<Object>(text).is_err(), "parsed: {}", text);
+        }
+    }
+
+    #[test]
+    fn reject_nested_sequences() {
+        let err = from_str::<Object>("1: uint64[uint64[1]]").unwrap_err();
+        assert_eq!(
+            err.kind(),
+            error::Kind::Text {
+                line: 1,
+                column: 11
+            }
+        );
+
+        // Deeply nested sequence syntax mustn't overflow the stack
+        let text = format!("1: {}", "uint64[".repeat(1_000_000));
+        assert!(from_str::<Object>(&text).is_err());
+    }
+}
@@ -0,0 +1,514 @@
+//! TJSON serialization of Veriform messages.
+//!
+//! [TJSON] is a tagged superset of JSON in which the name of every object
+//! member carries a type signature. Veriform messages are represented as
+//! TJSON objects whose member names are the tags of their fields, e.g.
+//!
+//! ```text
+//! {
+//!     "1:O": {
+//!         "24:u": "42"
+//!     },
+//!     "2!:d16": "48656c6c6f2c20776f726c6421"
+//! }
+//! ```
+//!
+//! The `!` suffix on a tag indicates the field is critical.
+//!
+//! Veriform wire types map to the following TJSON types:
+//!
+//! | Wire Type  | TJSON                          |
+//! |------------|--------------------------------|
+//! | `bool`     | `b`                            |
+//! | `uint64`   | `u`                            |
+//! | `sint64`   | `i`                            |
+//! | `bytes`    | `d16` (also accepts `d`/`d64`) |
+//! | `string`   | `s`                            |
+//! | `message`  | `O`                            |
+//! | `sequence` | `A<...>`                       |
+//!
+//! Verihash only hashes the content of a message and not its serialization,
+//! so the digest of a message can be computed directly over its TJSON
+//! representation using [`digest`], producing the same result as the
+//! [`Decoder`](crate::decoder::Decoder) does when decoding its binary form.
+//!
+//! [TJSON]: https://www.tjson.org/
+
+use crate::{
+    error::{self, Error},
+    field::{Tag, WireType},
+    value::{Object, Sequence, Value},
+    verihash::{DigestOutput, VerihashVersion},
+    Message,
+};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use digest::Digest;
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// Serialize a message as TJSON
+pub fn to_string<M: Message>(message: &M) -> Result<String, Error> {
+    let object = to_object(message)?;
+    serde_json::to_string(&ObjectRef(&object)).map_err(|_| error::Kind::Failed.into())
+}
+
+/// Serialize a message as pretty-printed TJSON
+pub fn to_string_pretty<M: Message>(message: &M) -> Result<String, Error> {
+    let object = to_object(message)?;
+    serde_json::to_string_pretty(&ObjectRef(&object)).map_err(|_| error::Kind::Failed.into())
+}
+
+/// Parse a message from TJSON
+pub fn from_str<M: Message>(s: &str) -> Result<M, Error> {
+    let encoded = parse(s)?.encode_vec()?;
+    M::decode(&mut crate::Decoder::new(), &encoded)
+}
+
+/// Compute the Verihash digest of a message serialized as TJSON
+pub fn digest<D: Digest>(s: &str) -> Result<DigestOutput<D>, Error> {
+    digest_with_version::<D>(s, VerihashVersion::default())
+}
+
+/// Compute the digest of a message serialized as TJSON using the given
+/// version of Verihash
+pub fn digest_with_version<D: Digest>(
+    s: &str,
+    version: VerihashVersion,
+) -> Result<DigestOutput<D>, Error> {
+    Ok(parse(s)?.digest_with_version::<D>(version))
+}
+
+/// Convert a message into its schema-less representation
+fn to_object<M: Message>(message: &M) -> Result<Object, Error> {
+    Object::decode(&mut crate::Decoder::new(), &message.encode_vec()?)
+}
+
+/// Parse a TJSON object
+fn parse(s: &str) -> Result<Object, Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(s);
+    let object = deserializer.deserialize_map(ObjectVisitor)?;
+    deserializer.end()?;
+    Ok(object)
+}
+
+/// TJSON type signatures supported by Veriform
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Type {
+    /// Booleans (`b`)
+    Bool,
+
+    /// Unsigned integers (`u`)
+    UInt64,
+
+    /// Signed integers (`i`)
+    SInt64,
+
+    /// Binary data (`d`, `d16`, `d64`)
+    Binary(BinaryEncoding),
+
+    /// Unicode strings (`s`)
+    String,
+
+    /// Objects (`O`), i.e. nested messages
+    Object,
+
+    /// Arrays (`A<...>`), i.e. sequences
+    Array(Box<Type>),
+}
+
+impl Type {
+    /// Get the TJSON type which represents the given value
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Sequence(seq) => Type::Array(Box::new(Type::from(seq.wire_type()))),
+            other => Type::from(other.wire_type()),
+        }
+    }
+
+    /// Get the wire type which corresponds to this TJSON type
+    fn wire_type(&self) -> WireType {
+        match self {
+            Type::Bool => WireType::True,
+            Type::UInt64 => WireType::UInt64,
+            Type::SInt64 => WireType::SInt64,
+            Type::Binary(_) => WireType::Bytes,
+            Type::String => WireType::String,
+            Type::Object => WireType::Message,
+            Type::Array(_) => WireType::Sequence,
+        }
+    }
+}
+
+impl From<WireType> for Type {
+    fn from(wire_type: WireType) -> Type {
+        match wire_type {
+            WireType::False | WireType::True => Type::Bool,
+            WireType::UInt64 => Type::UInt64,
+            WireType::SInt64 => Type::SInt64,
+            WireType::Bytes => Type::Binary(BinaryEncoding::Base16),
+            WireType::String => Type::String,
+            WireType::Message => Type::Object,
+            WireType::Sequence => unreachable!("nested sequences are not supported"),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => f.write_str("b"),
+            Type::UInt64 => f.write_str("u"),
+            Type::SInt64 => f.write_str("i"),
+            Type::Binary(BinaryEncoding::Base16) => f.write_str("d16"),
+            Type::Binary(BinaryEncoding::Base64) => f.write_str("d64"),
+            Type::String => f.write_str("s"),
+            Type::Object => f.write_str("O"),
+            Type::Array(elem) => write!(f, "A<{}>", elem),
+        }
+    }
+}
+
+impl FromStr for Type {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "b" => Ok(Type::Bool),
+            "u" => Ok(Type::UInt64),
+            "i" => Ok(Type::SInt64),
+            "d16" => Ok(Type::Binary(BinaryEncoding::Base16)),
+            "d" | "d64" => Ok(Type::Binary(BinaryEncoding::Base64)),
+            "s" => Ok(Type::String),
+            "O" => Ok(Type::Object),
+            _ if s.starts_with("A<") && s.ends_with('>') => {
+                let elem = s[2..s.len() - 1].parse()?;
+                Ok(Type::Array(Box::new(elem)))
+            }
+            _ => Err(error::Kind::InvalidWireType.into()),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &Type {
+    type Value = Value;
+
+    fn deserialize<De>(self, deserializer: De) -> Result<Value, De::Error>
+    where
+        De: Deserializer<'de>,
+    {
+        match self {
+            Type::Bool => bool::deserialize(deserializer).map(Value::Bool),
+            Type::UInt64 => {
+                let s = String::deserialize(deserializer)?;
+                parse_integer(&s, false)
+                    .and_then(|n| n.parse().ok())
+                    .map(Value::UInt64)
+                    .ok_or_else(|| de::Error::custom("invalid unsigned integer"))
+            }
+            Type::SInt64 => {
+                let s = String::deserialize(deserializer)?;
+                parse_integer(&s, true)
+                    .and_then(|n| n.parse().ok())
+                    .map(Value::SInt64)
+                    .ok_or_else(|| de::Error::custom("invalid signed integer"))
+            }
+            Type::Binary(encoding) => {
+                let s = String::deserialize(deserializer)?;
+                encoding
+                    .decode(&s)
+                    .map(Value::Bytes)
+                    .ok_or_else(|| de::Error::custom("invalid binary data"))
+            }
+            Type::String => String::deserialize(deserializer).map(Value::String),
+            Type::Object => deserializer
+                .deserialize_map(ObjectVisitor)
+                .map(Value::Message),
+            Type::Array(elem) => deserializer
+                .deserialize_seq(SequenceVisitor(elem))
+                .map(Value::Sequence),
+        }
+    }
+}
+
+/// Encodings for binary data
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum BinaryEncoding {
+    /// Lower-case hexadecimal (`d16`)
+    Base16,
+
+    /// Unpadded URL-safe Base64 (`d`/`d64`)
+    Base64,
+}
+
+impl BinaryEncoding {
+    /// Decode binary data, returning `None` if it's malformed
+    fn decode(self, s: &str) -> Option<Vec<u8>> {
+        match self {
+            BinaryEncoding::Base16 => subtle_encoding::hex::decode(s).ok(),
+            BinaryEncoding::Base64 => {
+                // Translate to the standard alphabet with padding
+                let mut encoded = Vec::with_capacity(s.len() + 3);
+
+                for &byte in s.as_bytes() {
+                    encoded.push(match byte {
+                        b'+' | b'/' | b'=' => return None,
+                        b'-' => b'+',
+                        b'_' => b'/',
+                        other => other,
+                    });
+                }
+
+                while encoded.len() % 4 != 0 {
+                    encoded.push(b'=');
+                }
+
+                subtle_encoding::base64::decode(encoded).ok()
+            }
+        }
+    }
+}
+
+/// Parse the name of a member of a TJSON object into a field tag, critical
+/// flag, and type signature
+fn parse_name(name: &str) -> Result<(Tag, bool, Type), Error> {
+    let invalid = || {
+        Error::from(error::Kind::FieldHeader {
+            tag: None,
+            wire_type: None,
+        })
+    };
+
+    let pos = name.rfind(':').ok_or_else(invalid)?;
+    let (tag, ty) = (&name[..pos], name[pos + 1..].parse()?);
+
+    let (tag, critical) = match tag.strip_suffix('!') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+
+    let tag = parse_integer(tag, false)
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(invalid)?;
+
+    Ok((tag, critical, ty))
+}
+
+/// Ensure an integer is formatted as TJSON requires (i.e. base 10 with no
+/// leading zeroes or `+` sign), returning it on success
+fn parse_integer(s: &str, signed: bool) -> Option<&str> {
+    let digits = if signed && s.starts_with('-') {
+        &s[1..]
+    } else {
+        s
+    };
+
+    let valid = !digits.is_empty()
+        && digits.bytes().all(|byte| byte.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'))
+        && s != "-0";
+
+    if valid {
+        Some(s)
+    } else {
+        None
+    }
+}
+
+/// Visitor for TJSON objects
+struct ObjectVisitor;
+
+impl<'de> Visitor<'de> for ObjectVisitor {
+    type Value = Object;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TJSON object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Object, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut object = Object::new();
+
+        while let Some(name) = map.next_key::<String>()? {
+            let (tag, critical, ty) = parse_name(&name).map_err(de::Error::custom)?;
+            let value = map.next_value_seed(&ty)?;
+            object
+                .insert(tag, critical, value)
+                .map_err(de::Error::custom)?;
+        }
+
+        Ok(object)
+    }
+}
+
+/// Visitor for TJSON arrays
+struct SequenceVisitor<'a>(&'a Type);
+
+impl<'a, 'de> Visitor<'de> for SequenceVisitor<'a> {
+    type Value = Sequence;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TJSON array of type {}", self.0)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Sequence, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut sequence = Sequence::new(self.0.wire_type()).map_err(de::Error::custom)?;
+
+        while let Some(value) = seq.next_element_seed(self.0)? {
+            sequence.push(value).map_err(de::Error::custom)?;
+        }
+
+        Ok(sequence)
+    }
+}
+
+/// Serializer for objects
+struct ObjectRef<'a>(&'a Object);
+
+impl<'a> Serialize for ObjectRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+
+        for (tag, field) in self.0 {
+            let name = format!(
+                "{}{}:{}",
+                tag,
+                if field.critical { "!" } else { "" },
+                Type::of(&field.value)
+            );
+
+            map.serialize_entry(&name, &ValueRef(&field.value))?;
+        }
+
+        map.end()
+    }
+}
+
+/// Serializer for values
+struct ValueRef<'a>(&'a Value);
+
+impl<'a> Serialize for ValueRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Value::Bool(value) => serializer.serialize_bool(*value),
+            Value::UInt64(value) => serializer.collect_str(value),
+            Value::SInt64(value) => serializer.collect_str(value),
+            Value::Bytes(bytes) => {
+                let hex = String::from_utf8(subtle_encoding::hex::encode(bytes))
+                    .map_err(serde::ser::Error::custom)?;
+                serializer.serialize_str(&hex)
+            }
+            Value::String(string) => serializer.serialize_str(string),
+            Value::Message(object) => ObjectRef(object).serialize(serializer),
+            Value::Sequence(seq) => {
+                let mut s = serializer.serialize_seq(Some(seq.len()))?;
+
+                for value in seq {
+                    s.serialize_element(&ValueRef(value))?;
+                }
+
+                s.end()
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+    use super::{digest, from_str, to_string, to_string_pretty};
+    use crate::{value::Object, Decoder, Encoder, Message};
+    use sha2::Sha256;
+
+    /// Example TJSON message containing every supported type
+    const EXAMPLE_TJSON: &str = r#"{
+        "1:b": true,
+        "2!:u": "18446744073709551615",
+        "3:i": "-42",
+        "4:d16": "48656c6c6f2c20776f726c6421",
+        "5:s": "Hello, world!",
+        "6:O": { "24:u": "42" },
+        "7:A<O>": [{ "1:u": "1" }, {}],
+        "8:A<u>": ["1", "2", "3"],
+        "9:A<d>": ["AQI", ""]
+    }"#;
+
+    /// Encode a message containing every scalar type as binary
+    fn scalar_binary(buffer: &mut [u8]) -> &[u8] {
+        let mut encoder = Encoder::new(buffer);
+        encoder.boolean(1, false, true).unwrap();
+        encoder.uint64(2, true, u64::MAX).unwrap();
+        encoder.sint64(3, false, -42).unwrap();
+        encoder.bytes(4, false, b"Hello, world!").unwrap();
+        encoder.string(5, false, "Hello, world!").unwrap();
+        encoder.finish()
+    }
+
+    #[test]
+    fn round_trip() {
+        let object: Object = from_str(EXAMPLE_TJSON).unwrap();
+        let tjson = to_string_pretty(&object).unwrap();
+        assert_eq!(object, from_str(&tjson).unwrap());
+    }
+
+    #[test]
+    fn binary_parity() {
+        let mut buffer = [0u8; 64];
+        let binary = scalar_binary(&mut buffer);
+        let tjson = to_string(&Object::decode(&mut Decoder::new(), binary).unwrap()).unwrap();
+        assert!(tjson.starts_with(r#"{"1:b":true,"2!:u":"18446744073709551615","3:i":"-42""#));
+
+        let object: Object = from_str(&tjson).unwrap();
+        assert_eq!(object.encode_vec().unwrap(), binary);
+    }
+
+    #[test]
+    fn digest_parity() {
+        let object: Object = from_str(EXAMPLE_TJSON).unwrap();
+        let binary = object.encode_vec().unwrap();
+
+        let mut decoder = Decoder::new();
+        Object::decode(&mut decoder, &binary).unwrap();
+
+        let mut expected = [0u8; 32];
+        decoder.fill_digest(&mut expected).unwrap();
+
+        let actual = digest::<Sha256>(EXAMPLE_TJSON).unwrap();
+        assert_eq!(&expected[..], &actual[..]);
+    }
+
+    #[test]
+    fn reject_malformed() {
+        for tjson in &[
+            r#"{"1:u": "42", "1:u": "24"}"#,
+            r#"{"01:u": "42"}"#,
+            r#"{"1:u": "042"}"#,
+            r#"{"1:u": 42}"#,
+            r#"{"1:i": "-0"}"#,
+            r#"{"1:d16": "ABCD"}"#,
+            r#"{"1:A<A<u>>": []}"#,
+            r#"{"1:A<b>": []}"#,
+            r#"{"foo:u": "42"}"#,
+            r#"{"1:f": 1.0}"#,
+            r#"{} {}"#,
+        ] {
+            assert!(from_str::<Object>(tjson).is_err(), "parsed: {}", tjson);
+        }
+    }
+}
@@ -0,0 +1,628 @@
+//! Schema-less representation of Veriform messages.
+//!
+//! The types in this module can represent any Veriform message without
+//! knowledge of its schema, which is useful for converting messages to and
+//! from other formats (e.g. TJSON) as well as generic tooling.
+
+use crate::{
+    decoder::{peek_header, peek_sequence_type, sequence, Decode, DecodeRef, DecodeSeq, Decoder},
+    encoder::Encoder,
+    error::{self, Error},
+    field::{self, Header, Tag, WireType},
+    message::{Element, Message},
+    verihash::{self, DigestOutput, TypeTag, VerihashVersion},
+};
+use alloc::{
+    collections::btree_map::{self, BTreeMap},
+    string::String,
+    vec::Vec,
+};
+use digest::Digest;
+
+/// Schema-less Veriform message: a set of fields ordered by their tags.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Object(BTreeMap<Tag, Field>);
+
+impl Object {
+    /// Create a new empty [`Object`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a field into this object.
+    ///
+    /// Returns an error if a field with the given tag is already present.
+    pub fn insert(&mut self, tag: Tag, critical: bool, value: Value) -> Result<(), Error> {
+        match self.0.entry(tag) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(Field { critical, value });
+                Ok(())
+            }
+            btree_map::Entry::Occupied(_) => Err(error::Kind::Order { tag }.into()),
+        }
+    }
+
+    /// Get the field with the given tag (if present)
+    pub fn get(&self, tag: Tag) -> Option<&Field> {
+        self.0.get(&tag)
+    }
+
+    /// Iterate over the fields of this object in tag order
+    pub fn iter(&self) -> btree_map::Iter<'_, Tag, Field> {
+        self.0.iter()
+    }
+
+    /// Get the number of fields in this object
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Does this object contain no fields?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Compute the Verihash digest of this object.
+    ///
+    /// This is identical to the digest computed by [`Decoder`] when decoding
+    /// the same message from its binary encoding.
+    pub fn digest<D: Digest>(&self) -> DigestOutput<D> {
+        let mut hasher = verihash::Hasher::<D>::new();
+
+        for (&tag, field) in self.iter() {
+            match &field.value {
+                Value::Bool(value) => hasher.tagged_boolean(tag, *value),
+                Value::UInt64(value) => hasher.tagged_uint64(tag, *value),
+                Value::SInt64(value) => hasher.tagged_sint64(tag, *value),
+                Value::Bytes(bytes) => {
+                    hasher.tag(tag);
+                    hasher.dynamically_sized_value(WireType::Bytes, bytes.len());
+                    hasher.update(bytes);
+                }
+                Value::String(string) => {
+                    hasher.tag(tag);
+                    hasher.dynamically_sized_value(WireType::String, string.len());
+                    hasher.update(string.as_bytes());
+                }
+                Value::Message(object) => {
+                    hasher.tag(tag);
+                    hasher.dynamically_sized_value(WireType::Message, object.encoded_len());
+                    hasher.tag(tag);
+                    hasher.fixed_size_value(WireType::Message, &object.digest::<D>());
+                }
+                Value::Sequence(seq) => {
+                    hasher.tag(tag);
+                    hasher.fixed_size_value(WireType::Sequence, &seq.digest::<D>());
+                }
+            }
+        }
+
+        hasher.finalize()
+    }
+
+    /// Compute the digest of this object using the given version of
+    /// Verihash: [`Object::digest`] for [`VerihashVersion::V0`], or
+    /// [`Object::tagged_digest`] for [`VerihashVersion::V1`].
+    ///
+    /// This is identical to the digest computed by a [`Decoder`] configured
+    /// with the same version when decoding the same message.
+    pub fn digest_with_version<D: Digest>(&self, version: VerihashVersion) -> DigestOutput<D> {
+        match version {
+            VerihashVersion::V0 => self.digest::<D>(),
+            VerihashVersion::V1 => self.tagged_digest::<D>(),
+        }
+    }
+
+    /// Compute the digest of this object using the tagged hashing scheme
+    /// described in the Veriform specification:
+    ///
+    /// ```text
+    /// "O" || Field #1 ID || Field #1 Hash || ... || Field #N ID || Field #N Hash
+    /// ```
+    pub fn tagged_digest<D: Digest>(&self) -> DigestOutput<D> {
+        let mut hasher = verihash::Hasher::<D>::for_type(TypeTag::Message);
+
+        for (&tag, field) in self.iter() {
+            hasher.tag(tag);
+            hasher.update(&field.value.tagged_digest::<D>());
+        }
+
+        hasher.finalize()
+    }
+}
+
+impl Message for Object {
+    fn decode<D>(decoder: &mut Decoder<D>, mut input: &[u8]) -> Result<Self, Error>
+    where
+        D: Digest,
+    {
+        let mut object = Object::new();
+
+        while !input.is_empty() {
+            let header = peek_header(input).map_err(|e| decoder.annotate(e))?;
+            let tag = header.tag;
+
+            let value = match header.wire_type {
+                WireType::False | WireType::True => Value::Bool(decoder.decode(tag, &mut input)?),
+                WireType::UInt64 => Value::UInt64(decoder.decode(tag, &mut input)?),
+                WireType::SInt64 => Value::SInt64(decoder.decode(tag, &mut input)?),
+                WireType::Bytes => {
+                    let bytes: &[u8] = decoder.decode_ref(tag, &mut input)?;
+                    Value::Bytes(bytes.into())
+                }
+                WireType::String => {
+                    let string: &str = decoder.decode_ref(tag, &mut input)?;
+                    Value::String(string.into())
+                }
+                WireType::Message => Value::Message(decoder.decode(tag, &mut input)?),
+                WireType::Sequence => Value::Sequence(Sequence::decode(decoder, tag, &mut input)?),
+            };
+
+            object
+                .insert(tag, header.critical, value)
+                .map_err(|e| decoder.annotate(e))?;
+        }
+
+        Ok(object)
+    }
+
+    fn encode<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a [u8], Error> {
+        let mut encoder = Encoder::new(buffer);
+
+        for (&tag, field) in self.iter() {
+            let critical = field.critical;
+
+            match &field.value {
+                Value::Bool(value) => encoder.boolean(tag, critical, *value)?,
+                Value::UInt64(value) => encoder.uint64(tag, critical, *value)?,
+                Value::SInt64(value) => encoder.sint64(tag, critical, *value)?,
+                Value::Bytes(bytes) => encoder.bytes(tag, critical, bytes)?,
+                Value::String(string) => encoder.string(tag, critical, string)?,
+                Value::Message(object) => encoder.message(tag, critical, object)?,
+                Value::Sequence(seq) => seq.encode(&mut encoder, tag, critical)?,
+            }
+        }
+
+        Ok(encoder.finish())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.iter()
+            .map(|(&tag, field)| match &field.value {
+                Value::Bool(_) => field::length::boolean(tag),
+                Value::UInt64(value) => field::length::uint64(tag, *value),
+                Value::SInt64(value) => field::length::sint64(tag, *value),
+                Value::Bytes(bytes) => field::length::bytes(tag, bytes),
+                Value::String(string) => field::length::string(tag, string),
+                Value::Message(object) => field::length::message(tag, object),
+                Value::Sequence(seq) => seq.encoded_len(tag),
+            })
+            .sum()
+    }
+}
+
+impl<'a> IntoIterator for &'a Object {
+    type Item = (&'a Tag, &'a Field);
+    type IntoIter = btree_map::Iter<'a, Tag, Field>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Field of an [`Object`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Field {
+    /// Is this field critical?
+    pub critical: bool,
+
+    /// Value of the field
+    pub value: Value,
+}
+
+/// Values which can be contained in a field or sequence
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// Boolean
+    Bool(bool),
+
+    /// 64-bit unsigned integer
+    UInt64(u64),
+
+    /// 64-bit (zigzag) signed integer
+    SInt64(i64),
+
+    /// Binary data
+    Bytes(Vec<u8>),
+
+    /// Unicode string
+    String(String),
+
+    /// Nested Veriform message
+    Message(Object),
+
+    /// Sequence of values
+    Sequence(Sequence),
+}
+
+impl Value {
+    /// Get the [`WireType`] of this value
+    pub fn wire_type(&self) -> WireType {
+        match self {
+            Value::Bool(false) => WireType::False,
+            Value::Bool(true) => WireType::True,
+            Value::UInt64(_) => WireType::UInt64,
+            Value::SInt64(_) => WireType::SInt64,
+            Value::Bytes(_) => WireType::Bytes,
+            Value::String(_) => WireType::String,
+            Value::Message(_) => WireType::Message,
+            Value::Sequence(_) => WireType::Sequence,
+        }
+    }
+
+    /// Compute the digest of this value using the tagged hashing scheme
+    /// described in the Veriform specification
+    pub fn tagged_digest<D: Digest>(&self) -> DigestOutput<D> {
+        match self {
+            Value::Bool(value) => {
+                verihash::Hasher::<D>::typed_digest(TypeTag::Bool, &[*value as u8])
+            }
+            Value::UInt64(value) => {
+                verihash::Hasher::<D>::typed_digest(TypeTag::UInt64, &value.to_le_bytes())
+            }
+            Value::SInt64(value) => {
+                verihash::Hasher::<D>::typed_digest(TypeTag::SInt64, &value.to_le_bytes())
+            }
+            Value::Bytes(bytes) => verihash::Hasher::<D>::typed_digest(TypeTag::Bytes, bytes),
+            Value::String(string) => {
+                verihash::Hasher::<D>::typed_digest(TypeTag::String, string.as_bytes())
+            }
+            Value::Message(object) => object.tagged_digest::<D>(),
+            Value::Sequence(seq) => seq.tagged_digest::<D>(),
+        }
+    }
+
+    /// Get the length of this value when encoded as an element of a sequence
+    fn element_len(&self) -> usize {
+        match self {
+            Value::UInt64(value) => vint64::encoded_len(*value),
+            Value::SInt64(value) => vint64::signed::encoded_len(*value),
+            Value::Bytes(bytes) => length_prefixed(bytes.len()),
+            Value::String(string) => length_prefixed(string.len()),
+            Value::Message(object) => length_prefixed(object.encoded_len()),
+            Value::Bool(_) | Value::Sequence(_) => unreachable!("invalid sequence element"),
+        }
+    }
+}
+
+/// Sequence of values which all have the same wire type
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sequence {
+    /// Wire type of the values in this sequence
+    wire_type: WireType,
+
+    /// Values in this sequence
+    values: Vec<Value>,
+}
+
+impl Sequence {
+    /// Create a new empty sequence of values with the given wire type.
+    ///
+    /// Returns an error if values of the given wire type can't be contained
+    /// in a sequence (i.e. booleans and nested sequences).
+    pub fn new(wire_type: WireType) -> Result<Self, Error> {
+        match wire_type {
+            WireType::False | WireType::True => Err(error::Kind::Decode {
+                element: Element::SequenceHeader,
+                wire_type,
+            }
+            .into()),
+            WireType::Sequence => Err(error::Kind::NestedSequence.into()),
+            _ => Ok(Self {
+                wire_type,
+                values: Vec::new(),
+            }),
+        }
+    }
+
+    /// Get the wire type of the values in this sequence
+    pub fn wire_type(&self) -> WireType {
+        self.wire_type
+    }
+
+    /// Append a value to this sequence.
+    ///
+    /// Returns an error if its wire type doesn't match the sequence's.
+    pub fn push(&mut self, value: Value) -> Result<(), Error> {
+        if value.wire_type() == self.wire_type {
+            self.values.push(value);
+            Ok(())
+        } else {
+            Err(error::Kind::UnexpectedWireType {
+                actual: value.wire_type(),
+                wanted: self.wire_type,
+            }
+            .into())
+        }
+    }
+
+    /// Iterate over the values in this sequence
+    pub fn iter(&self) -> core::slice::Iter<'_, Value> {
+        self.values.iter()
+    }
+
+    /// Get the number of values in this sequence
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Is this sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Compute the Verihash digest of this sequence
+    pub fn digest<D: Digest>(&self) -> DigestOutput<D> {
+        let mut hasher = verihash::Hasher::<D>::new();
+
+        // Domain separate sequence hashes by their contained wire type
+        hasher.update(&[self.wire_type.to_u8()]);
+
+        for value in self.iter() {
+            match value {
+                Value::UInt64(value) => {
+                    hasher.fixed_size_value(WireType::UInt64, &value.to_le_bytes())
+                }
+                Value::SInt64(value) => {
+                    hasher.fixed_size_value(WireType::SInt64, &value.to_le_bytes())
+                }
+                Value::Bytes(bytes) => {
+                    hasher.dynamically_sized_value(WireType::Bytes, bytes.len());
+                    hasher.update(bytes);
+                }
+                Value::String(string) => {
+                    hasher.dynamically_sized_value(WireType::String, string.len());
+                    hasher.update(string.as_bytes());
+                }
+                Value::Message(object) => {
+                    hasher.dynamically_sized_value(WireType::Message, object.encoded_len());
+                    hasher.update(&object.digest::<D>());
+                }
+                Value::Bool(_) | Value::Sequence(_) => unreachable!("invalid sequence element"),
+            }
+        }
+
+        hasher.finalize()
+    }
+
+    /// Compute the digest of this sequence using the tagged hashing scheme
+    /// described in the Veriform specification:
+    ///
+    /// ```text
+    /// "A" || Element Type || Element #1 Hash || ... || Element #N Hash
+    /// ```
+    pub fn tagged_digest<D: Digest>(&self) -> DigestOutput<D> {
+        let mut hasher = verihash::Hasher::<D>::for_sequence(VerihashVersion::V1, self.wire_type);
+
+        for value in self.iter() {
+            hasher.update(&value.tagged_digest::<D>());
+        }
+
+        hasher.finalize()
+    }
+
+    /// Decode a sequence field with the given tag
+    fn decode<D: Digest>(
+        decoder: &mut Decoder<D>,
+        tag: Tag,
+        input: &mut &[u8],
+    ) -> Result<Self, Error> {
+        let mut seq = peek_sequence_type(input)
+            .and_then(Sequence::new)
+            .map_err(|e| decoder.annotate_field(e, tag))?;
+
+        match seq.wire_type {
+            WireType::UInt64 => {
+                let iter: sequence::Iter<'_, '_, u64, D> = decoder.decode_seq(tag, input)?;
+
+                for value in iter {
+                    seq.values.push(Value::UInt64(value?));
+                }
+            }
+            WireType::SInt64 => {
+                let iter: sequence::Iter<'_, '_, i64, D> = decoder.decode_seq(tag, input)?;
+
+                for value in iter {
+                    seq.values.push(Value::SInt64(value?));
+                }
+            }
+            WireType::Bytes => {
+                let iter: sequence::Iter<'_, '_, [u8], D> = decoder.decode_seq(tag, input)?;
+
+                for bytes in iter {
+                    seq.values.push(Value::Bytes(bytes?.into()));
+                }
+            }
+            WireType::String => {
+                let iter: sequence::Iter<'_, '_, str, D> = decoder.decode_seq(tag, input)?;
+
+                for string in iter {
+                    seq.values.push(Value::String(string?.into()));
+                }
+            }
+            WireType::Message => {
+                let iter: sequence::Iter<'_, '_, Object, D> = decoder.decode_seq(tag, input)?;
+
+                for object in iter {
+                    seq.values.push(Value::Message(object?));
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(seq)
+    }
+
+    /// Encode this sequence as a field with the given tag
+    fn encode(&self, encoder: &mut Encoder<'_>, tag: Tag, critical: bool) -> Result<(), Error> {
+        encoder.write_sequence(tag, critical, self.wire_type, |encoder| {
+            for value in self.iter() {
+                match value {
+                    Value::UInt64(value) => encoder.write(vint64::encode(*value))?,
+                    Value::SInt64(value) => encoder.write(vint64::signed::encode(*value))?,
+                    Value::Bytes(bytes) => encoder.write_value(bytes)?,
+                    Value::String(string) => encoder.write_value(string.as_bytes())?,
+                    Value::Message(object) => encoder.write_message(object)?,
+                    Value::Bool(_) | Value::Sequence(_) => {
+                        unreachable!("invalid sequence element")
+                    }
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    /// Compute the length of this sequence when encoded as a field
+    fn encoded_len(&self, tag: Tag) -> usize {
+        let body_len = self.body_len();
+
+        Header::new(tag, false, WireType::Sequence).encoded_len()
+            + vint64::encoded_len((body_len as u64) << 4 | self.wire_type as u64)
+            + body_len
+    }
+
+    /// Compute the length of the body of this sequence
+    fn body_len(&self) -> usize {
+        self.iter().map(Value::element_len).sum()
+    }
+}
+
+impl<'a> IntoIterator for &'a Sequence {
+    type Item = &'a Value;
+    type IntoIter = core::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Compute the length of a length-prefixed value
+fn length_prefixed(length: usize) -> usize {
+    vint64::encoded_len(length as u64) + length
+}
+
+#[cfg(all(test, feature = "sha2"))]
+mod tests {
+    use super::{Object, Sequence, Value};
+    use crate::{field::WireType, Decoder, Encoder, Message};
+
+    /// Create an example object containing every type of value
+    fn example_object() -> Object {
+        let mut nested = Object::new();
+        nested.insert(0, true, Value::UInt64(42)).unwrap();
+
+        let mut seq = Sequence::new(WireType::Message).unwrap();
+        seq.push(Value::Message(nested.clone())).unwrap();
+        seq.push(Value::Message(Object::new())).unwrap();
+
+        let mut object = Object::new();
+        object.insert(1, false, Value::Bool(true)).unwrap();
+        object.insert(2, false, Value::UInt64(42)).unwrap();
+        object.insert(3, true, Value::SInt64(-42)).unwrap();
+        object
+            .insert(4, false, Value::Bytes(b"foobar".to_vec()))
+            .unwrap();
+        object
+            .insert(5, false, Value::String("baz".into()))
+            .unwrap();
+        object.insert(6, false, Value::Message(nested)).unwrap();
+        object.insert(7, false, Value::Sequence(seq)).unwrap();
+        object.insert(8, false, Value::Bytes(vec![])).unwrap();
+        object
+    }
+
+    #[test]
+    fn round_trip() {
+        let object = example_object();
+        let encoded = object.encode_vec().unwrap();
+        assert_eq!(encoded.len(), object.encoded_len());
+
+        let mut decoder = Decoder::new();
+        let decoded = Object::decode(&mut decoder, &encoded).unwrap();
+        assert_eq!(object, decoded);
+    }
+
+    #[test]
+    fn round_trip_multibyte_prefixes() {
+        // Nested message and sequence bodies longer than fit in a single
+        // byte length prefix
+        let mut nested = Object::new();
+        nested
+            .insert(1, false, Value::Bytes(vec![42; 300]))
+            .unwrap();
+
+        let mut seq = Sequence::new(WireType::Message).unwrap();
+
+        for _ in 0..3 {
+            seq.push(Value::Message(nested.clone())).unwrap();
+        }
+
+        let mut object = Object::new();
+        object.insert(1, false, Value::Message(nested)).unwrap();
+        object.insert(2, false, Value::Sequence(seq)).unwrap();
+
+        // `encode_vec` uses a buffer which is exactly the size of the message
+        let encoded = object.encode_vec().unwrap();
+        assert_eq!(encoded.len(), object.encoded_len());
+
+        let mut decoder = Decoder::new();
+        let decoded = Object::decode(&mut decoder, &encoded).unwrap();
+        assert_eq!(object, decoded);
+    }
+
+    #[test]
+    fn message_seq_length_mismatch() {
+        let object = example_object();
+        let mut buffer = [0u8; 1024];
+        let mut encoder = Encoder::new(&mut buffer);
+
+        let body_len = 1 + object.encoded_len();
+        let messages = [&object as &dyn Message];
+
+        assert!(encoder
+            .message_seq(1, false, body_len, messages.iter().cloned())
+            .is_ok());
+        assert!(encoder
+            .message_seq(2, false, body_len + 1, messages.iter().cloned())
+            .is_err());
+    }
+
+    #[test]
+    fn digest_matches_decoder() {
+        let object = example_object();
+        let encoded = object.encode_vec().unwrap();
+
+        let mut decoder = Decoder::new();
+        Object::decode(&mut decoder, &encoded).unwrap();
+
+        let mut digest = [0u8; 32];
+        decoder.fill_digest(&mut digest).unwrap();
+        assert_eq!(&digest[..], &object.digest::<sha2::Sha256>()[..]);
+    }
+
+    #[test]
+    fn duplicate_tag() {
+        let mut object = Object::new();
+        object.insert(1, false, Value::UInt64(1)).unwrap();
+        assert!(object.insert(1, false, Value::UInt64(2)).is_err());
+    }
+
+    #[test]
+    fn heterogeneous_sequence() {
+        let mut seq = Sequence::new(WireType::UInt64).unwrap();
+        assert!(seq.push(Value::SInt64(-1)).is_err());
+    }
+}
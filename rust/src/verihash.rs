@@ -12,8 +12,101 @@ pub type DigestOutput<D> = GenericArray<u8, <D as Digest>::OutputSize>;
 // TODO(tarcieri): support string tags?
 const TAG_PREFIX: u8 = WireType::UInt64.to_u8();
 
+/// Versions of the Verihash algorithm.
+///
+/// Digests computed using one version are NOT comparable with digests
+/// computed using another. The default is [`VerihashVersion::V0`] so that
+/// digests computed by previous releases of this crate remain reproducible.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum VerihashVersion {
+    /// Original implementation: values are prefixed with their wire type and
+    /// hashed inline into the digest of the message containing them.
+    V0,
+
+    /// Tagged hashing scheme described in the Veriform specification: every
+    /// value is hashed separately, prefixed by its type tag character
+    /// (e.g. "u", "d", "O"), and messages and sequences are hashed as the
+    /// concatenation of the digests of their contents.
+    V1,
+}
+
+// `#[derive(Default)]` with `#[default]` on a variant needs Rust 1.62, which
+// is newer than this crate's MSRV (1.46)
+#[allow(clippy::derivable_impls)]
+impl Default for VerihashVersion {
+    fn default() -> Self {
+        VerihashVersion::V0
+    }
+}
+
+/// Type tags which domain separate the digests of each type of value in
+/// Verihash's tagged hashing scheme (see "Tagged Hashing" in the spec)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum TypeTag {
+    /// Booleans (`b`)
+    Bool,
+
+    /// Unsigned 64-bit integers (`u`)
+    UInt64,
+
+    /// Signed 64-bit integers (`i`)
+    SInt64,
+
+    /// Binary data (`d`)
+    Bytes,
+
+    /// Unicode strings (`s`)
+    String,
+
+    /// Message objects (`O`)
+    Message,
+
+    /// Sequences (`A`)
+    Sequence,
+}
+
+impl TypeTag {
+    /// Get the ASCII character which identifies this type
+    pub fn to_u8(self) -> u8 {
+        match self {
+            TypeTag::Bool => b'b',
+            TypeTag::UInt64 => b'u',
+            TypeTag::SInt64 => b'i',
+            TypeTag::Bytes => b'd',
+            TypeTag::String => b's',
+            TypeTag::Message => b'O',
+            TypeTag::Sequence => b'A',
+        }
+    }
+}
+
+impl From<WireType> for TypeTag {
+    fn from(wire_type: WireType) -> TypeTag {
+        match wire_type {
+            WireType::False | WireType::True => TypeTag::Bool,
+            WireType::UInt64 => TypeTag::UInt64,
+            WireType::SInt64 => TypeTag::SInt64,
+            WireType::Bytes => TypeTag::Bytes,
+            WireType::String => TypeTag::String,
+            WireType::Message => TypeTag::Message,
+            WireType::Sequence => TypeTag::Sequence,
+        }
+    }
+}
+
 /// Verihash hasher: computes digests of both messages and sequences
-pub(crate) struct Hasher<D: Digest>(D);
+pub(crate) struct Hasher<D: Digest> {
+    /// Underlying hash function
+    digest: D,
+
+    /// Version of the Verihash algorithm to compute
+    version: VerihashVersion,
+
+    /// Hasher for a dynamically sized value whose tagged digest is presently
+    /// being computed, along with the number of bytes remaining in it
+    /// (`V1` only)
+    value: Option<(D, usize)>,
+}
 
 impl<D> Hasher<D>
 where
@@ -21,7 +114,57 @@ where
 {
     /// Create a new Verihash hasher
     pub fn new() -> Self {
-        Hasher(D::new())
+        Hasher {
+            digest: D::new(),
+            version: VerihashVersion::V0,
+            value: None,
+        }
+    }
+
+    /// Create a new hasher for a tagged hash of the given type
+    pub fn for_type(type_tag: TypeTag) -> Self {
+        let mut hasher = Self::new();
+        hasher.version = VerihashVersion::V1;
+        hasher.update(&[type_tag.to_u8()]);
+        hasher
+    }
+
+    /// Create a new hasher for the digest of a message
+    pub fn for_message(version: VerihashVersion) -> Self {
+        match version {
+            VerihashVersion::V0 => Self::new(),
+            VerihashVersion::V1 => Self::for_type(TypeTag::Message),
+        }
+    }
+
+    /// Create a new hasher for the digest of a sequence whose elements are
+    /// of the given wire type
+    pub fn for_sequence(version: VerihashVersion, wire_type: WireType) -> Self {
+        // Domain separate sequence hashes by their contained wire type
+        match version {
+            VerihashVersion::V0 => {
+                let mut hasher = Self::new();
+                hasher.update(&[wire_type.to_u8()]);
+                hasher
+            }
+            VerihashVersion::V1 => {
+                let mut hasher = Self::for_type(TypeTag::Sequence);
+                hasher.update(&[TypeTag::from(wire_type).to_u8()]);
+                hasher
+            }
+        }
+    }
+
+    /// Get the version of Verihash this hasher is computing
+    pub fn version(&self) -> VerihashVersion {
+        self.version
+    }
+
+    /// Compute the tagged hash of a scalar value with the given type
+    pub fn typed_digest(type_tag: TypeTag, data: &[u8]) -> DigestOutput<D> {
+        let mut hasher = Self::for_type(type_tag);
+        hasher.update(data);
+        hasher.finalize()
     }
 
     /// Hash a tagged boolean value
@@ -64,30 +207,82 @@ where
     /// Hash a numerical tag
     // TODO(tarcieri): support string tags?
     pub fn tag(&mut self, tag: Tag) {
-        self.update(&[TAG_PREFIX]);
+        if self.version == VerihashVersion::V0 {
+            self.update(&[TAG_PREFIX]);
+        }
+
         self.update(&tag.to_le_bytes());
     }
 
-    /// Hash a dynamically sized value
+    /// Hash a dynamically sized value.
+    ///
+    /// The body of bytes and string values must be subsequently hashed
+    /// using [`Hasher::value_chunk`]. The digests of messages and sequences
+    /// must be hashed using [`Hasher::nested_digest`].
     pub fn dynamically_sized_value(&mut self, wire_type: WireType, length: usize) {
-        self.update(&[wire_type.to_u8()]);
-        self.update(&(length as u64).to_le_bytes());
+        match self.version {
+            VerihashVersion::V0 => {
+                self.update(&[wire_type.to_u8()]);
+                self.update(&(length as u64).to_le_bytes());
+            }
+            VerihashVersion::V1 => match wire_type {
+                WireType::Bytes | WireType::String => {
+                    let mut value = D::new();
+                    value.update([TypeTag::from(wire_type).to_u8()]);
+                    self.value = Some((value, length));
+                    self.value_chunk(&[]);
+                }
+                _ => (),
+            },
+        }
+    }
+
+    /// Hash a chunk of the body of a dynamically sized value
+    pub fn value_chunk(&mut self, bytes: &[u8]) {
+        if let Some((mut value, remaining)) = self.value.take() {
+            value.update(bytes);
+
+            if bytes.len() < remaining {
+                self.value = Some((value, remaining - bytes.len()));
+            } else {
+                self.update(&value.finalize());
+            }
+        } else {
+            self.update(bytes);
+        }
+    }
+
+    /// Hash the digest of a nested message or sequence
+    pub fn nested_digest(&mut self, wire_type: WireType, digest: &DigestOutput<D>) {
+        match self.version {
+            VerihashVersion::V0 => self.fixed_size_value(wire_type, digest),
+            VerihashVersion::V1 => self.update(digest),
+        }
     }
 
     /// Hash an untagged value
     pub fn fixed_size_value(&mut self, wire_type: WireType, body: &[u8]) {
-        self.update(&[wire_type.to_u8()]);
-        self.update(body);
+        match self.version {
+            VerihashVersion::V0 => {
+                self.update(&[wire_type.to_u8()]);
+                self.update(body);
+            }
+            VerihashVersion::V1 => {
+                let digest = Self::typed_digest(wire_type.into(), body);
+                self.update(&digest);
+            }
+        }
     }
 
     /// Update data directly into the underlying hash function
     pub fn update(&mut self, data: &[u8]) {
-        self.0.update(data);
+        self.digest.update(data);
     }
 
     /// Finish computing the digest, returning the output value
     pub fn finalize(self) -> DigestOutput<D> {
-        self.0.finalize()
+        debug_assert!(self.value.is_none());
+        self.digest.finalize()
     }
 }
 
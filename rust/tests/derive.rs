@@ -1,10 +1,15 @@
 //! Integration tests for `veriform_derive`
 
 use heapless::{
-    consts::{U1024, U8},
-    Vec,
+    consts::{U1024, U16, U8},
+    FnvIndexMap, Vec,
+};
+use veriform::{
+    encoder::LengthTable,
+    error::Segment,
+    reflect::{FieldType, MessageKind, Reflect},
+    Decoder, Message,
 };
-use veriform::{Decoder, Message};
 
 /// Buffer type.
 ///
@@ -18,10 +23,10 @@ pub fn new_buffer() -> Buffer {
     vec
 }
 
-#[derive(Message, Debug, Eq, PartialEq)]
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
 pub struct EmptyStruct {}
 
-#[derive(Message, Debug, Eq, PartialEq)]
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
 pub enum ExampleEnum {
     #[field(tag = 0, wire_type = "bytes", size = 32)]
     BytesVariant([u8; 32]),
@@ -53,7 +58,7 @@ fn enum_round_trip() {
     assert_eq!(example, decoded);
 }
 
-#[derive(Message, Debug, Eq, PartialEq)]
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
 pub struct ExampleStruct {
     #[field(tag = 0, wire_type = "uint64", critical = true)]
     pub uint64_field: u64,
@@ -104,3 +109,603 @@ fn struct_round_trip() {
 
     assert_eq!(example, decoded);
 }
+
+#[test]
+fn struct_encode_with_lengths() {
+    let mut example = ExampleStruct::default();
+    example
+        .msg_sequence_field
+        .push(ExampleEnum::MessageVariant(EmptyStruct {}))
+        .unwrap();
+
+    let mut expected_buf = new_buffer();
+    let expected = example.encode(&mut expected_buf).unwrap();
+
+    // One entry for the sequence, one for each of its elements, and one for
+    // the message nested in the last element
+    let mut entries = [0usize; 6];
+    let mut lengths = LengthTable::new(&mut entries);
+    let encoded_len = example.encoded_len_with(&mut lengths).unwrap();
+    assert_eq!(encoded_len, example.encoded_len());
+    assert_eq!(lengths.len(), 6);
+
+    let mut encoded_buf = new_buffer();
+    let encoded = example.encode_with(&mut encoded_buf, &mut lengths).unwrap();
+    assert_eq!(encoded, expected);
+
+    // Too few entries to record every length
+    let mut entries = [0usize; 5];
+    let mut lengths = LengthTable::new(&mut entries);
+    assert!(example.encoded_len_with(&mut lengths).is_err());
+}
+
+#[test]
+fn struct_error_location() {
+    let example = ExampleStruct::default();
+
+    let mut encoded_buf = new_buffer();
+    let encoded_len = example.encode(&mut encoded_buf).unwrap().len();
+    encoded_buf.truncate(encoded_len);
+
+    // Replace the tag of the second element of the sequence with an unknown one
+    let element_offset = 43;
+    encoded_buf[element_offset] = 169;
+
+    let mut decoder = Decoder::new();
+    let err = ExampleStruct::decode(&mut decoder, &encoded_buf).unwrap_err();
+
+    assert_eq!(
+        err.kind(),
+        veriform::error::Kind::FieldHeader {
+            tag: Some(5),
+            wire_type: None
+        }
+    );
+    assert_eq!(err.position(), Some(element_offset));
+
+    let path = err.path().unwrap();
+    assert_eq!(
+        path.iter().collect::<Vec<_, U8>>(),
+        [Segment::Field(2), Segment::Element(1)]
+    );
+}
+
+/// `ExampleStruct` with a larger sequence capacity
+#[derive(Message, Debug, Eq, PartialEq)]
+pub struct LargeExampleStruct {
+    #[field(tag = 0, wire_type = "uint64", critical = true)]
+    pub uint64_field: u64,
+
+    #[field(tag = 1, wire_type = "sint64", critical = true)]
+    pub sint64_field: i64,
+
+    #[field(tag = 2, wire_type = "sequence", critical = true, max = 16)]
+    pub msg_sequence_field: heapless::Vec<ExampleEnum, U16>,
+}
+
+#[test]
+fn struct_sequence_overflow() {
+    let mut msg_sequence_field = heapless::Vec::new();
+
+    for _ in 0..16 {
+        msg_sequence_field.push(ExampleEnum::default()).unwrap();
+    }
+
+    let example = LargeExampleStruct {
+        uint64_field: 42,
+        sint64_field: -42,
+        msg_sequence_field,
+    };
+
+    let mut encoded_buf = new_buffer();
+    let encoded_len = example.encode(&mut encoded_buf).unwrap().len();
+    encoded_buf.truncate(encoded_len);
+
+    // The sequence is abandoned partway through, which must not panic
+    let mut decoder = Decoder::new();
+    let err = ExampleStruct::decode(&mut decoder, &encoded_buf).unwrap_err();
+
+    assert_eq!(
+        err.kind(),
+        veriform::error::Kind::Decode {
+            element: veriform::message::Element::Value,
+            wire_type: veriform::field::WireType::Sequence
+        }
+    );
+
+    // The incomplete sequence is reported by subsequent calls
+    let err = decoder.fill_digest(&mut [0u8; 32]).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        veriform::error::Kind::IncompleteSequence { .. }
+    ));
+}
+
+#[derive(Message, Reflect, Debug, Default, Eq, PartialEq)]
+pub struct MapStruct {
+    #[field(tag = 0, wire_type = "map", critical = true)]
+    pub names: FnvIndexMap<u64, heapless::String<U16>, U8>,
+
+    #[field(tag = 1, wire_type = "map")]
+    pub nested: FnvIndexMap<i64, EmptyStruct, U8>,
+}
+
+impl MapStruct {
+    /// Create an example map struct, inserting entries in the given order
+    fn new(keys: &[u64]) -> Self {
+        let mut example = Self::default();
+
+        for &key in keys {
+            let name = if key % 2 == 0 { "even" } else { "odd" };
+            example.names.insert(key, name.into()).unwrap();
+            example
+                .nested
+                .insert(-(key as i64), EmptyStruct {})
+                .unwrap();
+        }
+
+        example
+    }
+}
+
+#[test]
+fn map_insertion_order() {
+    let example = MapStruct::new(&[1, 2, 3]);
+
+    let mut encoded_buf = new_buffer();
+    let encoded = example.encode(&mut encoded_buf).unwrap();
+    assert_eq!(encoded.len(), example.encoded_len());
+
+    // Same keys and values, inserted in a different order
+    let reordered = MapStruct::new(&[3, 1, 2]);
+
+    let mut reordered_buf = new_buffer();
+    assert_eq!(reordered.encode(&mut reordered_buf).unwrap(), encoded);
+
+    let mut entries = [0usize; 16];
+    let mut lengths = LengthTable::new(&mut entries);
+    let encoded_len = example.encoded_len_with(&mut lengths).unwrap();
+    assert_eq!(encoded_len, encoded.len());
+
+    let mut encoded_with_buf = new_buffer();
+    let encoded_with = example
+        .encode_with(&mut encoded_with_buf, &mut lengths)
+        .unwrap();
+    assert_eq!(encoded_with, encoded);
+
+    let mut decoder = Decoder::new();
+    let decoded = MapStruct::decode(&mut decoder, encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    let descriptor = MapStruct::DESCRIPTOR;
+    assert_eq!(descriptor.field(0).unwrap().ty, FieldType::Map);
+}
+
+/// Struct with integer fields narrower than their wire types
+#[derive(Message, Debug, Eq, PartialEq)]
+pub struct NarrowStruct {
+    #[field(tag = 0, wire_type = "uint64")]
+    pub byte: u8,
+
+    #[field(tag = 1, wire_type = "uint64")]
+    pub length: usize,
+
+    #[field(tag = 2, wire_type = "sint64")]
+    pub offset: i16,
+}
+
+/// `NarrowStruct` with 64-bit fields
+#[derive(Message, Debug, Eq, PartialEq)]
+pub struct WideStruct {
+    #[field(tag = 0, wire_type = "uint64")]
+    pub byte: u64,
+
+    #[field(tag = 1, wire_type = "uint64")]
+    pub length: u64,
+
+    #[field(tag = 2, wire_type = "sint64")]
+    pub offset: i64,
+}
+
+#[test]
+fn narrow_integers() {
+    let example = NarrowStruct {
+        byte: 255,
+        length: 1024,
+        offset: -300,
+    };
+
+    let mut encoded_buf = new_buffer();
+    let encoded = example.encode(&mut encoded_buf).unwrap();
+    assert_eq!(encoded.len(), example.encoded_len());
+
+    // Narrow integers are encoded identically to the equivalent 64-bit ones
+    let wide = WideStruct {
+        byte: 255,
+        length: 1024,
+        offset: -300,
+    };
+
+    let mut wide_buf = new_buffer();
+    assert_eq!(wide.encode(&mut wide_buf).unwrap(), encoded);
+
+    let decoded = NarrowStruct::decode(&mut Decoder::new(), encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    // Values which don't fit in the field's type are rejected
+    let wide = WideStruct {
+        byte: 255,
+        length: 1024,
+        offset: -40000,
+    };
+
+    let mut wide_buf = new_buffer();
+    let encoded = wide.encode(&mut wide_buf).unwrap();
+
+    let err = NarrowStruct::decode(&mut Decoder::new(), encoded).unwrap_err();
+    assert_eq!(err.kind(), veriform::error::Kind::Range { tag: 2 });
+    assert_eq!(err.position(), Some(6));
+    assert_eq!(
+        err.path().unwrap().iter().collect::<Vec<_, U8>>(),
+        [Segment::Field(2)]
+    );
+}
+
+/// Message nesting a type which doesn't impl `Reflect`
+#[derive(Message, Debug, Eq, PartialEq)]
+pub struct NarrowWrapper {
+    #[field(tag = 0, wire_type = "message")]
+    pub narrow: NarrowStruct,
+}
+
+#[test]
+fn nested_message_without_reflect() {
+    let example = NarrowWrapper {
+        narrow: NarrowStruct {
+            byte: 1,
+            length: 2,
+            offset: -3,
+        },
+    };
+
+    let mut buffer = new_buffer();
+    let encoded = example.encode(&mut buffer).unwrap();
+    assert_eq!(encoded.len(), example.encoded_len());
+
+    let decoded = NarrowWrapper::decode(&mut Decoder::new(), encoded).unwrap();
+    assert_eq!(example, decoded);
+}
+
+/// Recursive policy expression
+#[cfg(feature = "alloc")]
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub enum Policy {
+    #[field(tag = 0, wire_type = "uint64")]
+    Key(u64),
+
+    #[field(tag = 1, wire_type = "message")]
+    Not(Box<Policy>),
+
+    #[field(tag = 2, wire_type = "message")]
+    Shared(std::sync::Arc<Policy>),
+}
+
+#[cfg(feature = "alloc")]
+impl Policy {
+    /// Create a policy negating a key the given number of times
+    fn negated(key: u64, depth: usize) -> Self {
+        (0..depth).fold(Policy::Key(key), |policy, _| Policy::Not(Box::new(policy)))
+    }
+}
+
+/// Tree of delegations, each of which delegates to a shared policy
+#[cfg(feature = "alloc")]
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub struct Delegation {
+    #[field(tag = 0, wire_type = "message")]
+    pub policy: std::rc::Rc<Policy>,
+
+    #[field(tag = 1, wire_type = "sequence")]
+    pub delegates: std::vec::Vec<Delegation>,
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn recursive_round_trip() {
+    let policy = std::rc::Rc::new(Policy::Shared(std::sync::Arc::new(Policy::negated(7, 3))));
+
+    let example = Delegation {
+        policy: policy.clone(),
+        delegates: vec![
+            Delegation {
+                policy: policy.clone(),
+                delegates: vec![],
+            },
+            Delegation {
+                policy,
+                delegates: vec![],
+            },
+        ],
+    };
+
+    let encoded = example.encode_vec().unwrap();
+
+    let mut encoded_buf = new_buffer();
+    assert_eq!(example.encode(&mut encoded_buf).unwrap(), &encoded[..]);
+
+    let mut decoder = Decoder::new();
+    let decoded = Delegation::decode(&mut decoder, &encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    // Pointers share the descriptor of the message they point to
+    let descriptor = Delegation::DESCRIPTOR;
+    let nested = descriptor.field(0).unwrap().nested_descriptor().unwrap();
+    assert_eq!(nested.name, "Policy");
+    assert_eq!(
+        nested.field(1).unwrap().nested_descriptor().unwrap().name,
+        "Policy"
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn recursive_nesting_depth() {
+    use veriform::{decoder::Config, error::Kind};
+
+    let encoded = Policy::negated(42, 4).encode_vec().unwrap();
+
+    let mut decoder = Decoder::with_config(Config::default().max_depth(5));
+    assert!(Policy::decode(&mut decoder, &encoded).is_ok());
+
+    // The fifth message nested inside the toplevel one exceeds the limit
+    let mut decoder = Decoder::with_config(Config::default().max_depth(4));
+    let err = Policy::decode(&mut decoder, &encoded).unwrap_err();
+    assert_eq!(err.kind(), Kind::NestingDepth { max: 4 });
+    assert_eq!(err.position(), Some(6));
+    assert_eq!(
+        err.to_string(),
+        "maximum message nesting depth exceeded: max=4 position=6 path=1.1.1.1"
+    );
+
+    // Recursion beyond the decoder's own limit fails cleanly, reporting the
+    // full path to the message which exceeded it
+    let encoded = Policy::negated(42, 64).encode_vec().unwrap();
+    let err = Policy::decode(&mut Decoder::new(), &encoded).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        Kind::NestingDepth {
+            max: veriform::decoder::MAX_DEPTH
+        }
+    );
+    let path = err.path().unwrap();
+    assert!(!path.is_truncated());
+    assert_eq!(path.len(), veriform::decoder::MAX_DEPTH);
+}
+
+/// Recursive linked list of values, whose last node has no `next` field
+#[cfg(feature = "alloc")]
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub struct Node {
+    #[field(tag = 0, wire_type = "uint64")]
+    pub value: u64,
+
+    #[field(tag = 1, wire_type = "message")]
+    pub next: Option<Box<Node>>,
+
+    #[field(tag = 2, wire_type = "bool")]
+    pub last: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl Node {
+    /// Create a list containing the given values
+    fn list(values: &[u64]) -> Option<Box<Self>> {
+        values.iter().rev().fold(None, |next, &value| {
+            Some(Box::new(Node {
+                value,
+                last: next.is_none(),
+                next,
+            }))
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn optional_message_round_trip() {
+    let example = *Node::list(&[1, 2, 3]).unwrap();
+    let encoded = example.encode_vec().unwrap();
+    assert_eq!(encoded.len(), example.encoded_len());
+
+    let mut encoded_buf = new_buffer();
+    assert_eq!(example.encode(&mut encoded_buf).unwrap(), &encoded[..]);
+
+    let decoded = Node::decode(&mut Decoder::new(), &encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    // Absent fields aren't encoded, and are decoded as `None`
+    let last = Node {
+        value: 3,
+        next: None,
+        last: true,
+    };
+
+    let mut buffer = new_buffer();
+    let mut encoder = veriform::Encoder::new(&mut buffer);
+    encoder.uint64(0, false, 3).unwrap();
+    encoder.boolean(2, false, true).unwrap();
+    let expected = encoder.finish();
+
+    assert_eq!(last.encode_vec().unwrap(), expected);
+    assert_eq!(Node::decode(&mut Decoder::new(), expected).unwrap(), last);
+
+    let field = Node::DESCRIPTOR.field(1).unwrap();
+    assert!(field.optional);
+    assert_eq!(field.nested_descriptor().unwrap().name, "Node");
+}
+
+/// Generic envelope containing a signed payload message
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub struct Signed<T> {
+    #[field(tag = 0, wire_type = "message", critical = true)]
+    pub payload: T,
+
+    #[field(tag = 1, wire_type = "bytes", size = 4)]
+    pub signature: [u8; 4],
+}
+
+/// Generic sequence of messages
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub struct Batch<T> {
+    #[field(tag = 0, wire_type = "sequence", max = 8)]
+    pub items: heapless::Vec<T, U8>,
+}
+
+/// Generic enum with a custom bound on one of its variants
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub enum Either<L, R> {
+    #[field(tag = 0, wire_type = "message")]
+    Left(L),
+
+    #[field(tag = 1, wire_type = "message", bound = "R: Reflect + Eq")]
+    Right(R),
+}
+
+#[test]
+fn generic_round_trip() {
+    let signed = |payload| Signed {
+        payload,
+        signature: [1, 2, 3, 4],
+    };
+
+    let mut example = Batch {
+        items: heapless::Vec::new(),
+    };
+
+    let items = &mut example.items;
+    items
+        .push(signed(Either::Left(ExampleEnum::default())))
+        .unwrap();
+    items.push(signed(Either::Right(EmptyStruct {}))).unwrap();
+
+    let mut encoded_buf = new_buffer();
+    let encoded = example.encode(&mut encoded_buf).unwrap();
+
+    let mut decoder = Decoder::new();
+    let decoded = Batch::decode(&mut decoder, encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    let descriptor = Batch::<Signed<Either<ExampleEnum, EmptyStruct>>>::DESCRIPTOR;
+    assert_eq!(descriptor.name, "Batch");
+
+    let item = descriptor.field(0).unwrap().nested_descriptor().unwrap();
+    assert_eq!(item.name, "Signed");
+
+    let payload = item.field(0).unwrap().nested_descriptor().unwrap();
+    assert_eq!(payload.name, "Either");
+    assert_eq!(payload.kind, MessageKind::Enum);
+    assert_eq!(
+        payload.field(0).unwrap().nested_descriptor().unwrap().name,
+        "ExampleEnum"
+    );
+}
+
+/// Generic message with a lifetime parameter
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub struct Env<'a, T> {
+    #[field(tag = 0, wire_type = "message")]
+    pub payload: T,
+
+    pub _marker: std::marker::PhantomData<&'a ()>,
+}
+
+/// Generic enum with a lifetime parameter
+#[derive(Message, Reflect, Debug, Eq, PartialEq)]
+pub enum EnvEnum<'a, T> {
+    #[field(tag = 0, wire_type = "message")]
+    Env(Env<'a, T>),
+
+    #[field(tag = 1, wire_type = "message")]
+    Empty(EmptyStruct),
+}
+
+#[test]
+fn lifetime_round_trip() {
+    let example = Env {
+        payload: ExampleEnum::default(),
+        _marker: std::marker::PhantomData,
+    };
+
+    let mut encoded_buf = new_buffer();
+    let encoded = example.encode(&mut encoded_buf).unwrap();
+    assert_eq!(encoded.len(), example.encoded_len());
+
+    let decoded = Env::decode(&mut Decoder::new(), encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    let example = EnvEnum::Env(example);
+    let mut encoded_buf = new_buffer();
+    let encoded = example.encode(&mut encoded_buf).unwrap();
+    assert_eq!(encoded.len(), example.encoded_len());
+
+    let decoded = EnvEnum::decode(&mut Decoder::new(), encoded).unwrap();
+    assert_eq!(example, decoded);
+
+    let descriptor = EnvEnum::<'_, ExampleEnum>::DESCRIPTOR;
+    let env = descriptor.field(0).unwrap().nested_descriptor().unwrap();
+    assert_eq!(env.name, "Env");
+    assert_eq!(env.fields.len(), 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn struct_text_with_names() {
+    let example = ExampleStruct::default();
+    let text = veriform::text::to_string_with_names(&example).unwrap();
+    assert!(text.starts_with("0! uint64_field: 42\n1! sint64_field: -42\n"));
+    assert!(text.contains("\n    0 BytesVariant: x\"000102"));
+
+    let decoded: ExampleStruct = veriform::text::from_str_with_names(&text).unwrap();
+    assert_eq!(decoded.encode_vec().unwrap(), example.encode_vec().unwrap());
+
+    // Field names must match the schema
+    let text = text.replace("sint64_field", "int64_field");
+    assert!(veriform::text::from_str_with_names::<ExampleStruct>(&text).is_err());
+    assert!(veriform::text::from_str::<ExampleStruct>(&text).is_ok());
+}
+
+#[test]
+fn struct_descriptor() {
+    let descriptor = ExampleStruct::DESCRIPTOR;
+    assert_eq!(descriptor.name, "ExampleStruct");
+    assert_eq!(descriptor.kind, MessageKind::Struct);
+
+    // The digest isn't a field of the message
+    assert_eq!(descriptor.fields.len(), 3);
+
+    let field = descriptor.field_by_name("sint64_field").unwrap();
+    assert_eq!(field.tag, 1);
+    assert_eq!(field.ty, FieldType::SInt64);
+    assert!(field.critical);
+    assert!(field.nested_descriptor().is_none());
+
+    let field = descriptor.field(2).unwrap();
+    assert_eq!(field.name, "msg_sequence_field");
+    assert_eq!(field.ty, FieldType::Sequence);
+    assert_eq!(field.max, Some(8));
+
+    let element = field.nested_descriptor().unwrap();
+    assert_eq!(element.name, "ExampleEnum");
+    assert_eq!(element.kind, MessageKind::Enum);
+
+    let variant = element.field(0).unwrap();
+    assert_eq!(variant.name, "BytesVariant");
+    assert_eq!(variant.ty, FieldType::Bytes);
+    assert_eq!(variant.size, Some(32));
+    assert!(!variant.critical);
+
+    let variant = element.field_by_name("MessageVariant").unwrap();
+    assert_eq!(variant.ty, FieldType::Message);
+    assert_eq!(variant.nested_descriptor().unwrap().name, "EmptyStruct");
+    assert!(element.field(2).is_none());
+}
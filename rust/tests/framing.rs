@@ -0,0 +1,249 @@
+//! Integration tests for message framing
+
+#![cfg(all(feature = "std", feature = "sha2"))]
+
+use veriform::{
+    error::Kind,
+    field::WireType,
+    framing::{Config, Reader, Writer},
+    value::{Object, Value},
+    Message, VerihashVersion,
+};
+
+type Sha256 = sha2::Sha256;
+
+/// Build an example message
+fn example_message(n: u64) -> Object {
+    let mut object = Object::new();
+    object.insert(0, false, Value::UInt64(n)).unwrap();
+    object
+        .insert(1, false, Value::String(format!("message {}", n)))
+        .unwrap();
+    object
+}
+
+/// Encode the given messages as a stream of frames
+fn encode_stream(config: Config, messages: &[Object]) -> Vec<u8> {
+    let mut writer = Writer::<_, Sha256>::new(Vec::new(), config);
+
+    for message in messages {
+        writer.write(message).unwrap();
+    }
+
+    writer.into_inner()
+}
+
+/// Decode a stream of frames, returning the decoded messages (or error)
+fn decode_stream(config: Config, stream: &[u8]) -> Vec<Result<Object, veriform::Error>> {
+    Reader::<_, Object, Sha256>::new(stream, config).collect()
+}
+
+#[test]
+fn round_trip() {
+    let messages: Vec<_> = (0..3).map(example_message).collect();
+    let stream = encode_stream(Config::default(), &messages);
+
+    let encoded_len = messages[0].encoded_len();
+    assert_eq!(stream[0], (encoded_len << 1 | 1) as u8);
+    assert_eq!(
+        &stream[1..=encoded_len],
+        &messages[0].encode_vec().unwrap()[..]
+    );
+
+    let decoded: Vec<_> = decode_stream(Config::default(), &stream)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(decoded, messages);
+}
+
+#[test]
+fn round_trip_with_trailer() {
+    for &version in &[VerihashVersion::V0, VerihashVersion::V1] {
+        let config = Config {
+            verihash_trailer: true,
+            verihash_version: version,
+            ..Config::default()
+        };
+
+        let messages: Vec<_> = (0..3).map(example_message).collect();
+        let stream = encode_stream(config, &messages);
+
+        let body_len = messages[0].encoded_len();
+        assert_eq!(
+            &stream[(1 + body_len)..(1 + body_len + 32)],
+            &messages[0].digest_with_version::<Sha256>(version)[..]
+        );
+
+        let decoded: Vec<_> = decode_stream(config, &stream)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(decoded, messages);
+    }
+}
+
+#[test]
+fn trailer_mismatch() {
+    let config = Config {
+        verihash_trailer: true,
+        ..Config::default()
+    };
+
+    let messages: Vec<_> = (0..2).map(example_message).collect();
+    let mut stream = encode_stream(config, &messages);
+    let last = stream.len() - 1;
+    stream[last] ^= 1;
+
+    let results = decode_stream(config, &stream);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(err.kind(), Kind::Trailer);
+    assert_eq!(err.position(), Some(stream.len() - 32));
+}
+
+#[test]
+fn max_frame_size() {
+    let message = example_message(42);
+    let stream = encode_stream(Config::default(), &[message.clone(), message.clone()]);
+
+    let config = Config {
+        max_frame_size: message.encoded_len() - 1,
+        ..Config::default()
+    };
+
+    let err = decode_stream(config, &stream)[0].clone().unwrap_err();
+    assert_eq!(
+        err.kind(),
+        Kind::FrameSize {
+            size: message.encoded_len(),
+            max: message.encoded_len() - 1
+        }
+    );
+    assert_eq!(err.position(), Some(0));
+
+    let mut writer = Writer::<_, Sha256>::new(Vec::new(), config);
+    let err = writer.write(&message).unwrap_err();
+    assert!(matches!(err.kind(), Kind::FrameSize { .. }));
+    assert!(writer.into_inner().is_empty());
+}
+
+#[test]
+fn truncated_stream() {
+    let messages: Vec<_> = (0..2).map(example_message).collect();
+    let stream = encode_stream(Config::default(), &messages);
+
+    let results = decode_stream(Config::default(), &stream[..stream.len() - 3]);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap(), &messages[0]);
+
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(
+        err.kind(),
+        Kind::Truncated {
+            remaining: 3,
+            wire_type: WireType::Message
+        }
+    );
+    assert_eq!(err.position(), Some(stream.len() - 3));
+}
+
+#[test]
+fn error_position_relative_to_stream() {
+    let messages: Vec<_> = (0..2).map(example_message).collect();
+    let mut stream = encode_stream(Config::default(), &messages);
+
+    // Corrupt the field header of the second message's first field
+    let second_frame = 1 + messages[0].encoded_len();
+    stream[second_frame + 1] = 0;
+
+    let results = decode_stream(Config::default(), &stream);
+    assert_eq!(results.len(), 2);
+
+    let err = results[1].as_ref().unwrap_err();
+    let position = err.position().unwrap();
+    assert!(position > second_frame, "position: {}", position);
+    assert!(position < stream.len(), "position: {}", position);
+}
+
+#[cfg(feature = "async")]
+mod codec {
+    use super::*;
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+    use veriform::framing::Codec;
+
+    #[test]
+    fn round_trip() {
+        let config = Config {
+            verihash_trailer: true,
+            ..Config::default()
+        };
+
+        let messages: Vec<_> = (0..3).map(example_message).collect();
+        let mut codec = Codec::<Object, Sha256>::new(config);
+        let mut buffer = BytesMut::new();
+
+        for message in &messages {
+            codec.encode(message, &mut buffer).unwrap();
+        }
+
+        assert_eq!(&buffer[..], &encode_stream(config, &messages)[..]);
+
+        // Feed the decoder one byte at a time
+        let encoded = buffer.split();
+        let mut decoded = vec![];
+
+        for &byte in encoded.iter() {
+            buffer.extend_from_slice(&[byte]);
+
+            if let Some(message) = codec.decode(&mut buffer).unwrap() {
+                decoded.push(message);
+            }
+        }
+
+        assert_eq!(decoded, messages);
+        assert_eq!(codec.position(), encoded.len());
+        assert_eq!(codec.decode_eof(&mut buffer).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let message = example_message(1);
+        let stream = encode_stream(Config::default(), &[message]);
+
+        let mut codec = Codec::<Object, Sha256>::default();
+        let mut buffer = BytesMut::from(&stream[..stream.len() - 2]);
+
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+
+        let err = codec.decode_eof(&mut buffer).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            Kind::Truncated {
+                remaining: 2,
+                wire_type: WireType::Message
+            }
+        );
+        assert_eq!(err.position(), Some(stream.len() - 2));
+    }
+
+    #[test]
+    fn max_frame_size() {
+        let message = example_message(1);
+        let stream = encode_stream(Config::default(), core::slice::from_ref(&message));
+
+        let mut codec = Codec::<Object, Sha256>::new(Config {
+            max_frame_size: 4,
+            ..Config::default()
+        });
+
+        let err = codec.decode(&mut BytesMut::from(&stream[..1])).unwrap_err();
+        assert!(matches!(err.kind(), Kind::FrameSize { max: 4, .. }));
+        assert!(codec.encode(&message, &mut BytesMut::new()).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Integration tests for `serde` support
+
+#![cfg(all(feature = "serde", feature = "alloc", feature = "sha2"))]
+
+use serde::{Deserialize, Serialize};
+use veriform::{
+    error, field::WireType, serde as vf_serde, value::Object, Decoder, Encoder, Message,
+    Sha256Digest,
+};
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct Inner {
+    pub id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    #[serde(rename = "7")]
+    Blue,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub enum Shape {
+    Circle(u64),
+    Rect { width: u64, height: u64 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct Example<'a> {
+    pub flag: bool,
+    pub count: u64,
+    pub offset: i32,
+    pub name: String,
+    #[serde(rename = "8")]
+    pub label: &'a str,
+    #[serde(rename = "9")]
+    pub missing: Option<u64>,
+    #[serde(rename = "10")]
+    pub inner: Inner,
+    #[serde(rename = "11")]
+    pub numbers: Vec<u64>,
+    #[serde(rename = "12")]
+    pub inners: Vec<Inner>,
+    #[serde(rename = "13")]
+    pub color: Color,
+    #[serde(rename = "14")]
+    pub shape: Shape,
+    #[serde(rename = "15")]
+    pub pair: (i64, String),
+}
+
+fn example() -> Example<'static> {
+    Example {
+        flag: true,
+        count: 42,
+        offset: -42,
+        name: "foo".into(),
+        label: "bar",
+        missing: None,
+        inner: Inner { id: 1 },
+        numbers: vec![1, 2, 3],
+        inners: vec![Inner { id: 2 }, Inner { id: 3 }],
+        color: Color::Blue,
+        shape: Shape::Rect {
+            width: 2,
+            height: 3,
+        },
+        pair: (-1, "baz".into()),
+    }
+}
+
+/// Compute the Verihash digest of a message using the schema-less decoder
+fn expected_digest(encoded: &[u8]) -> Sha256Digest {
+    let mut decoder = Decoder::new();
+    Object::decode(&mut decoder, encoded).unwrap();
+
+    let mut digest = Sha256Digest::default();
+    decoder.fill_digest(&mut digest).unwrap();
+    digest
+}
+
+#[test]
+fn round_trip() {
+    let example = example();
+    let encoded = vf_serde::to_vec(&example).unwrap();
+    let decoded: Example<'_> = vf_serde::from_slice(&encoded).unwrap();
+    assert_eq!(example, decoded);
+}
+
+#[test]
+fn declaration_order_tags() {
+    let mut buffer = [0u8; 64];
+    let mut encoder = Encoder::new(&mut buffer);
+    encoder.boolean(0, false, true).unwrap();
+    encoder.uint64(1, false, 42).unwrap();
+    encoder.sint64(2, false, -42).unwrap();
+    encoder.string(3, false, "foo").unwrap();
+    encoder.string(8, false, "bar").unwrap();
+    let expected = encoder.finish();
+
+    let encoded = vf_serde::to_vec(&example()).unwrap();
+    assert_eq!(&encoded[..expected.len()], expected);
+}
+
+#[test]
+fn digest_computed_while_deserializing() {
+    let encoded = vf_serde::to_vec(&example()).unwrap();
+
+    let mut decoder = Decoder::new();
+    let _: Example<'_> = vf_serde::from_slice_with_decoder(&mut decoder, &encoded).unwrap();
+
+    let mut digest = Sha256Digest::default();
+    decoder.fill_digest(&mut digest).unwrap();
+    assert_eq!(digest, expected_digest(&encoded));
+}
+
+#[test]
+fn unknown_fields_ignored() {
+    let encoded = vf_serde::to_vec(&example()).unwrap();
+
+    #[derive(Deserialize)]
+    struct Partial {
+        #[serde(rename = "1")]
+        count: u64,
+    }
+
+    let partial: Partial = vf_serde::from_slice(&encoded).unwrap();
+    assert_eq!(partial.count, 42);
+}
+
+#[test]
+fn floats_unsupported() {
+    #[derive(Serialize)]
+    struct Float {
+        value: f64,
+    }
+
+    assert!(vf_serde::to_vec(&Float { value: 1.0 }).is_err());
+}
+
+#[test]
+fn unknown_critical_fields_rejected() {
+    let mut buffer = [0u8; 64];
+    let mut encoder = Encoder::new(&mut buffer);
+    encoder.uint64(1, false, 42).unwrap();
+    encoder.uint64(5, true, 7).unwrap();
+    let encoded = encoder.finish();
+
+    #[derive(Debug, Deserialize)]
+    struct Partial {
+        #[serde(rename = "1")]
+        _count: u64,
+    }
+
+    let err = vf_serde::from_slice::<Partial>(encoded).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        error::Kind::FieldHeader {
+            tag: Some(5),
+            wire_type: Some(WireType::UInt64)
+        }
+    );
+    assert_eq!(err.position(), Some(2));
+    assert_eq!(err.path().unwrap().to_string(), "5");
+}
+
+#[test]
+fn deserialize_errors_located() {
+    let encoded = vf_serde::to_vec(&example()).unwrap();
+
+    #[derive(Debug, Deserialize)]
+    struct Id {
+        #[serde(rename = "1")]
+        _id: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Partial {
+        #[serde(rename = "10")]
+        _inner: Id,
+    }
+
+    let err = vf_serde::from_slice::<Partial>(&encoded).unwrap_err();
+    assert_eq!(err.kind(), error::Kind::Serde);
+    assert_eq!(err.path().unwrap().to_string(), "10");
+    assert!(err.position().is_some());
+}
+
+#[test]
+fn serialize_errors_located() {
+    #[derive(Serialize)]
+    struct Float {
+        value: f64,
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        floats: Vec<Float>,
+    }
+
+    let outer = Outer {
+        floats: vec![Float { value: 1.0 }],
+    };
+
+    let err = vf_serde::to_vec(&outer).unwrap_err();
+    assert_eq!(err.kind(), error::Kind::Serde);
+    assert_eq!(err.path().unwrap().to_string(), "0.sequence[0].0");
+}
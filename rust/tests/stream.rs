@@ -0,0 +1,129 @@
+//! Integration tests for asynchronous streaming decoding
+
+#![cfg(all(feature = "async", feature = "sha2"))]
+
+use veriform::{
+    decoder::stream::{Decoder as StreamDecoder, Event, Reader},
+    error::Kind,
+    field::WireType,
+    value::{Object, Value},
+    Decoder, Message, Sha256Digest, VerihashVersion,
+};
+
+/// Size of the `bytes` field in the example message
+const BLOB_SIZE: usize = 100_000;
+
+/// Size of the reader's buffer
+const BUFFER_SIZE: usize = 1024;
+
+/// Build an example message containing a large `bytes` field
+fn example_message() -> Object {
+    let blob: Vec<u8> = (0..BLOB_SIZE).map(|n| n as u8).collect();
+
+    let mut inner = Object::new();
+    inner.insert(0, false, Value::UInt64(42)).unwrap();
+    inner.insert(1, false, Value::Bytes(blob)).unwrap();
+
+    let mut object = Object::new();
+    object
+        .insert(0, false, Value::String("example".into()))
+        .unwrap();
+    object.insert(1, true, Value::Message(inner)).unwrap();
+    object.insert(2, false, Value::SInt64(-1)).unwrap();
+    object
+}
+
+/// Create a reader for the given input
+fn reader(input: &[u8], version: VerihashVersion) -> Reader<&[u8], sha2::Sha256> {
+    let decoder = StreamDecoder::with_verihash_version(version);
+    Reader::with_capacity(input, decoder, BUFFER_SIZE)
+}
+
+#[tokio::test]
+async fn read_events() {
+    let encoded = example_message().encode_vec().unwrap();
+    let mut reader = reader(&encoded, VerihashVersion::V0);
+
+    let mut blob = Vec::new();
+    let mut events = Vec::new();
+
+    while let Some(event) = reader.next_event().await.unwrap() {
+        match event {
+            Event::ValueChunk {
+                wire_type: WireType::Bytes,
+                bytes,
+                remaining,
+            } => {
+                assert!(bytes.len() <= BUFFER_SIZE);
+                blob.extend_from_slice(bytes);
+                assert_eq!(remaining, BLOB_SIZE - blob.len());
+            }
+            Event::ValueChunk { bytes, .. } => assert_eq!(bytes, b"example"),
+            other => events.push(format!("{:?}", other)),
+        }
+    }
+
+    assert_eq!(blob.len(), BLOB_SIZE);
+    assert!(blob.iter().enumerate().all(|(n, &b)| b == n as u8));
+
+    assert_eq!(
+        events,
+        [
+            "FieldHeader(Header { tag: 0, critical: false, wire_type: String })",
+            "ValueStart { wire_type: String, length: 7 }",
+            "FieldHeader(Header { tag: 1, critical: true, wire_type: Message })",
+            &format!("MessageStart {{ length: {} }}", encoded.len() - 15),
+            "FieldHeader(Header { tag: 0, critical: false, wire_type: UInt64 })",
+            "UInt64(42)",
+            "FieldHeader(Header { tag: 1, critical: false, wire_type: Bytes })",
+            &format!("ValueStart {{ wire_type: Bytes, length: {} }}", BLOB_SIZE),
+            "MessageEnd",
+            "FieldHeader(Header { tag: 2, critical: false, wire_type: SInt64 })",
+            "SInt64(-1)",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn digest_matches_decoder() {
+    let message = example_message();
+    let encoded = message.encode_vec().unwrap();
+
+    for &version in &[VerihashVersion::V0, VerihashVersion::V1] {
+        let mut reader = reader(&encoded, version);
+        while reader.next_event().await.unwrap().is_some() {}
+
+        let mut actual = Sha256Digest::default();
+        reader.fill_digest(&mut actual).unwrap();
+
+        let mut decoder = Decoder::with_verihash_version(version);
+        Object::decode(&mut decoder, &encoded).unwrap();
+
+        let mut expected = Sha256Digest::default();
+        decoder.fill_digest(&mut expected).unwrap();
+
+        assert_eq!(actual, expected, "{:?}", version);
+        assert_eq!(
+            &actual[..],
+            &message.digest_with_version::<sha2::Sha256>(version)[..]
+        );
+    }
+}
+
+#[tokio::test]
+async fn truncated() {
+    let encoded = example_message().encode_vec().unwrap();
+    let truncated = &encoded[..encoded.len() - 10];
+    let mut reader = reader(truncated, VerihashVersion::V0);
+
+    let err = loop {
+        match reader.next_event().await {
+            Ok(Some(_)) => continue,
+            Ok(None) => panic!("expected error"),
+            Err(err) => break err,
+        }
+    };
+
+    assert!(matches!(err.kind(), Kind::Truncated { .. }));
+    assert_eq!(err.position(), Some(truncated.len()));
+}
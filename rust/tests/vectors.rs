@@ -0,0 +1,381 @@
+//! Conformance tests using the test vectors shared across implementations
+//! (located in the toplevel `vectors/` directory of this repository).
+//!
+//! Every `*.tjson` file in that directory is checked according to the kind
+//! of vectors it contains, given by its toplevel `kind:s` member, so new
+//! files (and new examples in existing files) are picked up automatically.
+
+#![cfg(feature = "tjson")]
+
+use serde_json::{Map, Value as JsonValue};
+use std::{
+    convert::TryInto,
+    fs,
+    path::{Path, PathBuf},
+};
+use veriform::{
+    builtins::{Float32, Float64},
+    decoder::{stream::Decoder as StreamDecoder, Config as DecoderConfig},
+    tjson,
+    value::{Object, Value},
+    Decoder, Error, Message, Sha256Digest, VerihashVersion,
+};
+
+/// Kinds of vector files this harness knows how to check
+const KINDS: &[&str] = &["floats", "messages", "varint", "verihash"];
+
+/// Path to the shared test vectors
+fn vectors_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../vectors")
+}
+
+/// Example from a vector file
+struct Example {
+    /// Name of the file containing this example
+    file: String,
+
+    /// Index of this example within the file
+    index: usize,
+
+    /// TJSON members of the example, keyed by name (sans type signature)
+    members: Map<String, JsonValue>,
+
+    /// Type signatures of the members, keyed by name
+    types: Map<String, JsonValue>,
+}
+
+impl Example {
+    /// Get a description of this example for use in failure messages
+    fn describe(&self) -> String {
+        match self.members.get("name").and_then(JsonValue::as_str) {
+            Some(name) => format!("{} #{} ({})", self.file, self.index, name),
+            None => format!("{} #{}", self.file, self.index),
+        }
+    }
+
+    /// Get the type signature of the given member (if present)
+    fn type_of(&self, name: &str) -> Option<&str> {
+        self.types.get(name).and_then(JsonValue::as_str)
+    }
+
+    /// Get the value of the given member (if present)
+    fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.members.get(name)
+    }
+
+    /// Does this example describe a successful case?
+    fn success(&self) -> bool {
+        self.get("success")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or_else(|| panic!("{}: missing `success:b`", self.describe()))
+    }
+
+    /// Get a `d16` member as bytes
+    fn bytes(&self, name: &str) -> Vec<u8> {
+        assert_eq!(self.type_of(name), Some("d16"), "{}", self.describe());
+        let hex = self.get(name).and_then(JsonValue::as_str).unwrap();
+        subtle_encoding::hex::decode(hex).unwrap()
+    }
+
+    /// Get a `u` member as an integer
+    fn uint64(&self, name: &str) -> u64 {
+        assert_eq!(self.type_of(name), Some("u"), "{}", self.describe());
+        self.get(name)
+            .and_then(JsonValue::as_str)
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    /// Get the decoder configuration for this example, i.e. the default
+    /// configuration with any limits given by `max_*:u` members applied
+    fn config(&self) -> DecoderConfig {
+        let limit = |name: &str| {
+            self.get(name)
+                .map(|_| self.uint64(name) as usize)
+                .unwrap_or(usize::MAX)
+        };
+
+        DecoderConfig::default()
+            .max_message_length(limit("max_message_length"))
+            .max_bytes_length(limit("max_bytes_length"))
+            .max_string_length(limit("max_string_length"))
+            .max_sequence_elements(limit("max_sequence_elements"))
+            .max_fields(limit("max_fields"))
+    }
+
+    /// Does this example set any limits in its decoder configuration?
+    fn has_limits(&self) -> bool {
+        self.config() != DecoderConfig::default()
+    }
+
+    /// Parse a member of any type as a schema-less value
+    fn value(&self, name: &str) -> Value {
+        let ty = self.type_of(name).unwrap();
+        let json = serde_json::to_string(self.get(name).unwrap()).unwrap();
+        let object: Object = tjson::from_str(&format!(r#"{{"0:{}":{}}}"#, ty, json))
+            .unwrap_or_else(|e| panic!("{}: {}", self.describe(), e));
+
+        object.get(0).unwrap().value.clone()
+    }
+
+    /// Get an `O` member as TJSON
+    fn object_tjson(&self, name: &str) -> String {
+        assert_eq!(self.type_of(name), Some("O"), "{}", self.describe());
+        serde_json::to_string(self.get(name).unwrap()).unwrap()
+    }
+}
+
+/// Paths of every vector file in the vectors directory, along with the
+/// kind of vectors each contains
+fn vector_files() -> Vec<(PathBuf, String)> {
+    let mut files = vec![];
+
+    for entry in fs::read_dir(vectors_dir()).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().and_then(|ext| ext.to_str()) != Some("tjson") {
+            continue;
+        }
+
+        let kind = load_root(&path)
+            .get("kind:s")
+            .and_then(JsonValue::as_str)
+            .unwrap_or_else(|| panic!("{}: missing `kind:s`", path.display()))
+            .to_owned();
+
+        files.push((path, kind));
+    }
+
+    files.sort();
+    files
+}
+
+/// Load the toplevel object of the given vector file
+fn load_root(path: &Path) -> JsonValue {
+    let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// Load the examples from every vector file of the given kind
+fn load_examples(kind: &str) -> Vec<Example> {
+    let examples: Vec<Example> = vector_files()
+        .iter()
+        .filter(|(_, file_kind)| file_kind == kind)
+        .flat_map(|(path, _)| load_file(path))
+        .collect();
+
+    assert!(!examples.is_empty(), "no `{}` vectors", kind);
+    examples
+}
+
+/// Load the examples from the given vector file
+fn load_file(path: &Path) -> Vec<Example> {
+    let file = path.file_name().unwrap().to_string_lossy().into_owned();
+
+    load_root(path)
+        .get("examples:A<O>")
+        .and_then(JsonValue::as_array)
+        .unwrap_or_else(|| panic!("{}: missing `examples:A<O>`", path.display()))
+        .iter()
+        .enumerate()
+        .map(|(index, example)| {
+            let mut members = Map::new();
+            let mut types = Map::new();
+
+            for (key, value) in example.as_object().unwrap() {
+                let pos = key.rfind(':').unwrap();
+                members.insert(key[..pos].to_owned(), value.clone());
+                types.insert(key[..pos].to_owned(), key[pos + 1..].into());
+            }
+
+            Example {
+                file: file.clone(),
+                index,
+                members,
+                types,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn vector_files_have_known_kinds() {
+    for (path, kind) in vector_files() {
+        assert!(
+            KINDS.contains(&kind.as_str()),
+            "{}: unknown kind of test vectors: {}",
+            path.display(),
+            kind
+        );
+    }
+}
+
+#[test]
+fn floats() {
+    for example in load_examples("floats") {
+        let encoded = example.bytes("encoded");
+        let ty = example.get("type").and_then(JsonValue::as_str);
+
+        // Bits of the decoded number in big endian byte order
+        let result = match ty {
+            Some("f32") => Float32::decode(&mut Decoder::new(), &encoded)
+                .map(|float| float.to_bits().to_be_bytes().to_vec()),
+            Some("f64") => Float64::decode(&mut Decoder::new(), &encoded)
+                .map(|float| float.to_bits().to_be_bytes().to_vec()),
+            other => panic!("{}: unknown type: {:?}", example.describe(), other),
+        };
+
+        if !example.success() {
+            assert!(result.is_err(), "{}: decoded", example.describe());
+            continue;
+        }
+
+        let output = result.unwrap_or_else(|e| panic!("{}: {}", example.describe(), e));
+        assert_eq!(output, example.bytes("output"), "{}", example.describe());
+
+        // Encoding the input should canonicalize it
+        let input = example.bytes("input");
+        let reencoded = match ty {
+            Some("f32") => Float32::from_bits(u32::from_be_bytes(input[..].try_into().unwrap()))
+                .encode_vec()
+                .unwrap(),
+            _ => Float64::from_bits(u64::from_be_bytes(input[..].try_into().unwrap()))
+                .encode_vec()
+                .unwrap(),
+        };
+
+        assert_eq!(reencoded, encoded, "{}", example.describe());
+    }
+}
+
+#[test]
+fn messages() {
+    for example in load_examples("messages") {
+        let encoded = example.bytes("encoded");
+        let config = example.config();
+        let result = Object::decode(&mut Decoder::with_config(config), &encoded);
+
+        if !example.success() {
+            assert!(result.is_err(), "{}: decoded", example.describe());
+            assert!(
+                stream_digest(&encoded, config).is_err(),
+                "{}: stream decoded",
+                example.describe()
+            );
+
+            // Examples which set limits must only be rejected because of them
+            if example.has_limits() {
+                let decoder = &mut Decoder::new();
+                let result = Object::decode(decoder, &encoded);
+                assert!(
+                    result.is_ok(),
+                    "{}: decoded without limits",
+                    example.describe()
+                );
+            }
+
+            continue;
+        }
+
+        let decoded = result.unwrap_or_else(|e| panic!("{}: {}", example.describe(), e));
+        let tjson = example.object_tjson("decoded");
+        let expected: Object = tjson::from_str(&tjson).unwrap();
+        assert_eq!(decoded, expected, "{}", example.describe());
+
+        // Encoding the decoded message should produce the original encoding
+        assert_eq!(
+            expected.encode_vec().unwrap(),
+            encoded,
+            "{}",
+            example.describe()
+        );
+
+        // The digest computed while decoding should match the one computed
+        // from the TJSON representation for every version of Verihash
+        for &version in &[VerihashVersion::V0, VerihashVersion::V1] {
+            let config = config.verihash_version(version);
+
+            let mut decoder = Decoder::with_config(config);
+            Object::decode(&mut decoder, &encoded).unwrap();
+
+            let mut digest = Sha256Digest::default();
+            decoder.fill_digest(&mut digest).unwrap();
+            assert_eq!(
+                &digest[..],
+                &tjson::digest_with_version::<sha2::Sha256>(&tjson, version).unwrap()[..],
+                "{} ({:?})",
+                example.describe(),
+                version
+            );
+
+            // The streaming decoder should compute the same digest when fed
+            // the message a byte at a time
+            assert_eq!(
+                stream_digest(&encoded, config).unwrap(),
+                digest,
+                "{} ({:?}, streaming)",
+                example.describe(),
+                version
+            );
+        }
+    }
+}
+
+/// Compute the digest of a message using the streaming decoder, feeding it
+/// the encoded message one byte at a time
+fn stream_digest(encoded: &[u8], config: DecoderConfig) -> Result<Sha256Digest, Error> {
+    let mut decoder = StreamDecoder::<sha2::Sha256>::with_config(config);
+
+    for byte in encoded {
+        let mut input = std::slice::from_ref(byte);
+        while decoder.decode(&mut input)?.is_some() {}
+    }
+
+    while decoder.decode(&mut &[][..])?.is_some() {}
+
+    let mut digest = Sha256Digest::default();
+    decoder.fill_digest(&mut digest)?;
+    Ok(digest)
+}
+
+#[test]
+fn varint() {
+    for example in load_examples("varint") {
+        let encoded = example.bytes("encoded");
+        let mut input = &encoded[..];
+        let result = vint64::decode(&mut input);
+
+        if !example.success() {
+            assert!(result.is_err(), "{}: decoded", example.describe());
+            continue;
+        }
+
+        let value = example.uint64("value");
+        assert_eq!(result.ok(), Some(value), "{}", example.describe());
+        assert!(input.is_empty(), "{}", example.describe());
+        assert_eq!(
+            vint64::encode(value).as_ref(),
+            &encoded[..],
+            "{}",
+            example.describe()
+        );
+    }
+}
+
+#[test]
+fn verihash() {
+    for example in load_examples("verihash") {
+        assert_eq!(
+            example.get("algorithm").and_then(JsonValue::as_str),
+            Some("SHA256"),
+            "{}",
+            example.describe()
+        );
+
+        let expected = example.bytes("digest");
+        let value = example.value("value");
+        let actual = value.tagged_digest::<sha2::Sha256>();
+        assert_eq!(&actual[..], &expected[..], "{}", example.describe());
+    }
+}
@@ -1,6 +1,6 @@
 //! vint64 benchmark (using criterion)
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 use criterion_cycles_per_byte::CyclesPerByte;
 
 const EXAMPLE_VALUES: [u64; 8] = [
@@ -14,6 +14,9 @@ const EXAMPLE_VALUES: [u64; 8] = [
     core::u64::MAX,
 ];
 
+/// Number of values to encode/decode in the slice benchmarks
+const SLICE_LEN: usize = 1024;
+
 fn bench(c: &mut Criterion<CyclesPerByte>) {
     let mut group = c.benchmark_group("vint64");
 
@@ -49,10 +52,75 @@ fn bench(c: &mut Criterion<CyclesPerByte>) {
     group.finish();
 }
 
+fn bench_slice(c: &mut Criterion<CyclesPerByte>) {
+    let mut group = c.benchmark_group("vint64 slice");
+
+    // Mostly small values (typical of telemetry) with occasional large ones
+    let values = (0..SLICE_LEN as u64)
+        .map(|n| {
+            EXAMPLE_VALUES[if n % 64 == 0 {
+                (n / 64) as usize & 0x07
+            } else {
+                1
+            }]
+        })
+        .collect::<Vec<_>>();
+
+    let mut encoded = vec![0u8; SLICE_LEN * vint64::MAX_BYTES];
+    let encoded_len = vint64::encode_slice(&values, &mut encoded).unwrap();
+    encoded.truncate(encoded_len);
+
+    group.throughput(Throughput::Bytes(encoded_len as u64));
+
+    group.bench_function("encode (one at a time)", |b| {
+        let mut output = vec![0u8; SLICE_LEN * vint64::MAX_BYTES];
+
+        b.iter(|| {
+            let mut pos = 0;
+
+            for &value in &values {
+                let encoded = vint64::encode(value);
+                output[pos..pos + encoded.as_ref().len()].copy_from_slice(encoded.as_ref());
+                pos += encoded.as_ref().len();
+            }
+
+            pos
+        });
+    });
+
+    group.bench_function("encode_slice", |b| {
+        let mut output = vec![0u8; SLICE_LEN * vint64::MAX_BYTES];
+        b.iter(|| vint64::encode_slice(&values, &mut output).unwrap());
+    });
+
+    group.bench_function("decode (one at a time)", |b| {
+        let mut output = vec![0u64; SLICE_LEN];
+
+        b.iter(|| {
+            let mut input = &encoded[..];
+
+            for value in output.iter_mut() {
+                *value = vint64::decode(&mut input).unwrap();
+            }
+        });
+    });
+
+    group.bench_function("decode_slice", |b| {
+        let mut output = vec![0u64; SLICE_LEN];
+
+        b.iter(|| {
+            let mut input = &encoded[..];
+            vint64::decode_slice(&mut input, &mut output).unwrap()
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default().with_measurement(CyclesPerByte);
-    targets = bench
+    targets = bench, bench_slice
 );
 
 criterion_main!(benches);
@@ -0,0 +1,388 @@
+//! Bulk encoding and decoding of slices of integers.
+//!
+//! Because the length of a `vint64` is determined entirely by its first
+//! byte, runs of values can be processed many at a time. The most common
+//! case in practice is small integers which encode as a single byte, so the
+//! fast paths in this module process runs of single-byte values in bulk and
+//! fall back to decoding (or encoding) one value at a time otherwise.
+//!
+//! The following implementations of the fast paths are available, and the
+//! best one supported by the current CPU is selected at runtime:
+//!
+//! - portable: 8 values at a time using 64-bit integer arithmetic ("SWAR")
+//! - SSE2 (x86/x86_64): 16 values at a time
+//! - AVX2 (x86/x86_64): 32 values at a time (decoding) or 16 (encoding)
+//! - NEON (aarch64): 16 values at a time (requires the `neon` feature)
+//!
+//! Runtime CPU feature detection on x86 requires the `std` feature. Without
+//! it, the implementation is selected at compile time based on the enabled
+//! target features.
+
+#[cfg(all(feature = "neon", target_arch = "aarch64"))]
+mod neon;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+use crate::{decode, encode, Error};
+use core::convert::TryInto;
+
+/// Mask of the lowest bit of each byte in a 64-bit word
+const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+
+/// Encode a slice of unsigned integers as `vint64`s into the given output
+/// buffer, returning the number of bytes written.
+///
+/// An output buffer of `values.len() * MAX_BYTES` is always sufficient.
+/// If the buffer is too small [`Error::Truncated`] is returned, in which
+/// case the contents of the buffer are unspecified.
+pub fn encode_slice(values: &[u64], output: &mut [u8]) -> Result<usize, Error> {
+    let backend = Backend::detect();
+    let mut values_pos = 0;
+    let mut output_pos = 0;
+
+    while values_pos < values.len() {
+        let n = backend.encode_run(&values[values_pos..], &mut output[output_pos..]);
+        values_pos += n;
+        output_pos += n;
+
+        if let Some(&value) = values.get(values_pos) {
+            let encoded = encode(value);
+            let bytes = encoded.as_ref();
+
+            output
+                .get_mut(output_pos..output_pos + bytes.len())
+                .ok_or(Error::Truncated)?
+                .copy_from_slice(bytes);
+
+            values_pos += 1;
+            output_pos += bytes.len();
+        }
+    }
+
+    Ok(output_pos)
+}
+
+/// Decode a run of `vint64`s from the input into the given output slice,
+/// returning the number of values decoded.
+///
+/// Decoding stops when either the output slice is full or the input is
+/// exhausted. Upon success, the input reference is updated to begin at the
+/// byte immediately after the last decoded value. Upon failure, it begins
+/// at the value which failed to decode.
+pub fn decode_slice(input: &mut &[u8], output: &mut [u64]) -> Result<usize, Error> {
+    let backend = Backend::detect();
+    let mut pos = 0;
+
+    while pos < output.len() && !input.is_empty() {
+        if input[0] & 1 == 1 {
+            pos += backend.decode_run(input, &mut output[pos..]);
+        }
+
+        if pos < output.len() && !input.is_empty() {
+            output[pos] = decode(input)?;
+            pos += 1;
+        }
+    }
+
+    Ok(pos)
+}
+
+/// Implementations of the bulk encoding/decoding fast paths
+// Not every implementation is selectable when using compile-time detection
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Backend {
+    /// Portable implementation
+    Portable,
+
+    /// SSE2 implementation
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Sse2,
+
+    /// AVX2 implementation
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Avx2,
+
+    /// NEON implementation
+    #[cfg(all(feature = "neon", target_arch = "aarch64"))]
+    Neon,
+}
+
+impl Backend {
+    /// Select the best implementation supported by the current CPU
+    #[allow(unreachable_code)]
+    pub fn detect() -> Self {
+        #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+        {
+            if std::is_x86_feature_detected!("avx2") {
+                return Backend::Avx2;
+            }
+
+            if std::is_x86_feature_detected!("sse2") {
+                return Backend::Sse2;
+            }
+        }
+
+        #[cfg(all(
+            not(feature = "std"),
+            any(target_arch = "x86", target_arch = "x86_64"),
+            target_feature = "avx2"
+        ))]
+        return Backend::Avx2;
+
+        #[cfg(all(
+            not(feature = "std"),
+            any(target_arch = "x86", target_arch = "x86_64"),
+            target_feature = "sse2"
+        ))]
+        return Backend::Sse2;
+
+        // NEON is part of the baseline aarch64 instruction set
+        #[cfg(all(feature = "neon", target_arch = "aarch64"))]
+        return Backend::Neon;
+
+        Backend::Portable
+    }
+
+    /// Get all of the implementations supported by the current CPU
+    #[cfg(test)]
+    pub fn supported() -> std::vec::Vec<Backend> {
+        #[allow(unused_mut)]
+        let mut backends = std::vec![Backend::Portable];
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if std::is_x86_feature_detected!("sse2") {
+                backends.push(Backend::Sse2);
+            }
+
+            if std::is_x86_feature_detected!("avx2") {
+                backends.push(Backend::Avx2);
+            }
+        }
+
+        #[cfg(all(feature = "neon", target_arch = "aarch64"))]
+        backends.push(Backend::Neon);
+
+        backends
+    }
+
+    /// Encode a run of values which fit in a single byte, returning the
+    /// number of values (and bytes) written
+    #[allow(unsafe_code)]
+    fn encode_run(self, values: &[u64], output: &mut [u8]) -> usize {
+        // SAFETY: backends are only selected when supported by the CPU
+        let n = match self {
+            Backend::Portable => 0,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse2 => unsafe { x86::encode_run_sse2(values, output) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx2 => unsafe { x86::encode_run_avx2(values, output) },
+            #[cfg(all(feature = "neon", target_arch = "aarch64"))]
+            Backend::Neon => unsafe { neon::encode_run(values, output) },
+        };
+
+        n + encode_run_portable(&values[n..], &mut output[n..])
+    }
+
+    /// Decode a run of single-byte values, returning the number of values
+    /// (and bytes) read
+    #[allow(unsafe_code)]
+    fn decode_run(self, input: &mut &[u8], output: &mut [u64]) -> usize {
+        // SAFETY: backends are only selected when supported by the CPU
+        let n = match self {
+            Backend::Portable => 0,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse2 => unsafe { x86::decode_run_sse2(input, output) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx2 => unsafe { x86::decode_run_avx2(input, output) },
+            #[cfg(all(feature = "neon", target_arch = "aarch64"))]
+            Backend::Neon => unsafe { neon::decode_run(input, output) },
+        };
+
+        n + decode_run_portable(input, &mut output[n..])
+    }
+}
+
+/// Encode a run of single-byte values 8 at a time
+fn encode_run_portable(values: &[u64], output: &mut [u8]) -> usize {
+    let mut n = 0;
+
+    for (chunk, out) in values.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+        if chunk.iter().fold(0, |acc, value| acc | value) >= 0x80 {
+            break;
+        }
+
+        for (byte, value) in out.iter_mut().zip(chunk) {
+            *byte = (*value << 1 | 1) as u8;
+        }
+
+        n += 8;
+    }
+
+    n
+}
+
+/// Decode a run of single-byte values 8 at a time
+fn decode_run_portable(input: &mut &[u8], output: &mut [u64]) -> usize {
+    let bytes = *input;
+    let mut n = 0;
+
+    for (chunk, out) in bytes.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+
+        if word & LOW_BITS != LOW_BITS {
+            break;
+        }
+
+        for (i, value) in out.iter_mut().enumerate() {
+            *value = (word >> (i * 8 + 1)) & 0x7f;
+        }
+
+        n += 8;
+    }
+
+    *input = &bytes[n..];
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_slice, encode_slice, Backend};
+    use crate::{encode, MAX_BYTES};
+    use proptest::{collection::vec, num::u64::ANY, prelude::*};
+    use std::{vec, vec::Vec};
+
+    /// Encode values one at a time
+    fn encode_each(values: &[u64]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|&value| encode(value).as_ref().to_vec())
+            .collect()
+    }
+
+    /// Values which encode as a single byte, with some larger values mixed in
+    fn mostly_small() -> impl Strategy<Value = u64> {
+        prop_oneof![8 => 0..0x80u64, 1 => ANY]
+    }
+
+    #[test]
+    fn backends_agree() {
+        // Runs of single-byte values interspersed with larger values
+        let values = (0..1000u64)
+            .map(|n| if n % 97 == 0 { n << 20 } else { n % 0x80 })
+            .collect::<Vec<_>>();
+        let expected = encode_each(&values);
+
+        for backend in Backend::supported() {
+            let mut output = vec![0u8; values.len() * MAX_BYTES];
+            let mut pos = 0;
+            let mut i = 0;
+
+            while i < values.len() {
+                let n = backend.encode_run(&values[i..], &mut output[pos..]);
+                i += n;
+                pos += n;
+
+                if i < values.len() {
+                    let encoded = encode(values[i]);
+                    output[pos..pos + encoded.as_ref().len()].copy_from_slice(encoded.as_ref());
+                    pos += encoded.as_ref().len();
+                    i += 1;
+                }
+            }
+
+            assert_eq!(&output[..pos], &expected[..], "{:?}", backend);
+
+            let mut input = &expected[..];
+            let mut decoded = vec![0u64; values.len()];
+            let mut n = 0;
+
+            while !input.is_empty() {
+                n += backend.decode_run(&mut input, &mut decoded[n..]);
+
+                if !input.is_empty() {
+                    decoded[n] = crate::decode(&mut input).unwrap();
+                    n += 1;
+                }
+            }
+
+            assert_eq!(decoded, values, "{:?}", backend);
+        }
+    }
+
+    #[test]
+    fn single_byte_runs() {
+        let values = (0..100u64).map(|n| n % 0x80).collect::<Vec<_>>();
+        let encoded = encode_each(&values);
+
+        for backend in Backend::supported() {
+            let mut output = [0u8; 100];
+            assert_eq!(
+                backend.encode_run(&values, &mut output),
+                96,
+                "{:?}",
+                backend
+            );
+            assert_eq!(&output[..96], &encoded[..96], "{:?}", backend);
+
+            let mut input = &encoded[..];
+            let mut decoded = [0u64; 100];
+            assert_eq!(
+                backend.decode_run(&mut input, &mut decoded),
+                96,
+                "{:?}",
+                backend
+            );
+            assert_eq!(&decoded[..96], &values[..96], "{:?}", backend);
+            assert_eq!(input.len(), 4);
+        }
+    }
+
+    #[test]
+    fn encode_truncated() {
+        let mut output = [0u8; 16];
+        assert!(encode_slice(&[1; 17], &mut output).is_err());
+        assert!(encode_slice(&[u64::MAX; 2], &mut output).is_err());
+    }
+
+    #[test]
+    fn decode_into_short_output() {
+        let encoded = encode_each(&[1; 40]);
+        let mut input = &encoded[..];
+        let mut output = [0u64; 33];
+        assert_eq!(decode_slice(&mut input, &mut output).unwrap(), 33);
+        assert_eq!(input.len(), 7);
+    }
+
+    #[test]
+    fn decode_error_position() {
+        let mut encoded = encode_each(&[1; 20]);
+        encoded.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]);
+        let mut input = &encoded[..];
+        let mut output = [0u64; 21];
+        assert!(decode_slice(&mut input, &mut output).is_err());
+        assert_eq!(input, &[0x08, 0x00, 0x00, 0x00]);
+    }
+
+    proptest! {
+        /// Ensure bulk encoding matches encoding one value at a time
+        #[test]
+        fn encode_matches_scalar(values in vec(mostly_small(), 0..200)) {
+            let mut output = vec![0u8; values.len() * MAX_BYTES];
+            let len = encode_slice(&values, &mut output).unwrap();
+            assert_eq!(&output[..len], &encode_each(&values)[..]);
+        }
+
+        /// Ensure slices of `vint64` values successfully round-trip
+        #[test]
+        fn roundtrip(values in vec(mostly_small(), 0..200)) {
+            let encoded = encode_each(&values);
+            let mut input = &encoded[..];
+            let mut decoded = vec![0u64; values.len()];
+            assert_eq!(decode_slice(&mut input, &mut decoded).unwrap(), values.len());
+            assert_eq!(decoded, values);
+            assert!(input.is_empty());
+        }
+    }
+}
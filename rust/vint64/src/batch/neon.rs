@@ -0,0 +1,82 @@
+//! NEON implementation of the bulk encoding/decoding fast paths.
+//!
+//! NEON is part of the baseline aarch64 instruction set, however the
+//! intrinsics used here require Rust 1.59+, hence they're gated on the
+//! `neon` feature.
+
+#![allow(unsafe_code)]
+
+use core::arch::aarch64::*;
+
+/// Encode a run of single-byte values 16 at a time
+#[target_feature(enable = "neon")]
+pub unsafe fn encode_run(values: &[u64], output: &mut [u8]) -> usize {
+    let mut n = 0;
+
+    while values.len() - n >= 16 && output.len() - n >= 16 {
+        let src = values.as_ptr().add(n);
+        let mut v = [vdupq_n_u64(0); 8];
+        let mut any = vdupq_n_u64(0);
+
+        for (i, lanes) in v.iter_mut().enumerate() {
+            *lanes = vld1q_u64(src.add(i * 2));
+            any = vorrq_u64(any, *lanes);
+        }
+
+        if vmaxvq_u32(vreinterpretq_u32_u64(any)) >= 0x80 {
+            break;
+        }
+
+        let mut dwords = [vdupq_n_u32(0); 4];
+
+        for (i, out) in dwords.iter_mut().enumerate() {
+            *out = vcombine_u32(vmovn_u64(v[i * 2]), vmovn_u64(v[i * 2 + 1]));
+        }
+
+        let words_lo = vcombine_u16(vmovn_u32(dwords[0]), vmovn_u32(dwords[1]));
+        let words_hi = vcombine_u16(vmovn_u32(dwords[2]), vmovn_u32(dwords[3]));
+        let bytes = vcombine_u8(vmovn_u16(words_lo), vmovn_u16(words_hi));
+        let encoded = vorrq_u8(vaddq_u8(bytes, bytes), vdupq_n_u8(1));
+
+        vst1q_u8(output.as_mut_ptr().add(n), encoded);
+        n += 16;
+    }
+
+    n
+}
+
+/// Decode a run of single-byte values 16 at a time
+#[target_feature(enable = "neon")]
+pub unsafe fn decode_run(input: &mut &[u8], output: &mut [u64]) -> usize {
+    let bytes = *input;
+    let mut n = 0;
+
+    while bytes.len() - n >= 16 && output.len() - n >= 16 {
+        let encoded = vld1q_u8(bytes.as_ptr().add(n));
+
+        // Every byte must have its lowest bit set
+        if vminvq_u8(vandq_u8(encoded, vdupq_n_u8(1))) == 0 {
+            break;
+        }
+
+        // Shift each byte right by 1 (negative shift amounts shift right)
+        let values = vshlq_u8(encoded, vdupq_n_s8(-1));
+        let dst = output.as_mut_ptr().add(n);
+        let words = [vmovl_u8(vget_low_u8(values)), vmovl_high_u8(values)];
+
+        for (i, &word) in words.iter().enumerate() {
+            let dwords = [vmovl_u16(vget_low_u16(word)), vmovl_high_u16(word)];
+
+            for (j, &dword) in dwords.iter().enumerate() {
+                let offset = i * 8 + j * 4;
+                vst1q_u64(dst.add(offset), vmovl_u32(vget_low_u32(dword)));
+                vst1q_u64(dst.add(offset + 2), vmovl_high_u32(dword));
+            }
+        }
+
+        n += 16;
+    }
+
+    *input = &bytes[n..];
+    n
+}
@@ -0,0 +1,185 @@
+//! SSE2 and AVX2 implementations of the bulk encoding/decoding fast paths.
+//!
+//! All functions in this module are `unsafe` as they require the
+//! corresponding CPU features to be available, which must be checked by the
+//! caller (see `Backend::detect`).
+
+#![allow(unsafe_code)]
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+/// Mask of the bits of a 64-bit integer which don't fit in a single-byte
+/// `vint64`
+const HIGH_BITS: i64 = !0x7f;
+
+/// Encode a run of single-byte values 16 at a time using SSE2
+#[target_feature(enable = "sse2")]
+pub unsafe fn encode_run_sse2(values: &[u64], output: &mut [u8]) -> usize {
+    let mut n = 0;
+
+    while values.len() - n >= 16 && output.len() - n >= 16 {
+        let src = values.as_ptr().add(n) as *const __m128i;
+        let mut v = [_mm_setzero_si128(); 8];
+        let mut any = _mm_setzero_si128();
+
+        for (i, lanes) in v.iter_mut().enumerate() {
+            *lanes = _mm_loadu_si128(src.add(i));
+            any = _mm_or_si128(any, *lanes);
+        }
+
+        let high = _mm_and_si128(any, _mm_set1_epi64x(HIGH_BITS));
+
+        if _mm_movemask_epi8(_mm_cmpeq_epi8(high, _mm_setzero_si128())) != 0xffff {
+            break;
+        }
+
+        // Gather the low 32-bits of each 64-bit value, then narrow to bytes
+        let mut dwords = [_mm_setzero_si128(); 4];
+
+        for (i, out) in dwords.iter_mut().enumerate() {
+            let a = _mm_shuffle_epi32(v[i * 2], 0b00_00_10_00);
+            let b = _mm_shuffle_epi32(v[i * 2 + 1], 0b00_00_10_00);
+            *out = _mm_unpacklo_epi64(a, b);
+        }
+
+        let words_lo = _mm_packs_epi32(dwords[0], dwords[1]);
+        let words_hi = _mm_packs_epi32(dwords[2], dwords[3]);
+        let bytes = _mm_packus_epi16(words_lo, words_hi);
+        let encoded = _mm_or_si128(_mm_add_epi8(bytes, bytes), _mm_set1_epi8(1));
+
+        _mm_storeu_si128(output.as_mut_ptr().add(n) as *mut __m128i, encoded);
+        n += 16;
+    }
+
+    n
+}
+
+/// Decode a run of single-byte values 16 at a time using SSE2
+#[target_feature(enable = "sse2")]
+pub unsafe fn decode_run_sse2(input: &mut &[u8], output: &mut [u64]) -> usize {
+    let bytes = *input;
+    let mut n = 0;
+
+    while bytes.len() - n >= 16 && output.len() - n >= 16 {
+        let encoded = _mm_loadu_si128(bytes.as_ptr().add(n) as *const __m128i);
+
+        // Every byte must have its lowest bit set
+        if _mm_movemask_epi8(_mm_slli_epi16(encoded, 7)) != 0xffff {
+            break;
+        }
+
+        let values = _mm_and_si128(_mm_srli_epi16(encoded, 1), _mm_set1_epi8(0x7f));
+        let zero = _mm_setzero_si128();
+        let dst = output.as_mut_ptr().add(n) as *mut __m128i;
+
+        let words = [
+            _mm_unpacklo_epi8(values, zero),
+            _mm_unpackhi_epi8(values, zero),
+        ];
+
+        for (i, &word) in words.iter().enumerate() {
+            let dwords = [
+                _mm_unpacklo_epi16(word, zero),
+                _mm_unpackhi_epi16(word, zero),
+            ];
+
+            for (j, &dword) in dwords.iter().enumerate() {
+                let offset = i * 4 + j * 2;
+                _mm_storeu_si128(dst.add(offset), _mm_unpacklo_epi32(dword, zero));
+                _mm_storeu_si128(dst.add(offset + 1), _mm_unpackhi_epi32(dword, zero));
+            }
+        }
+
+        n += 16;
+    }
+
+    *input = &bytes[n..];
+    n
+}
+
+/// Encode a run of single-byte values 16 at a time using AVX2
+#[target_feature(enable = "avx2")]
+pub unsafe fn encode_run_avx2(values: &[u64], output: &mut [u8]) -> usize {
+    let mut n = 0;
+
+    while values.len() - n >= 16 && output.len() - n >= 16 {
+        let src = values.as_ptr().add(n) as *const __m256i;
+        let mut v = [_mm256_setzero_si256(); 4];
+        let mut any = _mm256_setzero_si256();
+
+        for (i, lanes) in v.iter_mut().enumerate() {
+            *lanes = _mm256_loadu_si256(src.add(i));
+            any = _mm256_or_si256(any, *lanes);
+        }
+
+        let high = _mm256_and_si256(any, _mm256_set1_epi64x(HIGH_BITS));
+
+        if _mm256_testz_si256(high, high) == 0 {
+            break;
+        }
+
+        // Gather the low 32-bits of each 64-bit value, then narrow to bytes
+        let index = _mm256_setr_epi32(0, 2, 4, 6, 0, 2, 4, 6);
+        let mut dwords = [_mm_setzero_si128(); 4];
+
+        for (out, &lanes) in dwords.iter_mut().zip(v.iter()) {
+            *out = _mm256_castsi256_si128(_mm256_permutevar8x32_epi32(lanes, index));
+        }
+
+        let words_lo = _mm_packs_epi32(dwords[0], dwords[1]);
+        let words_hi = _mm_packs_epi32(dwords[2], dwords[3]);
+        let bytes = _mm_packus_epi16(words_lo, words_hi);
+        let encoded = _mm_or_si128(_mm_add_epi8(bytes, bytes), _mm_set1_epi8(1));
+
+        _mm_storeu_si128(output.as_mut_ptr().add(n) as *mut __m128i, encoded);
+        n += 16;
+    }
+
+    n
+}
+
+/// Decode a run of single-byte values 32 at a time using AVX2
+#[target_feature(enable = "avx2")]
+pub unsafe fn decode_run_avx2(input: &mut &[u8], output: &mut [u64]) -> usize {
+    let bytes = *input;
+    let mut n = 0;
+
+    while bytes.len() - n >= 32 && output.len() - n >= 32 {
+        let encoded = _mm256_loadu_si256(bytes.as_ptr().add(n) as *const __m256i);
+
+        // Every byte must have its lowest bit set
+        if _mm256_movemask_epi8(_mm256_slli_epi16(encoded, 7)) != -1 {
+            break;
+        }
+
+        let values = _mm256_and_si256(_mm256_srli_epi16(encoded, 1), _mm256_set1_epi8(0x7f));
+        let dst = output.as_mut_ptr().add(n) as *mut __m256i;
+
+        let halves = [
+            _mm256_castsi256_si128(values),
+            _mm256_extracti128_si256(values, 1),
+        ];
+
+        for (i, &half) in halves.iter().enumerate() {
+            // Zero-extend each group of 4 bytes into 4 64-bit integers
+            let groups = [
+                half,
+                _mm_srli_si128(half, 4),
+                _mm_srli_si128(half, 8),
+                _mm_srli_si128(half, 12),
+            ];
+
+            for (j, &group) in groups.iter().enumerate() {
+                _mm256_storeu_si256(dst.add(i * 4 + j), _mm256_cvtepu8_epi64(group));
+            }
+        }
+
+        n += 32;
+    }
+
+    *input = &bytes[n..];
+    n
+}
@@ -0,0 +1,246 @@
+//! Reading and writing `vint64`s from byte streams.
+//!
+//! When the `std` feature is enabled, the [`ReadVInt64`] and [`WriteVInt64`]
+//! extension traits add methods for reading and writing `vint64`s to all
+//! types which impl `std::io::Read` and `std::io::Write` respectively:
+//!
+//! ```
+//! # #[cfg(feature = "std")]
+//! # {
+//! use vint64::io::{ReadVInt64, WriteVInt64};
+//!
+//! let mut buffer = Vec::new();
+//! buffer.write_vint64(300).unwrap();
+//! buffer.write_signed_vint64(-42).unwrap();
+//!
+//! let mut reader = &buffer[..];
+//! assert_eq!(reader.read_vint64().unwrap(), 300);
+//! assert_eq!(reader.read_signed_vint64().unwrap(), -42);
+//! # }
+//! ```
+//!
+//! In `no_std` environments, the same methods are available on the
+//! [`Source`] and [`Sink`] traits, which can be implemented for any type
+//! capable of reading or writing bytes.
+//!
+//! Readers use [`decoded_len`] to read exactly as many bytes as the `vint64`
+//! occupies, leaving any subsequent data in the stream unread.
+
+use crate::{decode, decoded_len, encode, signed, Error, MAX_BYTES};
+
+#[cfg(feature = "std")]
+use std::io;
+
+/// Minimal source of bytes which `vint64`s can be read from.
+pub trait Source {
+    /// Error type: must be able to represent `vint64` decoding errors
+    type Error: From<Error>;
+
+    /// Read exactly enough bytes to fill the given buffer
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Read an unsigned `vint64`
+    fn read_vint64(&mut self) -> Result<u64, Self::Error> {
+        let mut bytes = [0u8; MAX_BYTES];
+        self.read_exact(&mut bytes[..1])?;
+
+        let length = decoded_len(bytes[0]);
+        self.read_exact(&mut bytes[1..length])?;
+
+        Ok(decode(&mut &bytes[..length])?)
+    }
+
+    /// Read a zigzag-encoded signed `vint64`
+    fn read_signed_vint64(&mut self) -> Result<i64, Self::Error> {
+        self.read_vint64().map(signed::zigzag::decode)
+    }
+}
+
+/// Minimal sink for bytes which `vint64`s can be written to.
+pub trait Sink {
+    /// Error type
+    type Error;
+
+    /// Write all of the given bytes
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Write an unsigned `vint64`, returning the number of bytes written
+    fn write_vint64(&mut self, value: u64) -> Result<usize, Self::Error> {
+        let encoded = encode(value);
+        self.write_all(encoded.as_ref())?;
+        Ok(encoded.as_ref().len())
+    }
+
+    /// Write a zigzag-encoded signed `vint64`, returning the number of bytes
+    /// written
+    fn write_signed_vint64(&mut self, value: i64) -> Result<usize, Self::Error> {
+        self.write_vint64(signed::zigzag::encode(value))
+    }
+}
+
+/// Extension trait for reading `vint64`s from `std::io::Read` types.
+#[cfg(feature = "std")]
+pub trait ReadVInt64: io::Read {
+    /// Read an unsigned `vint64`.
+    ///
+    /// Non-canonical encodings are reported as [`io::ErrorKind::InvalidData`].
+    fn read_vint64(&mut self) -> io::Result<u64> {
+        Source::read_vint64(&mut IoSource(self))
+    }
+
+    /// Read a zigzag-encoded signed `vint64`
+    fn read_signed_vint64(&mut self) -> io::Result<i64> {
+        Source::read_signed_vint64(&mut IoSource(self))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read + ?Sized> ReadVInt64 for R {}
+
+/// Extension trait for writing `vint64`s to `std::io::Write` types.
+#[cfg(feature = "std")]
+pub trait WriteVInt64: io::Write {
+    /// Write an unsigned `vint64`, returning the number of bytes written
+    fn write_vint64(&mut self, value: u64) -> io::Result<usize> {
+        Sink::write_vint64(&mut IoSink(self), value)
+    }
+
+    /// Write a zigzag-encoded signed `vint64`, returning the number of bytes
+    /// written
+    fn write_signed_vint64(&mut self, value: i64) -> io::Result<usize> {
+        Sink::write_signed_vint64(&mut IoSink(self), value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write + ?Sized> WriteVInt64 for W {}
+
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Adapter from `std::io::Read` to [`Source`]
+#[cfg(feature = "std")]
+struct IoSource<'a, R: io::Read + ?Sized>(&'a mut R);
+
+#[cfg(feature = "std")]
+impl<'a, R: io::Read + ?Sized> Source for IoSource<'a, R> {
+    type Error = io::Error;
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buf)
+    }
+}
+
+/// Adapter from `std::io::Write` to [`Sink`]
+#[cfg(feature = "std")]
+struct IoSink<'a, W: io::Write + ?Sized>(&'a mut W);
+
+#[cfg(feature = "std")]
+impl<'a, W: io::Write + ?Sized> Sink for IoSink<'a, W> {
+    type Error = io::Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sink, Source};
+    use crate::Error;
+    use std::vec::Vec;
+
+    /// Source which reads bytes one at a time from a slice
+    struct ByteSource<'a> {
+        bytes: &'a [u8],
+        reads: usize,
+    }
+
+    impl<'a> Source for ByteSource<'a> {
+        type Error = Error;
+
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+            if buf.len() > self.bytes.len() {
+                return Err(Error::Truncated);
+            }
+
+            let (bytes, rest) = self.bytes.split_at(buf.len());
+            buf.copy_from_slice(bytes);
+            self.bytes = rest;
+            self.reads += 1;
+            Ok(())
+        }
+    }
+
+    impl Sink for Vec<u8> {
+        type Error = Error;
+
+        fn write_all(&mut self, bytes: &[u8]) -> Result<(), Error> {
+            self.extend_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn source_reads_exact_length() {
+        let mut source = ByteSource {
+            bytes: &[0x08, 0x0f, 0xff, 0xf0, 0xde, 0xad],
+            reads: 0,
+        };
+
+        assert_eq!(source.read_vint64().unwrap(), 0x0f0f_f0f0);
+        assert_eq!(source.reads, 2);
+        assert_eq!(source.bytes, &[0xde, 0xad]);
+    }
+
+    #[test]
+    fn source_truncated() {
+        let mut source = ByteSource {
+            bytes: &[0x08, 0x0f],
+            reads: 0,
+        };
+
+        assert!(source.read_vint64().is_err());
+    }
+
+    #[test]
+    fn sink_round_trip() {
+        let mut sink = Vec::new();
+        assert_eq!(sink.write_vint64(0x0f0f).unwrap(), 2);
+        assert_eq!(sink.write_signed_vint64(-0x0f0f_f0f0).unwrap(), 5);
+
+        let mut source = ByteSource {
+            bytes: &sink,
+            reads: 0,
+        };
+
+        assert_eq!(source.read_vint64().unwrap(), 0x0f0f);
+        assert_eq!(source.read_signed_vint64().unwrap(), -0x0f0f_f0f0);
+        assert!(source.bytes.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn std_io_round_trip() {
+        use super::{ReadVInt64, WriteVInt64};
+        use std::io;
+
+        let mut writer = Vec::new();
+        assert_eq!(WriteVInt64::write_vint64(&mut writer, u64::MAX).unwrap(), 9);
+        WriteVInt64::write_signed_vint64(&mut writer, -42).unwrap();
+
+        let mut reader = &writer[..];
+        assert_eq!(reader.read_vint64().unwrap(), u64::MAX);
+        assert_eq!(reader.read_signed_vint64().unwrap(), -42);
+
+        let err = reader.read_vint64().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = (&[0x08, 0x00, 0x00, 0x00][..]).read_vint64().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -65,8 +65,27 @@
 //! // Decode with `vint64::decode_signed`.
 //! let signed = vint64::signed::encode(-42);
 //! assert_eq!(signed.as_ref(), &[0xa7]);
+//!
+//! // Encode and decode slices of integers in bulk
+//! let mut buffer = [0u8; 3 * vint64::MAX_BYTES];
+//! let len = vint64::encode_slice(&[1, 2, 300], &mut buffer).unwrap();
+//! assert_eq!(&buffer[..len], &[0x03, 0x05, 0xb2, 0x04]);
+//!
+//! let mut slice = &buffer[..len];
+//! let mut values = [0u64; 3];
+//! assert_eq!(vint64::decode_slice(&mut slice, &mut values).unwrap(), 3);
+//! assert_eq!(values, [1, 2, 300]);
 //! ```
 //!
+//! The [`io`] module provides traits for reading and writing `vint64`s from
+//! byte streams, including `std::io::Read` and `std::io::Write` when the
+//! `std` feature is enabled.
+//!
+//! Bulk encoding and decoding uses SIMD instructions where available (SSE2
+//! and AVX2 on x86/x86_64, and NEON on aarch64 with the `neon` feature).
+//! Enable the `std` feature to select the best x86 implementation at
+//! runtime rather than at compile time.
+//!
 //! [LEB128]: https://cr.yp.to/libtai/vint.html
 //! [Extensible Binary Meta Language]: https://en.wikipedia.org/wiki/Extensible_Binary_Meta_Language
 //! [Matroska]: https://www.matroska.org/
@@ -75,13 +94,18 @@
 #![doc(html_root_url = "https://docs.rs/vint64/1.0.0")]
 #![warn(missing_docs, rust_2018_idioms, unused_qualifications, unsafe_code)]
 
-#[cfg(feature = "std")]
+#[cfg(any(feature = "std", test))]
 extern crate std;
 
+mod batch;
 mod error;
+pub mod io;
 pub mod signed;
 
-pub use self::error::Error;
+pub use self::{
+    batch::{decode_slice, encode_slice},
+    error::Error,
+};
 
 use core::{
     convert::{TryFrom, TryInto},
@@ -231,8 +255,9 @@ pub fn decode(input: &mut &[u8]) -> Result<u64, Error> {
 
 #[cfg(test)]
 mod tests {
-    use super::{decode, encode, signed};
-    use proptest::{num::u64::ANY, prelude::*};
+    use super::{decode, encode, signed, MAX_BYTES};
+    use proptest::{collection::vec, num::u64::ANY, prelude::*};
+    use std::vec;
 
     #[test]
     fn encode_zero() {
@@ -345,5 +370,18 @@ mod tests {
             let decoded = decode(&mut encoded.as_ref()).unwrap();
             assert_eq!(n, decoded);
         }
+
+        /// Ensure slices of signed `vint64` values successfully round-trip
+        #[test]
+        fn roundtrip_signed_slice(values in vec(-200..200i64, 0..300)) {
+            let mut encoded = vec![0u8; values.len() * MAX_BYTES];
+            let len = signed::encode_slice(&values, &mut encoded).unwrap();
+
+            let mut input = &encoded[..len];
+            let mut decoded = vec![0i64; values.len()];
+            assert_eq!(signed::decode_slice(&mut input, &mut decoded).unwrap(), values.len());
+            assert_eq!(decoded, values);
+            assert!(input.is_empty());
+        }
     }
 }
@@ -2,6 +2,9 @@
 
 use crate::{Error, VInt64};
 
+/// Number of values to zigzag encode/decode at a time when processing slices
+const BATCH_SIZE: usize = 64;
+
 /// Encode a signed integer as a zigzag-encoded `vint64`.
 #[inline]
 pub fn encode(value: i64) -> VInt64 {
@@ -20,6 +23,50 @@ pub fn encoded_len(value: i64) -> usize {
     super::encoded_len(zigzag::encode(value))
 }
 
+/// Encode a slice of signed integers as zigzag-encoded `vint64`s into the
+/// given output buffer, returning the number of bytes written.
+///
+/// See [`vint64::encode_slice`](../fn.encode_slice.html) for more information.
+pub fn encode_slice(values: &[i64], output: &mut [u8]) -> Result<usize, Error> {
+    let mut buffer = [0u64; BATCH_SIZE];
+    let mut pos = 0;
+
+    for chunk in values.chunks(BATCH_SIZE) {
+        for (encoded, &value) in buffer.iter_mut().zip(chunk) {
+            *encoded = zigzag::encode(value);
+        }
+
+        pos += super::encode_slice(&buffer[..chunk.len()], &mut output[pos..])?;
+    }
+
+    Ok(pos)
+}
+
+/// Decode a run of zigzag-encoded `vint64`s from the input into the given
+/// output slice, returning the number of values decoded.
+///
+/// See [`vint64::decode_slice`](../fn.decode_slice.html) for more information.
+pub fn decode_slice(input: &mut &[u8], output: &mut [i64]) -> Result<usize, Error> {
+    let mut buffer = [0u64; BATCH_SIZE];
+    let mut pos = 0;
+
+    for chunk in output.chunks_mut(BATCH_SIZE) {
+        let n = super::decode_slice(input, &mut buffer[..chunk.len()])?;
+
+        for (value, &encoded) in chunk.iter_mut().zip(&buffer[..n]) {
+            *value = zigzag::decode(encoded);
+        }
+
+        pos += n;
+
+        if n < chunk.len() {
+            break;
+        }
+    }
+
+    Ok(pos)
+}
+
 /// Zigzag encoding for signed integers.
 ///
 /// This module contains the raw zigzag encoding algorithm.